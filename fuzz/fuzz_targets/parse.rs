@@ -0,0 +1,7 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate email_format;
+
+fuzz_target!(|data: &[u8]| {
+    email_format::fuzz_parse(data);
+});
@@ -0,0 +1,171 @@
+use std::mem;
+
+use ::{Email, TryFrom};
+use rfc5322::{Message, Fields, Field, Body};
+use rfc5322::error::ParseError;
+use rfc5322::headers::{From, OrigDate, Sender, ReplyTo, To, Cc, Bcc, MessageId,
+                       InReplyTo, References, Subject, Comments, Keywords,
+                       OptionalField};
+
+// Builder methods that set (or replace) a field which may appear only once
+macro_rules! set_field {
+    ($name:ident, $typ:ident, $doc:expr) => {
+        #[doc=$doc]
+        pub fn $name<T>(mut self, value: T) -> EmailBuilder
+            where $typ: TryFrom<T, Error=ParseError>
+        {
+            match TryFrom::try_from(value) {
+                Ok(v) => self.set(Field::$typ(v)),
+                Err(e) => self.fail(e),
+            }
+            self
+        }
+    };
+}
+
+// Builder methods that add a field which may appear any number of times
+macro_rules! add_field {
+    ($name:ident, $typ:ident, $doc:expr) => {
+        #[doc=$doc]
+        pub fn $name<T>(mut self, value: T) -> EmailBuilder
+            where $typ: TryFrom<T, Error=ParseError>
+        {
+            match TryFrom::try_from(value) {
+                Ok(v) => self.fields.push(Field::$typ(v)),
+                Err(e) => self.fail(e),
+            }
+            self
+        }
+    };
+}
+
+/// Builds an `Email` with chained calls, instead of `Email::new()` followed
+/// by a series of setters that each return a `Result`. The first error
+/// encountered is kept and returned from `build()`.
+///
+/// ```
+/// use email_format::EmailBuilder;
+///
+/// let email = EmailBuilder::new()
+///     .from("myself@mydomain.com")
+///     .date("Wed, 5 Jan 2015 15:13:05 +1300")
+///     .to("You <you@yourdomain.com>")
+///     .subject("Hello Friend")
+///     .body("Good to hear from you.")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct EmailBuilder {
+    date: Option<OrigDate>,
+    from: Option<From>,
+    fields: Vec<Field>,
+    body: Option<Body>,
+    error: Option<ParseError>,
+}
+
+impl EmailBuilder {
+    /// Start building an email. `from()` and `date()` must be called before
+    /// `build()`, as those fields are required.
+    pub fn new() -> EmailBuilder {
+        EmailBuilder {
+            date: None,
+            from: None,
+            fields: Vec::new(),
+            body: None,
+            error: None,
+        }
+    }
+
+    /// Set the `Date` field
+    pub fn date<D>(mut self, date: D) -> EmailBuilder
+        where OrigDate: TryFrom<D, Error=ParseError>
+    {
+        match TryFrom::try_from(date) {
+            Ok(d) => self.date = Some(d),
+            Err(e) => self.fail(e),
+        }
+        self
+    }
+
+    /// Set the `From` field
+    pub fn from<F>(mut self, from: F) -> EmailBuilder
+        where From: TryFrom<F, Error=ParseError>
+    {
+        match TryFrom::try_from(from) {
+            Ok(f) => self.from = Some(f),
+            Err(e) => self.fail(e),
+        }
+        self
+    }
+
+    set_field!(sender, Sender, "Set the `Sender` field");
+    set_field!(reply_to, ReplyTo, "Set the `Reply-To` field");
+    set_field!(to, To, "Set the `To` field");
+    set_field!(cc, Cc, "Set the `Cc` field");
+    set_field!(bcc, Bcc, "Set the `Bcc` field");
+    set_field!(message_id, MessageId, "Set the `Message-ID` field");
+    set_field!(in_reply_to, InReplyTo, "Set the `In-Reply-To` field");
+    set_field!(references, References, "Set the `References` field");
+    set_field!(subject, Subject, "Set the `Subject` field");
+    add_field!(comments, Comments, "Add a `Comments` field");
+    add_field!(keywords, Keywords, "Add a `Keywords` field");
+    add_field!(optional_field, OptionalField, "Add an optional field");
+
+    /// Set the body
+    pub fn body<B>(mut self, body: B) -> EmailBuilder
+        where Body: TryFrom<B, Error=ParseError>
+    {
+        match TryFrom::try_from(body) {
+            Ok(b) => self.body = Some(b),
+            Err(e) => self.fail(e),
+        }
+        self
+    }
+
+    /// Build the email, or return the first error encountered while building
+    /// it. It is an error if `from()` or `date()` was never called.
+    pub fn build(self) -> Result<Email, ParseError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let date = match self.date {
+            Some(d) => d,
+            None => return Err(ParseError::NotFound("Date")),
+        };
+        let from = match self.from {
+            Some(f) => f,
+            None => return Err(ParseError::NotFound("From")),
+        };
+        let mut fields = vec![Field::OrigDate(date), Field::From(from)];
+        fields.extend(self.fields);
+        Ok(Email {
+            message: Message {
+                fields: Fields {
+                    trace_blocks: vec![],
+                    fields: fields,
+                },
+                body: self.body,
+            },
+            raw: None,
+            max_recipients: None,
+        })
+    }
+
+    // Replace a field of the same kind, or add it
+    fn set(&mut self, field: Field) {
+        for existing in self.fields.iter_mut() {
+            if mem::discriminant(existing) == mem::discriminant(&field) {
+                *existing = field;
+                return;
+            }
+        }
+        self.fields.push(field);
+    }
+
+    fn fail(&mut self, e: ParseError) {
+        if self.error.is_none() {
+            self.error = Some(e);
+        }
+    }
+}
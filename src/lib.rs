@@ -76,6 +76,8 @@
 //!  assert_eq!(remainder.len(), 0);
 //! ```
 //!
+//! `Email::parse_complete` instead fails if any trailing bytes remain.
+//!
 //! ## Usage with lettre and/or mailstrom
 //!
 //! If compiled with the `lettre` feature, you can generate a `SendableEmail`
@@ -84,6 +86,14 @@
 //! ```ignore
 //! let sendable_email = email.as_sendable_email().unwrap();
 //! ```
+//!
+//! With lettre 0.10 or later, compile with the `lettre010` feature instead
+//! and send the raw message with its envelope:
+//!
+//! ```ignore
+//! let sendable = email.as_lettre_sendable().unwrap();
+//! transport.send_raw(&sendable.envelope, &sendable.message).unwrap();
+//! ```
 
 extern crate buf_read_ext;
 
@@ -91,8 +101,12 @@ extern crate buf_read_ext;
 extern crate time;
 #[cfg(feature="chrono")]
 extern crate chrono;
+#[cfg(feature="encoding")]
+extern crate encoding_rs;
 #[cfg(feature="lettre")]
 extern crate lettre;
+#[cfg(feature="lettre010")]
+extern crate lettre010;
 
 #[cfg(test)]
 mod tests;
@@ -101,17 +115,38 @@ mod tests;
 /// an `Email`.
 pub mod rfc5322;
 
-use std::io::Write;
+mod builder;
+pub use builder::EmailBuilder;
+
+use std::io::{Write, Read, BufReader};
 use std::io::Error as IoError;
+use std::fs::File;
+use std::path::Path;
 use std::fmt;
+use std::mem;
+use std::str::FromStr;
+use std::hash::{Hash, Hasher};
+use std::collections::HashSet;
+use buf_read_ext::BufReadExt;
 
-use rfc5322::{Message, Fields, Field};
-use rfc5322::{Parsable, Streamable};
-use rfc5322::error::ParseError;
-use rfc5322::Body;
+use rfc5322::{Message, Fields, Field, Trace, TraceBlock, OptTraceBlock};
+use rfc5322::{Parsable, Streamable, CountingSink, fold};
+use rfc5322::error::{ParseError, ValidationError};
+use rfc5322::{Body, parse_body};
+use rfc5322::encoding;
+use rfc5322::encoded_word;
+use rfc5322::idna;
+use rfc5322::dkim::{self, Canonicalization};
+use rfc5322::multipart::{self, Multipart, RawPart};
+use rfc5322::limits::{self, ParseLimits};
+use rfc5322::authres::AuthenticationResults;
+use rfc5322::types::{Mechanism, Address, AddressList, Mailbox, MailboxList, MsgId, is_wsp};
+use rfc5322::email_address::{EmailAddress, mailbox_from_parts};
 use rfc5322::headers::{From, OrigDate, Sender, ReplyTo, To, Cc, Bcc, MessageId,
                            InReplyTo, References, Subject, Comments, Keywords,
-                           OptionalField};
+                           OptionalField, Return, Received, ContentType,
+                           ContentDisposition, ContentTransferEncoding, MimeVersion,
+                           ContentLanguage};
 
 /// Attempt to construct `Self` via a conversion (borrowed from rust `std`)
 ///
@@ -138,6 +173,18 @@ impl<T> TryFrom<T> for T {
 #[derive(Debug, Clone)]
 pub struct Email {
     message: Message,
+    raw: Option<RawHeader>,
+    max_recipients: Option<usize>,
+}
+
+// The bytes each trace block, field and the body was parsed from, kept by
+// `Email::parse_verbatim()`. Each streams as these bytes for as long as an
+// equal one remains in the email.
+#[derive(Debug, Clone)]
+struct RawHeader {
+    trace_blocks: Vec<(TraceBlock, Vec<u8>)>,
+    fields: Vec<(Field, Vec<u8>)>,
+    body: Option<(Body, Vec<u8>)>,
 }
 
 impl Email {
@@ -155,7 +202,30 @@ impl Email {
                         Field::From(TryFrom::try_from(from)?) ],
                 },
                 body: None,
-            }
+            },
+            raw: None,
+            max_recipients: None,
+        })
+    }
+
+    /// Create a new email structure with only a `From` field, for when the
+    /// `Date` is not known until just before the email is sent. Call
+    /// `set_date()` (or `set_date_now()` with the `chrono` or `time` feature)
+    /// before then: an email without a `Date` still streams, but is not valid
+    /// RFC 5322, and `validate()` reports it as `ValidationError::MissingDate`.
+    pub fn new_without_date<F>(from: F) -> Result<Email, ParseError>
+        where From: TryFrom<F, Error=ParseError>
+    {
+        Ok(Email {
+            message: Message {
+                fields: Fields {
+                    trace_blocks: vec![],
+                    fields: vec![ Field::From(TryFrom::try_from(from)?) ],
+                },
+                body: None,
+            },
+            raw: None,
+            max_recipients: None,
         })
     }
 
@@ -170,16 +240,55 @@ impl Email {
                 return Ok(())
             }
         }
-        unreachable!()
+        // A parsed email, or one from `new_without_date()`, may lack a `Date`
+        self.message.fields.fields.insert(0, Field::OrigDate(value));
+        Ok(())
+    }
+    /// Replace the `Date` field in the email with the current time, in the
+    /// local zone
+    #[cfg(feature="chrono")]
+    pub fn set_date_now(&mut self) {
+        // A formatted chrono::DateTime always parses
+        self.set_date(&::chrono::Local::now()).unwrap();
+    }
+    /// Replace the `Date` field in the email with the current time, in the
+    /// local zone
+    #[cfg(all(feature="time", not(feature="chrono")))]
+    pub fn set_date_now(&mut self) {
+        // A formatted time::Tm always parses
+        self.set_date(&::time::now()).unwrap();
     }
-    /// Fetch the `Date` field from the email
-    pub fn get_date(&self) -> OrigDate {
+    /// Fetch the `Date` field from the email. This is always present in an
+    /// email built with `Email::new()`, but a parsed email may lack it.
+    pub fn get_date(&self) -> Option<OrigDate> {
+        self.date_ref().cloned()
+    }
+    /// Borrow the `Date` field from the email
+    pub fn date_ref(&self) -> Option<&OrigDate> {
         for field in self.message.fields.fields.iter() {
-            if let Field::OrigDate(ref d) = *field {
-                return d.clone();
+            if let Field::OrigDate(ref x) = *field {
+                return Some(x);
             }
         }
-        unreachable!()
+        None
+    }
+    /// Fetch the `Date` field from the email as a `chrono::DateTime`
+    #[cfg(feature="chrono")]
+    pub fn get_date_chrono(&self) ->
+        Result<::chrono::DateTime<::chrono::FixedOffset>, ParseError>
+    {
+        match self.date_ref() {
+            Some(date) => date.to_chrono(),
+            None => Err(ParseError::NotFound("Date")),
+        }
+    }
+    /// Fetch the `Date` field from the email as a `time::Tm`
+    #[cfg(feature="time")]
+    pub fn get_date_tm(&self) -> Result<::time::Tm, ParseError> {
+        match self.date_ref() {
+            Some(date) => date.to_tm(),
+            None => Err(ParseError::NotFound("Date")),
+        }
     }
 
     /// Replace the `From` field in the email
@@ -193,16 +302,62 @@ impl Email {
                 return Ok(());
             }
         }
-        unreachable!()
+        // A parsed email may lack a `From` field
+        let index = match self.message.fields.fields.first() {
+            Some(&Field::OrigDate(_)) => 1,
+            _ => 0,
+        };
+        self.message.fields.fields.insert(index, Field::From(value));
+        Ok(())
+    }
+    /// Replace the `From` field in the email with a single mailbox built from
+    /// an optional display name and an address such as `john@example.com`.
+    /// The display name is quoted if it contains specials (e.g. `Doe, John`)
+    /// and RFC 2047 encoded if it is not ASCII.
+    pub fn set_from_parts(&mut self, display: Option<&str>, addr: &str)
+                          -> Result<(), ParseError>
+    {
+        let mailbox = mailbox_from_parts(display, addr)?;
+        self.set_from(From(MailboxList(vec![mailbox])))
+    }
+    /// Replace the `From` field with the mailboxes of `authors` and set the
+    /// `Sender` field to `sender`, which RFC 5322 section 3.6.2 requires when
+    /// `From` has more than one mailbox. Fails without changing the email if
+    /// `authors` is empty or an address cannot be written as a mailbox.
+    pub fn set_authors<I>(&mut self, authors: I, sender: EmailAddress) -> Result<(), ParseError>
+        where I: IntoIterator<Item=EmailAddress>
+    {
+        let from: From = TryFrom::try_from(authors.into_iter().collect::<Vec<EmailAddress>>())?;
+        let sender = Sender(sender.to_mailbox()?);
+        self.set_from(from)?;
+        self.set_sender(sender)
+    }
+    /// Replace the `From` field in the email, RFC 2047 encoding any non-ASCII
+    /// display names (e.g. `"Hans Müller <hm@example.de>"`)
+    pub fn set_from_encoded(&mut self, from: &str) -> Result<(), ParseError> {
+        self.set_from(&*encoded_word::encode_display_names(from))
     }
-    /// Fetch the `From` field from the email
-    pub fn get_from(&self) -> From {
+    /// Fetch the `From` field from the email. This is always present in an
+    /// email built with `Email::new()`, but a parsed email may lack it.
+    pub fn get_from(&self) -> Option<From> {
+        self.from_ref().cloned()
+    }
+    /// Borrow the `From` field from the email
+    pub fn from_ref(&self) -> Option<&From> {
         for field in self.message.fields.fields.iter() {
-            if let Field::From(ref f) = *field {
-                return f.clone()
+            if let Field::From(ref x) = *field {
+                return Some(x);
             }
         }
-        unreachable!()
+        None
+    }
+    /// Fetch the `From` addresses as `EmailAddress`es. This is empty if there
+    /// is no `From` field.
+    pub fn from_addresses(&self) -> Vec<EmailAddress> {
+        match self.from_ref() {
+            Some(from) => EmailAddress::from_mailbox_list(&from.0),
+            None => Vec::new(),
+        }
     }
 
     /// Set or replace the `Sender` field in the email
@@ -221,13 +376,44 @@ impl Email {
     }
     /// Fetch the `Sender` field from the email
     pub fn get_sender(&self) -> Option<Sender> {
+        self.sender_ref().cloned()
+    }
+    /// Borrow the `Sender` field from the email
+    pub fn sender_ref(&self) -> Option<&Sender> {
         for field in self.message.fields.fields.iter() {
-            if let Field::Sender(ref s) = *field {
-                return Some(s.clone());
+            if let Field::Sender(ref x) = *field {
+                return Some(x);
             }
         }
         None
     }
+    /// The mailbox responsible for sending the email (RFC 5322 section
+    /// 3.6.2): the `Sender` mailbox if there is one, otherwise the first `From`
+    /// mailbox. This is `None` only for a parsed email lacking both fields.
+    pub fn effective_sender(&self) -> Option<EmailAddress> {
+        if let Some(sender) = self.sender_ref() {
+            return Some(EmailAddress::from_mailbox(&sender.0));
+        }
+        self.from_ref()
+            .and_then(|from| (from.0).0.first())
+            .map(EmailAddress::from_mailbox)
+    }
+    /// Remove the `Sender` field if `From` holds a single mailbox with the
+    /// same address, as RFC 5322 section 3.6.2 says it should then be
+    /// omitted. Returns whether it was removed.
+    pub fn normalize_sender(&mut self) -> bool {
+        let redundant = match (self.sender_ref(), self.from_ref()) {
+            (Some(sender), Some(from)) if (from.0).0.len() == 1 => {
+                EmailAddress::from_mailbox(&sender.0)
+                    .same_address(&EmailAddress::from_mailbox(&(from.0).0[0]))
+            },
+            _ => false,
+        };
+        if redundant {
+            self.clear_sender();
+        }
+        redundant
+    }
     /// Remove the `Sender` field from the email
     pub fn clear_sender(&mut self) {
         self.message.fields.fields.retain(|field| {
@@ -249,11 +435,20 @@ impl Email {
         self.message.fields.fields.push(Field::ReplyTo(value));
         Ok(())
     }
+    /// Set or replace the `Reply-To` field in the email, encoding display names
+    /// as `set_from_encoded()` does
+    pub fn set_reply_to_encoded(&mut self, reply_to: &str) -> Result<(), ParseError> {
+        self.set_reply_to(&*encoded_word::encode_display_names(reply_to))
+    }
     /// Fetch the `Reply-To` field from the email
     pub fn get_reply_to(&self) -> Option<ReplyTo> {
+        self.reply_to_ref().cloned()
+    }
+    /// Borrow the `Reply-To` field from the email
+    pub fn reply_to_ref(&self) -> Option<&ReplyTo> {
         for field in self.message.fields.fields.iter() {
-            if let Field::ReplyTo(ref rt) = *field {
-                return Some(rt.clone())
+            if let Field::ReplyTo(ref x) = *field {
+                return Some(x);
             }
         }
         None
@@ -270,6 +465,8 @@ impl Email {
         where To: TryFrom<T, Error=ParseError>
     {
         let value: To = TryFrom::try_from(to)?;
+        self.check_recipients(self.to_addresses().len(),
+                              EmailAddress::from_addresses(&value.0).len())?;
         for field in self.message.fields.fields.iter_mut() {
             if let Field::To(_) = *field {
                 *field = Field::To(value);
@@ -279,15 +476,80 @@ impl Email {
         self.message.fields.fields.push(Field::To(value));
         Ok(())
     }
+    /// Set or replace the `To` field in the email, encoding display names
+    /// as `set_from_encoded()` does
+    pub fn set_to_encoded(&mut self, to: &str) -> Result<(), ParseError> {
+        self.set_to(&*encoded_word::encode_display_names(to))
+    }
+    /// Append an address to the `To` field, adding the field if absent
+    pub fn add_to<A>(&mut self, addr: A) -> Result<(), ParseError>
+        where Address: TryFrom<A, Error=ParseError>
+    {
+        let value: Address = TryFrom::try_from(addr)?;
+        self.check_recipients(0, EmailAddress::from_address(&value).len())?;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::To(ref mut x) = *field {
+                (x.0).0.push(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::To(To(AddressList(vec![value]))));
+        Ok(())
+    }
+    /// Set or replace the `To` field in the email, converting internationalized
+    /// domains such as `user@例え.jp` into their ASCII-compatible form and
+    /// encoding display names as `set_from_encoded()` does
+    pub fn set_to_idna(&mut self, to: &str) -> Result<(), ParseError> {
+        let to = idna::addresses_to_ascii(to)?;
+        self.set_to_encoded(&*to)
+    }
     /// Fetch the `To` field from the email
     pub fn get_to(&self) -> Option<To> {
+        self.to_ref().cloned()
+    }
+    /// Borrow the `To` field from the email
+    pub fn to_ref(&self) -> Option<&To> {
         for field in self.message.fields.fields.iter() {
-            if let Field::To(ref t) = *field {
-                return Some(t.clone())
+            if let Field::To(ref x) = *field {
+                return Some(x);
             }
         }
         None
     }
+    /// Fetch the `To` addresses as `EmailAddress`es, expanding any groups.
+    /// This is empty if there is no `To` field.
+    pub fn to_addresses(&self) -> Vec<EmailAddress> {
+        match self.to_ref() {
+            Some(to) => EmailAddress::from_addresses(&to.0),
+            None => Vec::new(),
+        }
+    }
+    /// Fetch the `To` mailboxes, expanding any groups into their members.
+    /// This is empty if there is no `To` field.
+    pub fn to_mailboxes(&self) -> Vec<Mailbox> {
+        match self.to_ref() {
+            Some(to) => to.0.mailboxes(),
+            None => Vec::new(),
+        }
+    }
+    /// Remove the mailboxes matching `addr` from the `To` field, comparing the
+    /// domain case-insensitively. The field is removed if no addresses remain.
+    /// Returns whether anything was removed.
+    pub fn remove_to(&mut self, addr: &EmailAddress) -> bool {
+        let mut removed = false;
+        let mut now_empty = false;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::To(ref mut x) = *field {
+                removed = remove_address(&mut x.0, addr);
+                now_empty = (x.0).0.is_empty();
+                break;
+            }
+        }
+        if now_empty {
+            self.clear_to();
+        }
+        removed
+    }
     /// Remove the `To` field from the email
     pub fn clear_to(&mut self) {
         self.message.fields.fields.retain(|field| {
@@ -300,6 +562,8 @@ impl Email {
         where Cc: TryFrom<C, Error=ParseError>
     {
         let value: Cc = TryFrom::try_from(cc)?;
+        self.check_recipients(self.cc_addresses().len(),
+                              EmailAddress::from_addresses(&value.0).len())?;
         for field in self.message.fields.fields.iter_mut() {
             if let Field::Cc(_) = *field {
                 *field = Field::Cc(value);
@@ -309,15 +573,65 @@ impl Email {
         self.message.fields.fields.push(Field::Cc(value));
         Ok(())
     }
+    /// Set or replace the `Cc` field in the email, encoding display names
+    /// as `set_from_encoded()` does
+    pub fn set_cc_encoded(&mut self, cc: &str) -> Result<(), ParseError> {
+        self.set_cc(&*encoded_word::encode_display_names(cc))
+    }
+    /// Append an address to the `Cc` field, adding the field if absent
+    pub fn add_cc<A>(&mut self, addr: A) -> Result<(), ParseError>
+        where Address: TryFrom<A, Error=ParseError>
+    {
+        let value: Address = TryFrom::try_from(addr)?;
+        self.check_recipients(0, EmailAddress::from_address(&value).len())?;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::Cc(ref mut x) = *field {
+                (x.0).0.push(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::Cc(Cc(AddressList(vec![value]))));
+        Ok(())
+    }
     /// Fetch the `Cc` field from the email
     pub fn get_cc(&self) -> Option<Cc> {
+        self.cc_ref().cloned()
+    }
+    /// Borrow the `Cc` field from the email
+    pub fn cc_ref(&self) -> Option<&Cc> {
         for field in self.message.fields.fields.iter() {
-            if let Field::Cc(ref cc) = *field {
-                return Some(cc.clone())
+            if let Field::Cc(ref x) = *field {
+                return Some(x);
             }
         }
         None
     }
+    /// Fetch the `Cc` addresses as `EmailAddress`es, expanding any groups.
+    /// This is empty if there is no `Cc` field.
+    pub fn cc_addresses(&self) -> Vec<EmailAddress> {
+        match self.cc_ref() {
+            Some(cc) => EmailAddress::from_addresses(&cc.0),
+            None => Vec::new(),
+        }
+    }
+    /// Remove the mailboxes matching `addr` from the `Cc` field, comparing the
+    /// domain case-insensitively. The field is removed if no addresses remain.
+    /// Returns whether anything was removed.
+    pub fn remove_cc(&mut self, addr: &EmailAddress) -> bool {
+        let mut removed = false;
+        let mut now_empty = false;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::Cc(ref mut x) = *field {
+                removed = remove_address(&mut x.0, addr);
+                now_empty = (x.0).0.is_empty();
+                break;
+            }
+        }
+        if now_empty {
+            self.clear_cc();
+        }
+        removed
+    }
     /// Remove the `Cc` field from the email
     pub fn clear_cc(&mut self) {
         self.message.fields.fields.retain(|field| {
@@ -330,6 +644,7 @@ impl Email {
         where Bcc: TryFrom<B, Error=ParseError>
     {
         let value: Bcc = TryFrom::try_from(bcc)?;
+        self.check_recipients(bcc_count(self.bcc_ref()), bcc_count(Some(&value)))?;
         for field in self.message.fields.fields.iter_mut() {
             if let Field::Bcc(_) = *field {
                 *field = Field::Bcc(value);
@@ -339,21 +654,99 @@ impl Email {
         self.message.fields.fields.push(Field::Bcc(value));
         Ok(())
     }
+    /// Append an address to the `Bcc` field, adding the field if absent or
+    /// replacing it if it holds no addresses
+    pub fn add_bcc<A>(&mut self, addr: A) -> Result<(), ParseError>
+        where Address: TryFrom<A, Error=ParseError>
+    {
+        let value: Address = TryFrom::try_from(addr)?;
+        self.check_recipients(0, EmailAddress::from_address(&value).len())?;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::Bcc(ref mut x) = *field {
+                if let Bcc::AddressList(ref mut al) = *x {
+                    al.0.push(value);
+                    return Ok(());
+                }
+                *x = Bcc::AddressList(AddressList(vec![value]));
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::Bcc(Bcc::AddressList(AddressList(vec![value]))));
+        Ok(())
+    }
     /// Fetch the `Bcc` field from the email
     pub fn get_bcc(&self) -> Option<Bcc> {
+        self.bcc_ref().cloned()
+    }
+    /// Borrow the `Bcc` field from the email
+    pub fn bcc_ref(&self) -> Option<&Bcc> {
         for field in self.message.fields.fields.iter() {
-            if let Field::Bcc(ref b) = *field {
-                return Some(b.clone())
+            if let Field::Bcc(ref x) = *field {
+                return Some(x);
             }
         }
         None
     }
+    /// Remove the mailboxes matching `addr` from the `Bcc` field, comparing the
+    /// domain case-insensitively. The field is removed if no addresses remain.
+    /// Returns whether anything was removed.
+    pub fn remove_bcc(&mut self, addr: &EmailAddress) -> bool {
+        let mut removed = false;
+        let mut now_empty = false;
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::Bcc(Bcc::AddressList(ref mut al)) = *field {
+                removed = remove_address(al, addr);
+                now_empty = al.0.is_empty();
+                break;
+            }
+        }
+        if now_empty {
+            self.clear_bcc();
+        }
+        removed
+    }
     /// Remove the `Bcc` field from the email
     pub fn clear_bcc(&mut self) {
         self.message.fields.fields.retain(|field| {
             if let Field::Bcc(_) = *field { false } else { true }
         });
     }
+    /// Every recipient across the `To`, `Cc` and `Bcc` fields, with groups
+    /// expanded, such as for an SMTP envelope. Duplicates are removed, comparing
+    /// the domain case-insensitively but the local part case-sensitively.
+    pub fn all_recipients(&self) -> Vec<EmailAddress> {
+        let mut recipients = self.to_addresses();
+        recipients.extend(self.cc_addresses());
+        if let Some(&Bcc::AddressList(ref al)) = self.bcc_ref() {
+            recipients.extend(EmailAddress::from_addresses(al));
+        }
+        let mut seen: HashSet<(String, String)> = HashSet::new();
+        recipients.retain(|a| seen.insert((a.local_part.clone(), a.domain.to_lowercase())));
+        recipients
+    }
+    /// The number of mailboxes across the `To`, `Cc` and `Bcc` fields, with
+    /// groups expanded. Unlike `all_recipients()`, duplicates are counted.
+    pub fn recipient_count(&self) -> usize {
+        self.to_addresses().len() + self.cc_addresses().len() + bcc_count(self.bcc_ref())
+    }
+    /// Limit the number of recipients that the `To`, `Cc` and `Bcc` setters,
+    /// such as `add_to()` and `set_cc()`, and `merge_headers_from()` will
+    /// allow, as counted by `recipient_count()`. A change which would leave
+    /// more than `max` then fails with `ParseError::LimitExceeded`. There is
+    /// no limit by default, or if `max` is `None`.
+    pub fn set_max_recipients(&mut self, max: Option<usize>) {
+        self.max_recipients = max;
+    }
+    // Check that replacing `removed` of the recipients with `added` others
+    // would not go over the recipient limit
+    fn check_recipients(&self, removed: usize, added: usize) -> Result<(), ParseError> {
+        if let Some(max) = self.max_recipients {
+            if self.recipient_count() - removed + added > max {
+                return Err(ParseError::LimitExceeded("Recipients"));
+            }
+        }
+        Ok(())
+    }
 
     /// Set or replace the `Message-ID` field in the email
     pub fn set_message_id<M>(&mut self, message_id: M) -> Result<(), ParseError>
@@ -369,11 +762,49 @@ impl Email {
         self.message.fields.fields.push(Field::MessageId(value));
         Ok(())
     }
+    /// Set or replace the `Message-ID` field in the email with the id
+    /// `<left@right>`, failing if either part is not valid on its own
+    pub fn set_message_id_parts(&mut self, left: &str, right: &str) -> Result<(), ParseError> {
+        let value = MessageId(MsgId::from_parts(left, right)?);
+        self.set_message_id(value)
+    }
+    /// Set or replace the `Message-ID` field in the email with a newly generated
+    /// unique identifier of the form `<timestamp.random@domain>`. The `domain`
+    /// must be a valid `id-right`, such as `mydomain.com` or `[192.0.2.1]`.
+    pub fn generate_message_id(&mut self, domain: &str) -> Result<(), ParseError> {
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::{SystemTime, UNIX_EPOCH};
+        use rfc5322::types::IdRight;
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let (_, rem) = IdRight::parse(domain.as_bytes())?;
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("Id-right", domain.len() - rem.len()));
+        }
+        let micros = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() * 1_000_000 + d.subsec_micros() as u64,
+            Err(_) => 0,
+        };
+        // Each RandomState is randomly keyed, so this hash is our random part.
+        // The counter and process id keep it unique even if the keys repeat.
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u32(::std::process::id());
+        let id = format!("<{:x}.{:016x}@{}>", micros, hasher.finish(), domain);
+        self.set_message_id(&*id)
+    }
     /// Fetch the `Message-ID` field from the email
     pub fn get_message_id(&self) -> Option<MessageId> {
+        self.message_id_ref().cloned()
+    }
+    /// Borrow the `Message-ID` field from the email
+    pub fn message_id_ref(&self) -> Option<&MessageId> {
         for field in self.message.fields.fields.iter() {
-            if let Field::MessageId(ref m) = *field {
-                return Some(m.clone())
+            if let Field::MessageId(ref x) = *field {
+                return Some(x);
             }
         }
         None
@@ -401,9 +832,13 @@ impl Email {
     }
     /// Fetch the `In-Reply-To` field from the email
     pub fn get_in_reply_to(&self) -> Option<InReplyTo> {
+        self.in_reply_to_ref().cloned()
+    }
+    /// Borrow the `In-Reply-To` field from the email
+    pub fn in_reply_to_ref(&self) -> Option<&InReplyTo> {
         for field in self.message.fields.fields.iter() {
             if let Field::InReplyTo(ref x) = *field {
-                return Some(x.clone())
+                return Some(x);
             }
         }
         None
@@ -431,9 +866,13 @@ impl Email {
     }
     /// Fetch the `References` field from the email
     pub fn get_references(&self) -> Option<References> {
+        self.references_ref().cloned()
+    }
+    /// Borrow the `References` field from the email
+    pub fn references_ref(&self) -> Option<&References> {
         for field in self.message.fields.fields.iter() {
             if let Field::References(ref x) = *field {
-                return Some(x.clone())
+                return Some(x);
             }
         }
         None
@@ -459,15 +898,50 @@ impl Email {
         self.message.fields.fields.push(Field::Subject(value));
         Ok(())
     }
+    /// Set or replace the `Subject` field in the email, dropping any whitespace
+    /// surrounding the value so that `" Hello "` streams as `Subject:Hello`
+    pub fn set_subject_trimmed<S>(&mut self, subject: S) -> Result<(), ParseError>
+        where Subject: TryFrom<S, Error=ParseError>
+    {
+        let value: Subject = TryFrom::try_from(subject)?;
+        self.set_subject::<Subject>(value.trimmed())
+    }
     /// Fetch the `Subject` field from the email
     pub fn get_subject(&self) -> Option<Subject> {
+        self.subject_ref().cloned()
+    }
+    /// Borrow the `Subject` field from the email
+    pub fn subject_ref(&self) -> Option<&Subject> {
         for field in self.message.fields.fields.iter() {
             if let Field::Subject(ref x) = *field {
-                return Some(x.clone())
+                return Some(x);
             }
         }
         None
     }
+    /// Sets the `Subject` field from arbitrary text. Text containing non-ASCII
+    /// characters is written as a sequence of RFC 2047 UTF-8 encoded-words;
+    /// plain ASCII text is set as-is.
+    pub fn set_subject_encoded(&mut self, subject: &str) -> Result<(), ParseError> {
+        if subject.is_ascii() {
+            self.set_subject(subject)
+        } else {
+            self.set_subject(&*encoded_word::encode(subject))
+        }
+    }
+    /// Fetch the `Subject` field from the email as text, decoding any RFC 2047
+    /// encoded-words.
+    pub fn get_subject_decoded(&self) -> Option<String> {
+        let subject = self.subject_ref()?;
+        let mut raw: Vec<u8> = Vec::new();
+        for part in (subject.0).parts.iter() {
+            if raw.len() > 0 {
+                raw.push(b' ');
+            }
+            raw.extend_from_slice(&part.0);
+        }
+        encoded_word::decode(&raw).ok()
+    }
     /// Remove the `Subject` field from the email
     pub fn clear_subject(&mut self) {
         self.message.fields.fields.retain(|field| {
@@ -494,6 +968,30 @@ impl Email {
         }
         output
     }
+    /// Fetch the `index`th `Comments` field from the email, counting from 0
+    /// in the order they appear
+    pub fn comment(&self, index: usize) -> Option<Comments> {
+        self.message.fields.fields.iter()
+            .filter_map(|field| if let Field::Comments(ref x) = *field { Some(x) } else { None })
+            .nth(index)
+            .cloned()
+    }
+    /// Remove the `index`th `Comments` field from the email, counting as
+    /// `comment()` does. Returns whether there was such a field.
+    pub fn remove_comment(&mut self, index: usize) -> bool {
+        let position = self.message.fields.fields.iter()
+            .enumerate()
+            .filter(|&(_, field)| if let Field::Comments(_) = *field { true } else { false })
+            .nth(index)
+            .map(|(i, _)| i);
+        match position {
+            Some(i) => {
+                self.message.fields.fields.remove(i);
+                true
+            },
+            None => false,
+        }
+    }
     /// Remove all `Comments` fields from the email
     pub fn clear_comments(&mut self) {
         self.message.fields.fields.retain(|field| {
@@ -527,106 +1025,1172 @@ impl Email {
         });
     }
 
-    /// Add an optional field to the email. This may be in addition to existing
-    /// optional fields.
-    pub fn add_optional_field<O>(&mut self, optional_field: O) -> Result<(), ParseError>
-        where OptionalField: TryFrom<O, Error=ParseError>
+    /// Set or replace the `Content-Type` field in the email. Any `Content-Type`
+    /// previously added as an optional field is removed.
+    pub fn set_content_type<C>(&mut self, content_type: C) -> Result<(), ParseError>
+        where ContentType: TryFrom<C, Error=ParseError>
     {
-        let value: OptionalField = TryFrom::try_from(optional_field)?;
-        self.message.fields.fields.push(Field::OptionalField(value));
+        let value: ContentType = TryFrom::try_from(content_type)?;
+        self.clear_optional_field("Content-Type");
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::ContentType(_) = *field {
+                *field = Field::ContentType(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::ContentType(value));
         Ok(())
     }
-    /// Fetch all optional fields from the email
-    pub fn get_optional_fields(&self) -> Vec<OptionalField> {
-        let mut output: Vec<OptionalField> = Vec::new();
+    /// Fetch the `Content-Type` field from the email
+    pub fn get_content_type(&self) -> Option<ContentType> {
+        self.content_type_ref().cloned()
+    }
+    /// Borrow the `Content-Type` field from the email
+    pub fn content_type_ref(&self) -> Option<&ContentType> {
         for field in self.message.fields.fields.iter() {
-            if let Field::OptionalField(ref x) = *field {
-                output.push(x.clone());
+            if let Field::ContentType(ref x) = *field {
+                return Some(x);
             }
         }
-        output
+        None
     }
-    /// Clear all optional fields from the email
-    pub fn clear_optional_fields(&mut self) {
+    /// Remove the `Content-Type` field from the email
+    pub fn clear_content_type(&mut self) {
         self.message.fields.fields.retain(|field| {
-            if let Field::OptionalField(_) = *field {
-                false
-            } else {
-                true
-            }
-        })
+            if let Field::ContentType(_) = *field { false } else { true }
+        });
+    }
+    /// Whether the `Content-Type` field has the top-level type `multipart`.
+    /// This is false if the field is absent or malformed.
+    pub fn is_multipart(&self) -> bool {
+        match self.content_type_ref() {
+            Some(ct) => ct.0.top_level.0.eq_ignore_ascii_case(b"multipart"),
+            None => false,
+        }
+    }
+    /// The `boundary` parameter of a multipart `Content-Type` field
+    pub fn boundary(&self) -> Option<String> {
+        if !self.is_multipart() {
+            return None;
+        }
+        self.content_type_ref().and_then(|ct| ct.0.parameter("boundary"))
+    }
+    /// Split the body of a multipart email into its parts (RFC 2046 section
+    /// 5.1). Fails if the email is not multipart, has no boundary, or its body
+    /// lacks the delimiters.
+    pub fn parts(&self) -> Result<Vec<RawPart>, ParseError> {
+        self.multipart().map(|m| m.parts)
+    }
+    /// Split the body of a multipart email as `parts()` does, also keeping its
+    /// preamble and epilogue
+    pub fn multipart(&self) -> Result<Multipart, ParseError> {
+        let boundary = match self.boundary() {
+            Some(b) => b,
+            None => return Err(ParseError::NotFound("Boundary")),
+        };
+        match self.message.body {
+            Some(ref body) => multipart::split(&body.0, &*boundary),
+            None => Err(ParseError::NotFound("Multipart Delimiter")),
+        }
     }
 
-    // TBD: trace
-    // TBD: resent-date
-    // TBD: resent-from
-    // TBD: resent-sender
-    // TBD: resent-to
-    // TBD: resent-cc
-    // TBD: resent-bcc
-    // TBD: resent-msg-id
+    /// Set the body to `parts` joined with a newly generated boundary which
+    /// occurs in none of them, and set the `Content-Type` field to
+    /// `content_type` with its `boundary` parameter set to match. This is the
+    /// counterpart of `parts()`. Fails if `content_type` is not multipart, or
+    /// if the parts do not make a valid body.
+    pub fn set_parts(&mut self, parts: Vec<RawPart>, content_type: ContentType)
+                     -> Result<(), ParseError>
+    {
+        use rfc5322::types::{Parameter, ParameterValue, Token};
 
-    /// Set or replace the `Body` in the email
-    pub fn set_body<B>(&mut self, body: B) -> Result<(), ParseError>
-        where Body: TryFrom<B, Error=ParseError>
+        let mut content_type = content_type;
+        if !content_type.0.top_level.0.eq_ignore_ascii_case(b"multipart") {
+            return Err(ParseError::ExpectedType("multipart Content-Type"));
+        }
+        let boundary = multipart::generate_boundary(&parts);
+        let multipart = Multipart {
+            preamble: None,
+            parts: parts,
+            padding: Vec::new(),
+            epilogue: None,
+        };
+        let body: Body = TryFrom::try_from(&*multipart.to_bytes(&*boundary))?;
+        content_type.0.parameters.retain(|p| !p.attribute.0.eq_ignore_ascii_case(b"boundary"));
+        content_type.0.parameters.push(Parameter {
+            pre_cfws: None,
+            attribute: Token(b"boundary".to_vec()),
+            value: ParameterValue::Token(Token(boundary.into_bytes())),
+            post_cfws: None,
+        });
+        self.set_content_type(content_type)?;
+        self.message.body = Some(body);
+        Ok(())
+    }
+
+    /// Set or replace the `Content-Disposition` field in the email. Any
+    /// `Content-Disposition` previously added as an optional field is removed.
+    pub fn set_content_disposition<C>(&mut self, disposition: C) -> Result<(), ParseError>
+        where ContentDisposition: TryFrom<C, Error=ParseError>
     {
-        let value: Body = TryFrom::try_from(body)?;
-        self.message.body = Some(value);
+        let value: ContentDisposition = TryFrom::try_from(disposition)?;
+        self.clear_optional_field("Content-Disposition");
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::ContentDisposition(_) = *field {
+                *field = Field::ContentDisposition(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::ContentDisposition(value));
         Ok(())
     }
-    /// Fetch the `Body` from the email
-    pub fn get_body(&self) -> Option<Body> {
-        self.message.body.clone()
+    /// Fetch the `Content-Disposition` field from the email
+    pub fn get_content_disposition(&self) -> Option<ContentDisposition> {
+        self.content_disposition_ref().cloned()
     }
-    /// Remove the `Body` from the email, leaving an empty body
-    pub fn clear_body(&mut self) {
-        self.message.body = None;
+    /// Borrow the `Content-Disposition` field from the email
+    pub fn content_disposition_ref(&self) -> Option<&ContentDisposition> {
+        for field in self.message.fields.fields.iter() {
+            if let Field::ContentDisposition(ref x) = *field {
+                return Some(x);
+            }
+        }
+        None
     }
-
-    /// Stream the email into a byte vector and return that
-    pub fn as_bytes(&self) -> Vec<u8> {
-        let mut output: Vec<u8> = Vec::new();
-        let _ = self.stream(&mut output); // no IoError ought to occur.
-        output
+    /// Remove the `Content-Disposition` field from the email
+    pub fn clear_content_disposition(&mut self) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::ContentDisposition(_) = *field { false } else { true }
+        });
     }
 
-    /// Stream the email into a byte vector, convert to a String, and
-    /// return that
-    pub fn as_string(&self) -> String {
-        let mut vec: Vec<u8> = Vec::new();
-        let _ = self.stream(&mut vec); // no IoError ought to occur.
-        unsafe {
-            // rfc5322 formatted emails fall within utf8, so this should not be
-            // able to fail
-            String::from_utf8_unchecked(vec)
+    /// Set or replace the `Content-Transfer-Encoding` field in the email. Any
+    /// `Content-Transfer-Encoding` previously added as an optional field is
+    /// removed.
+    pub fn set_content_transfer_encoding<C>(&mut self, cte: C) -> Result<(), ParseError>
+        where ContentTransferEncoding: TryFrom<C, Error=ParseError>
+    {
+        let value: ContentTransferEncoding = TryFrom::try_from(cte)?;
+        self.clear_optional_field("Content-Transfer-Encoding");
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::ContentTransferEncoding(_) = *field {
+                *field = Field::ContentTransferEncoding(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::ContentTransferEncoding(value));
+        Ok(())
+    }
+    /// Fetch the `Content-Transfer-Encoding` field from the email
+    pub fn get_content_transfer_encoding(&self) -> Option<ContentTransferEncoding> {
+        self.content_transfer_encoding_ref().cloned()
+    }
+    /// Borrow the `Content-Transfer-Encoding` field from the email
+    pub fn content_transfer_encoding_ref(&self) -> Option<&ContentTransferEncoding> {
+        for field in self.message.fields.fields.iter() {
+            if let Field::ContentTransferEncoding(ref x) = *field {
+                return Some(x);
+            }
         }
+        None
+    }
+    /// Remove the `Content-Transfer-Encoding` field from the email
+    pub fn clear_content_transfer_encoding(&mut self) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::ContentTransferEncoding(_) = *field { false } else { true }
+        });
     }
 
-    /// Create a `lettre::SendableEmail` from this Email.
-    ///
-    /// We require `&mut self` because we temporarily strip off the Bcc line
-    /// when we generate the message, and then we put it back afterwards to
-    /// leave `self` in a functionally unmodified state.
-    #[cfg(feature="lettre")]
-    pub fn as_sendable_email(&mut self) ->
-        Result<::lettre::SendableEmail, &'static str>
+    /// Set or replace the `MIME-Version` field in the email. Any
+    /// `MIME-Version` previously added as an optional field is removed.
+    /// `MimeVersion::default()` is version 1.0.
+    pub fn set_mime_version<M>(&mut self, version: M) -> Result<(), ParseError>
+        where MimeVersion: TryFrom<M, Error=ParseError>
     {
-        use lettre::{SendableEmail, EmailAddress, Envelope};
-        use rfc5322::types::Address;
-
-        let mut rfc_recipients: Vec<Address> = Vec::new();
-        if let Some(to) = self.get_to() {
-            rfc_recipients.extend((to.0).0);
-        }
-        if let Some(cc) = self.get_cc() {
-            rfc_recipients.extend((cc.0).0);
+        let value: MimeVersion = TryFrom::try_from(version)?;
+        self.clear_optional_field("MIME-Version");
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::MimeVersion(_) = *field {
+                *field = Field::MimeVersion(value);
+                return Ok(());
+            }
         }
-        if let Some(bcc) = self.get_bcc() {
-            if let Bcc::AddressList(al) = bcc {
-                rfc_recipients.extend(al.0);
+        self.message.fields.fields.push(Field::MimeVersion(value));
+        Ok(())
+    }
+    /// Fetch the `MIME-Version` field from the email
+    pub fn get_mime_version(&self) -> Option<MimeVersion> {
+        self.mime_version_ref().cloned()
+    }
+    /// Borrow the `MIME-Version` field from the email
+    pub fn mime_version_ref(&self) -> Option<&MimeVersion> {
+        for field in self.message.fields.fields.iter() {
+            if let Field::MimeVersion(ref x) = *field {
+                return Some(x);
             }
         }
-
+        None
+    }
+    /// Remove the `MIME-Version` field from the email
+    pub fn clear_mime_version(&mut self) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::MimeVersion(_) = *field { false } else { true }
+        });
+    }
+
+    /// Set or replace the `Content-Language` field in the email, such as with
+    /// `"en-US, fr"`. Any `Content-Language` previously added as an optional
+    /// field is removed.
+    pub fn set_content_language<C>(&mut self, language: C) -> Result<(), ParseError>
+        where ContentLanguage: TryFrom<C, Error=ParseError>
+    {
+        let value: ContentLanguage = TryFrom::try_from(language)?;
+        self.clear_optional_field("Content-Language");
+        for field in self.message.fields.fields.iter_mut() {
+            if let Field::ContentLanguage(_) = *field {
+                *field = Field::ContentLanguage(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::ContentLanguage(value));
+        Ok(())
+    }
+    /// Fetch the language tags of the `Content-Language` field from the
+    /// email, which are empty if there is no such field
+    pub fn get_content_language(&self) -> Vec<String> {
+        self.content_language_ref().map(|x| x.tags()).unwrap_or_default()
+    }
+    /// Borrow the `Content-Language` field from the email
+    pub fn content_language_ref(&self) -> Option<&ContentLanguage> {
+        for field in self.message.fields.fields.iter() {
+            if let Field::ContentLanguage(ref x) = *field {
+                return Some(x);
+            }
+        }
+        None
+    }
+    /// Remove the `Content-Language` field from the email
+    pub fn clear_content_language(&mut self) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::ContentLanguage(_) = *field { false } else { true }
+        });
+    }
+
+    /// Add an optional field to the email. This may be in addition to existing
+    /// optional fields.
+    pub fn add_optional_field<O>(&mut self, optional_field: O) -> Result<(), ParseError>
+        where OptionalField: TryFrom<O, Error=ParseError>
+    {
+        let value: OptionalField = TryFrom::try_from(optional_field)?;
+        self.message.fields.fields.push(Field::OptionalField(value));
+        Ok(())
+    }
+    /// Set or replace an optional field in the email. An existing optional
+    /// field with the same name (compared case-insensitively) is replaced in
+    /// place, and any further fields of that name are removed.
+    pub fn set_optional_field<O>(&mut self, optional_field: O) -> Result<(), ParseError>
+        where OptionalField: TryFrom<O, Error=ParseError>
+    {
+        let value: OptionalField = TryFrom::try_from(optional_field)?;
+        let name = format!("{}", value.name);
+        let mut found: bool = false;
+        self.message.fields.fields.retain(|field| {
+            if let Field::OptionalField(ref x) = *field {
+                if x.name.eq_ignore_case(&*name) {
+                    if found { return false; }
+                    found = true;
+                }
+            }
+            true
+        });
+        for field in self.message.fields.fields.iter_mut() {
+            let matches = if let Field::OptionalField(ref x) = *field {
+                x.name.eq_ignore_case(&*name)
+            } else {
+                false
+            };
+            if matches {
+                *field = Field::OptionalField(value);
+                return Ok(());
+            }
+        }
+        self.message.fields.fields.push(Field::OptionalField(value));
+        Ok(())
+    }
+    /// Fetch all optional fields from the email
+    pub fn get_optional_fields(&self) -> Vec<OptionalField> {
+        let mut output: Vec<OptionalField> = Vec::new();
+        for field in self.message.fields.fields.iter() {
+            if let Field::OptionalField(ref x) = *field {
+                output.push(x.clone());
+            }
+        }
+        output
+    }
+    /// Fetch the value of the first optional field named `name` (compared
+    /// case-insensitively), streamed into bytes
+    pub fn get_optional_field_value(&self, name: &str) -> Option<Vec<u8>> {
+        for field in self.message.fields.fields.iter() {
+            if let Field::OptionalField(ref x) = *field {
+                if x.name.eq_ignore_case(name) {
+                    let mut output: Vec<u8> = Vec::new();
+                    let _ = x.value.stream(&mut output); // no IoError ought to occur.
+                    return Some(output);
+                }
+            }
+        }
+        None
+    }
+    /// Iterate over the optional fields named `name` (compared
+    /// case-insensitively) without cloning them, in the order they appear.
+    /// Fields belonging to trace blocks are not included.
+    pub fn optional_fields_named<'a>(&'a self, name: &'a str)
+                                     -> impl Iterator<Item=&'a OptionalField> + 'a
+    {
+        self.message.fields.fields.iter().filter_map(move |field| match *field {
+            Field::OptionalField(ref x) if x.name.eq_ignore_case(name) => Some(x),
+            _ => None,
+        })
+    }
+    /// Parse every `Authentication-Results` field (RFC 8601), including those
+    /// within trace blocks, in the order they appear, which is most recent
+    /// first. Fields which do not parse are skipped; their raw text is still
+    /// available through `get_optional_field_value()`.
+    pub fn get_authentication_results(&self) -> Vec<AuthenticationResults> {
+        let mut optional_fields: Vec<&OptionalField> = Vec::new();
+        for block in &self.message.fields.trace_blocks {
+            if let TraceBlock::Opt(ref block) = *block {
+                optional_fields.extend(block.opt_fields.iter());
+            }
+        }
+        for field in &self.message.fields.fields {
+            if let Field::OptionalField(ref x) = *field {
+                optional_fields.push(x);
+            }
+        }
+        let mut output: Vec<AuthenticationResults> = Vec::new();
+        for field in optional_fields {
+            if !field.name.eq_ignore_case("Authentication-Results") {
+                continue;
+            }
+            let mut value: Vec<u8> = Vec::new();
+            let _ = field.value.stream(&mut value); // no IoError ought to occur.
+            if let Ok((results, rem)) = AuthenticationResults::parse(&value) {
+                if rem.is_empty() {
+                    output.push(results);
+                }
+            }
+        }
+        output
+    }
+    /// Fetch the first field named `name` (compared case-insensitively)
+    /// exactly as it is written out, including the name and the terminating
+    /// CRLF. Fields belonging to trace blocks are not searched.
+    pub fn raw_header(&self, name: &str) -> Option<Vec<u8>> {
+        let position = self.message.fields.fields.iter()
+            .position(|field| field.canonical_name().eq_ignore_ascii_case(name))?;
+        self.wire_fields().ok().map(|mut fields| fields.swap_remove(position))
+    }
+    /// Check whether the email has an optional field named `name` (compared
+    /// case-insensitively)
+    pub fn has_optional_field(&self, name: &str) -> bool {
+        self.message.fields.fields.iter().any(|field| {
+            if let Field::OptionalField(ref x) = *field {
+                x.name.eq_ignore_case(name)
+            } else {
+                false
+            }
+        })
+    }
+    /// Remove all optional fields named `name` (compared case-insensitively)
+    /// from the email
+    pub fn clear_optional_field(&mut self, name: &str) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::OptionalField(ref x) = *field {
+                !x.name.eq_ignore_case(name)
+            } else {
+                true
+            }
+        })
+    }
+    /// Clear all optional fields from the email
+    pub fn clear_optional_fields(&mut self) {
+        self.message.fields.fields.retain(|field| {
+            if let Field::OptionalField(_) = *field {
+                false
+            } else {
+                true
+            }
+        })
+    }
+
+    /// Copy the header fields of `other` into this email, such as to stamp
+    /// per-message fields onto a template. `Comments` and `Keywords` fields
+    /// are always added. A field that may appear only once, or an optional
+    /// field with the same name (compared case-insensitively) as one already
+    /// present, replaces the existing ones if `overwrite` is set and is
+    /// skipped otherwise. `Content-Type`, `Content-Transfer-Encoding`,
+    /// `Content-Disposition` and `Content-Language` describe the body of
+    /// `other` rather than this email, so are never copied. Trace fields are not copied either. Fails
+    /// with `ParseError::LimitExceeded`, leaving this email unchanged, if the
+    /// merged fields would hold more recipients than `set_max_recipients()`
+    /// allows.
+    pub fn merge_headers_from(&mut self, other: &Email, overwrite: bool)
+                              -> Result<(), ParseError>
+    {
+        let before = self.message.fields.fields.clone();
+        let existing: Vec<String> = self.message.fields.fields.iter()
+            .filter_map(|field| match *field {
+                Field::OptionalField(_) => Some(field.canonical_name().to_ascii_lowercase()),
+                _ => None,
+            })
+            .collect();
+        let mut replaced: Vec<String> = Vec::new();
+        for field in &other.message.fields.fields {
+            match *field {
+                Field::ContentType(_) | Field::ContentTransferEncoding(_) |
+                Field::ContentDisposition(_) | Field::ContentLanguage(_) => continue,
+                Field::Comments(_) | Field::Keywords(_) => { },
+                Field::OptionalField(_) => {
+                    let name = field.canonical_name().to_ascii_lowercase();
+                    if existing.contains(&name) {
+                        if !overwrite {
+                            continue;
+                        }
+                        if !replaced.contains(&name) {
+                            self.clear_optional_field(&*name);
+                            replaced.push(name);
+                        }
+                    }
+                },
+                _ => {
+                    let position = self.message.fields.fields.iter()
+                        .position(|f| mem::discriminant(f) == mem::discriminant(field));
+                    if let Some(i) = position {
+                        if overwrite {
+                            self.message.fields.fields[i] = field.clone();
+                        }
+                        continue;
+                    }
+                },
+            }
+            self.message.fields.fields.push(field.clone());
+        }
+        if let Some(max) = self.max_recipients {
+            if self.recipient_count() > max {
+                self.message.fields.fields = before;
+                return Err(ParseError::LimitExceeded("Recipients"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Set or replace the `Return-Path` field in the leading trace block of
+    /// the email. A `Return-Path` may only appear in a trace together with at
+    /// least one `Received` field, so this fails if the email has no trace.
+    pub fn set_return_path<R>(&mut self, return_path: R) -> Result<(), ParseError>
+        where Return: TryFrom<R, Error=ParseError>
+    {
+        let value: Return = TryFrom::try_from(return_path)?;
+        let trace = match self.message.fields.trace_blocks.first_mut() {
+            Some(&mut TraceBlock::Resent(ref mut block)) => &mut block.trace,
+            Some(&mut TraceBlock::Opt(ref mut block)) => &mut block.trace,
+            None => return Err(ParseError::NotFound("Trace")),
+        };
+        trace.return_path = Some(value);
+        Ok(())
+    }
+    /// Fetch the `Return-Path` field from the leading trace block of the email
+    pub fn get_return_path(&self) -> Option<Return> {
+        match self.message.fields.trace_blocks.first() {
+            Some(&TraceBlock::Resent(ref block)) => block.trace.return_path.clone(),
+            Some(&TraceBlock::Opt(ref block)) => block.trace.return_path.clone(),
+            None => None,
+        }
+    }
+
+    /// Add a `Received` field to the leading trace block of the email, creating
+    /// that trace block if the email has none. The new field is placed ahead of
+    /// any existing `Received` fields, as the most recent trace comes first.
+    pub fn add_received<R>(&mut self, received: R) -> Result<(), ParseError>
+        where Received: TryFrom<R, Error=ParseError>
+    {
+        let value: Received = TryFrom::try_from(received)?;
+        match self.message.fields.trace_blocks.first_mut() {
+            Some(&mut TraceBlock::Resent(ref mut block)) => {
+                block.trace.received.insert(0, value);
+                return Ok(());
+            },
+            Some(&mut TraceBlock::Opt(ref mut block)) => {
+                block.trace.received.insert(0, value);
+                return Ok(());
+            },
+            None => { },
+        }
+        self.message.fields.trace_blocks.push(TraceBlock::Opt(OptTraceBlock {
+            trace: Trace {
+                return_path: None,
+                received: vec![value],
+            },
+            opt_fields: vec![],
+        }));
+        Ok(())
+    }
+    /// Fetch all `Received` fields from the leading trace block of the email
+    pub fn get_received(&self) -> Vec<Received> {
+        match self.message.fields.trace_blocks.first() {
+            Some(&TraceBlock::Resent(ref block)) => block.trace.received.clone(),
+            Some(&TraceBlock::Opt(ref block)) => block.trace.received.clone(),
+            None => Vec::new(),
+        }
+    }
+    /// Fetch the `Received` fields from every trace block of the email, in
+    /// the order they appear, which is most recent first
+    pub fn received_headers(&self) -> Vec<Received> {
+        let mut output: Vec<Received> = Vec::new();
+        for block in &self.message.fields.trace_blocks {
+            match *block {
+                TraceBlock::Resent(ref block) => output.extend(block.trace.received.iter().cloned()),
+                TraceBlock::Opt(ref block) => output.extend(block.trace.received.iter().cloned()),
+            }
+        }
+        output
+    }
+
+    // TBD: resent-date
+    // TBD: resent-from
+    // TBD: resent-sender
+    // TBD: resent-to
+    // TBD: resent-cc
+    // TBD: resent-bcc
+    // TBD: resent-msg-id
+
+    /// Set or replace the `Body` in the email
+    pub fn set_body<B>(&mut self, body: B) -> Result<(), ParseError>
+        where Body: TryFrom<B, Error=ParseError>
+    {
+        let value: Body = TryFrom::try_from(body)?;
+        self.message.body = Some(value);
+        Ok(())
+    }
+    /// Set or replace the `Body` in the email with `text`, first converting
+    /// each lone LF or CR into CRLF. Fails if a line is then longer than 998
+    /// octets, or if `text` contains characters not allowed in a body.
+    pub fn set_body_text(&mut self, text: &str) -> Result<(), ParseError> {
+        self.set_body(&*to_crlf(text))
+    }
+    /// Set or replace the `Body` in the email with plain `text`, and set the
+    /// header fields to match: any existing `Content-Type` is replaced with
+    /// `text/plain; charset=utf-8`, and `Content-Transfer-Encoding` is set
+    /// to `7bit` if `text` can be sent as it is, or else to `base64` with
+    /// the body encoded. Lone LFs and CRs become CRLF as with
+    /// `set_body_text()`. A `MIME-Version` field is added if there is none.
+    /// Use this rather than `set_body()` to turn a multipart email into a
+    /// plain text one, as it leaves no stale boundary behind.
+    pub fn set_text_body(&mut self, text: &str) {
+        let body = to_crlf(text);
+        if self.set_body(&*body).is_ok() {
+            // These are valid values, so cannot fail
+            let _ = self.set_content_transfer_encoding(Mechanism::SevenBit);
+        } else {
+            self.set_body_base64(&body);
+        }
+        let _ = self.set_content_type("text/plain; charset=utf-8");
+        if self.mime_version_ref().is_none() {
+            let _ = self.set_mime_version(MimeVersion::default());
+        }
+    }
+    /// Set or replace the `Body` in the email with `text` as
+    /// `set_body_text()` does, first wrapping each line longer than `width`
+    /// octets at spaces. Words longer than `width` are broken. `width` is
+    /// limited to the 998 octets a body line may hold.
+    pub fn set_body_wrapped(&mut self, text: &str, width: usize) -> Result<(), ParseError> {
+        let width = ::std::cmp::max(1, ::std::cmp::min(width, 998));
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let mut wrapped = String::with_capacity(text.len() + text.len() / width);
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 { wrapped.push('\n'); }
+            wrap_line(line, width, &mut wrapped);
+        }
+        self.set_body_text(&*wrapped)
+    }
+    /// Fetch the `Body` from the email
+    pub fn get_body(&self) -> Option<Body> {
+        self.message.body.clone()
+    }
+    /// Borrow the `Body` from the email
+    pub fn body_ref(&self) -> Option<&Body> {
+        self.message.body.as_ref()
+    }
+    /// The body of the email exactly as it streams, without the header
+    /// fields or the blank line before it, or empty if there is no body.
+    /// Unlike `get_body_decoded()`, no transfer encoding is undone.
+    pub fn raw_body_bytes(&self) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        let _ = self.stream_body(&mut output); // no IoError ought to occur.
+        output
+    }
+    /// Borrow the `Body` from the email as a `&str`, or `None` if there is no
+    /// body or it is not valid UTF-8
+    pub fn body_str(&self) -> Option<&str> {
+        self.message.body.as_ref().and_then(|b| ::std::str::from_utf8(&b.0).ok())
+    }
+    /// Decode the body as `get_body_decoded()` does, then convert it from
+    /// the `charset` of the `Content-Type` field into UTF-8. Any charset
+    /// known to the `encoding_rs` crate is understood, including ISO-8859-1,
+    /// windows-1252 and UTF-8. Without a `charset` the body is taken to be
+    /// US-ASCII. Fails if the charset is unknown or the body is not valid in
+    /// it.
+    #[cfg(feature="encoding")]
+    pub fn body_decoded_to_utf8(&self) -> Result<String, ParseError> {
+        let body = self.get_body_decoded()?;
+        let charset = self.content_type_ref()
+            .and_then(|ct| ct.0.parameter("charset"))
+            .unwrap_or_else(|| "us-ascii".to_owned());
+        let encoding = match ::encoding_rs::Encoding::for_label(charset.trim().as_bytes()) {
+            Some(e) => e,
+            None => return Err(ParseError::ExpectedType("known charset")),
+        };
+        match encoding.decode_without_bom_handling_and_without_replacement(&body) {
+            Some(text) => Ok(text.into_owned()),
+            None => Err(ParseError::ExpectedType("body valid in its charset")),
+        }
+    }
+    /// Set or replace the `Body` in the email with the base64 encoding of
+    /// `raw`, wrapped at 76 columns, and set the `Content-Transfer-Encoding`
+    /// field to `base64`
+    pub fn set_body_base64(&mut self, raw: &[u8]) {
+        let encoded = encoding::base64_encode(raw);
+        let mut body: Vec<u8> = Vec::with_capacity(encoded.len() + encoded.len() / 38);
+        for (i, line) in encoded.chunks(76).enumerate() {
+            if i > 0 { body.extend_from_slice(b"\r\n"); }
+            body.extend_from_slice(line);
+        }
+        // base64 output is 7-bit and the lines are short, so it is a valid body
+        self.message.body = Some(Body(body));
+        let _ = self.set_content_transfer_encoding(Mechanism::Base64); // cannot fail
+    }
+    /// Fetch the `Body` from the email, decoded according to the
+    /// `Content-Transfer-Encoding` field if that is `base64` or
+    /// `quoted-printable`. A missing body is returned as an empty vector.
+    pub fn get_body_decoded(&self) -> Result<Vec<u8>, ParseError> {
+        let body: &[u8] = match self.message.body {
+            Some(ref b) => &b.0,
+            None => return Ok(Vec::new()),
+        };
+        match self.content_transfer_encoding_ref().map(|cte| cte.mechanism) {
+            Some(Mechanism::Base64) => encoding::base64_decode(body),
+            Some(Mechanism::QuotedPrintable) => encoding::quoted_printable_decode(body),
+            _ => Ok(body.to_vec()),
+        }
+    }
+    /// Remove the `Body` from the email, leaving an empty body
+    pub fn clear_body(&mut self) {
+        self.message.body = None;
+    }
+    /// Remove the `Body` from the email and return it, leaving the email
+    /// without one
+    pub fn take_body(&mut self) -> Option<Body> {
+        self.message.body.take()
+    }
+
+    /// Iterate over the name and streamed value of each field in the email,
+    /// in the order they appear. Fields belonging to trace blocks
+    /// (`Return-Path`, `Received`, and the fields that follow them) are not
+    /// included.
+    pub fn fields<'a>(&'a self) -> impl Iterator<Item=(String, Vec<u8>)> + 'a {
+        self.message.fields.fields.iter().map(|field| {
+            let name = field.canonical_name().to_owned();
+            let mut bytes: Vec<u8> = Vec::new();
+            let _ = field.stream(&mut bytes); // no IoError ought to occur.
+            // strip the "Name:" prefix and the trailing CRLF
+            let value = bytes[name.len() + 1 .. bytes.len() - 2].to_vec();
+            (name, value)
+        })
+    }
+
+    /// Parse an email from a `Read` source, such as an .eml file, without
+    /// first reading it all into memory. The header block is read up to the
+    /// blank line and parsed, then the body is read line by line. Unlike
+    /// `Email::parse`, input which does not parse in full is an error. As
+    /// with `Email::parse`, if the first line ends in a bare LF then so may
+    /// every line of the header.
+    pub fn parse_from_reader<R: Read>(reader: R) -> Result<Email, ParseError> {
+        let mut reader = BufReader::new(reader);
+        let mut header: Vec<u8> = Vec::new();
+        let mut lf_only: Option<bool> = None;
+        let found = loop {
+            let mut line: Vec<u8> = Vec::new();
+            let (_, found) = reader.stream_until_token(b"\n", &mut line)?;
+            let crlf = found && line.ends_with(b"\r");
+            if crlf {
+                line.pop();
+            }
+            let lf_only = *lf_only.get_or_insert(found && !crlf);
+            if found && !crlf && !lf_only {
+                // a bare LF amongst CRLFs, which Fields::parse() rejects
+                header.extend(line);
+                header.push(b'\n');
+                continue;
+            }
+            if line.len() == 0 || !found {
+                header.extend(line);
+                break found;
+            }
+            header.extend(line);
+            header.extend_from_slice(b"\r\n");
+        };
+        let (fields, rem) = limits::checked(|| Fields::parse(&header))?;
+        if rem.len() > 0 {
+            return Err(trailing_input(&header, rem));
+        }
+        let body = if found {
+            Some(Body::parse_from_reader(&mut reader)?)
+        } else {
+            None
+        };
+        Ok(Email {
+            message: Message {
+                fields: fields,
+                body: body,
+            },
+            raw: None,
+            max_recipients: None,
+        })
+    }
+
+    /// Read and parse an email from the file at `path`, as
+    /// `parse_from_reader()` does. Filesystem errors are returned as
+    /// `ParseError::Io`.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Email, ParseError> {
+        let file = File::open(path)?;
+        Email::parse_from_reader(file)
+    }
+
+    /// Stream the email into the file at `path`, creating or truncating it
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), IoError> {
+        let mut file = File::create(path)?;
+        self.stream(&mut file)?;
+        file.flush()
+    }
+
+    /// Stream the email as the content of an SMTP `DATA` command (RFC 5321
+    /// section 4.5.2): a `.` beginning any line is doubled, and the line
+    /// holding only a `.` that ends the data is appended. Returns the number
+    /// of bytes written.
+    pub fn stream_smtp<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let bytes = self.as_bytes();
+        let mut count: usize = 0;
+        let mut line_start: bool = true;
+        let mut start: usize = 0;
+        for (i, &c) in bytes.iter().enumerate() {
+            if line_start && c == b'.' {
+                w.write_all(&bytes[start..i + 1])?;
+                count += i + 1 - start;
+                start = i; // the dot is written again with what follows it
+            }
+            line_start = c == b'\n';
+        }
+        w.write_all(&bytes[start..])?;
+        count += bytes.len() - start;
+        if !bytes.ends_with(b"\r\n") {
+            w.write_all(b"\r\n")?;
+            count += 2;
+        }
+        w.write_all(b".\r\n")?;
+        count += 3;
+        Ok(count)
+    }
+
+    /// Parse an email as `Email::parse` does, but keep the bytes that each
+    /// header field was parsed from. Streaming then reproduces those bytes
+    /// exactly, preserving the casing of field names, the whitespace after
+    /// colons, comments and folding, as DKIM verification requires. Field
+    /// order is always preserved. A field which is changed, added or replaced
+    /// streams in canonical form, while the others keep their original bytes.
+    /// The body is always streamed verbatim.
+    pub fn parse_verbatim(input: &[u8]) -> Result<(Email, &[u8]), ParseError> {
+        limits::checked(|| Email::parse_verbatim_unchecked(input))
+    }
+
+    fn parse_verbatim_unchecked(input: &[u8]) -> Result<(Email, &[u8]), ParseError> {
+        let mut rem = input;
+        let tripped = limits::tripped();
+        let mut raw_trace_blocks: Vec<(TraceBlock, Vec<u8>)> = Vec::new();
+        loop {
+            let tb = TraceBlock::parse(rem);
+            limits::check_at(input, rem, tripped)?;
+            match tb {
+                Ok((tb, r)) => {
+                    raw_trace_blocks.push((tb, rem[..rem.len() - r.len()].to_vec()));
+                    rem = r;
+                },
+                Err(_) => break,
+            }
+        }
+        let mut raw_fields: Vec<(Field, Vec<u8>)> = Vec::new();
+        loop {
+            let f = Field::parse(rem);
+            limits::check_at(input, rem, tripped)?;
+            match f {
+                Ok((f, r)) => {
+                    raw_fields.push((f, rem[..rem.len() - r.len()].to_vec()));
+                    rem = r;
+                },
+                Err(_) => break,
+            }
+        }
+        let raw_body = if rem.starts_with(b"\r\n") {
+            let (body, r) = parse_body(input, &rem[2..])?;
+            let bytes = rem[2..rem.len() - r.len()].to_vec();
+            rem = r;
+            Some((body, bytes))
+        } else {
+            None
+        };
+        Ok((Email {
+            message: Message {
+                fields: Fields {
+                    trace_blocks: raw_trace_blocks.iter().map(|&(ref tb, _)| tb.clone()).collect(),
+                    fields: raw_fields.iter().map(|&(ref f, _)| f.clone()).collect(),
+                },
+                body: raw_body.as_ref().map(|&(ref body, _)| body.clone()),
+            },
+            raw: Some(RawHeader {
+                trace_blocks: raw_trace_blocks,
+                fields: raw_fields,
+                body: raw_body,
+            }),
+            max_recipients: None,
+        }, rem))
+    }
+
+    /// Parse the whole of `input` as `parse_verbatim()` does, for archiving
+    /// where the message must be kept byte for byte. `stream()` then writes
+    /// the trace blocks, fields and body which have not been changed as the
+    /// exact bytes they were parsed from, and those that have in canonical
+    /// form. Fails with `ParseError::At`, giving the offset at which parsing
+    /// stopped, if any input would not be kept.
+    pub fn parse_preserving(input: &[u8]) -> Result<Email, ParseError> {
+        let (email, rem) = Email::parse_verbatim(input)?;
+        if rem.len() > 0 {
+            return Err(trailing_input(input, rem));
+        }
+        Ok(email)
+    }
+
+    /// Parse an email as `Email::parse` does, but fail with
+    /// `ParseError::DuplicateField` (located by `ParseError::At` at the
+    /// repetition) if a field that may appear at most once (anything but
+    /// `Comments`, `Keywords` and optional fields) is repeated, as such
+    /// duplicates can be used to smuggle headers past filters.
+    pub fn parse_strict(input: &[u8]) -> Result<(Email, &[u8]), ParseError> {
+        let (email, rem) = Email::parse(input)?;
+        let fields = &email.message.fields.fields;
+        if let Some(name) = repeated_fields(fields).into_iter().next() {
+            // find the repetition by parsing the fields before it again
+            let index = fields.iter().enumerate()
+                .filter(|&(_, f)| f.canonical_name() == &*name)
+                .nth(1).map_or(0, |(i, _)| i);
+            let mut r = input;
+            while let Ok((_, r2)) = TraceBlock::parse(r) { r = r2; }
+            for _ in 0..index {
+                match Field::parse(r) {
+                    Ok((_, r2)) => r = r2,
+                    Err(_) => break,
+                }
+            }
+            return Err(ParseError::at(input, r, ParseError::DuplicateField(name)));
+        }
+        Ok((email, rem))
+    }
+
+    /// Parse an email as `Email::parse` does, but fail with `ParseError::At`
+    /// giving the offset at which parsing stopped if any input remains,
+    /// rather than returning the remainder.
+    pub fn parse_complete(input: &[u8]) -> Result<Email, ParseError> {
+        let (email, rem) = Email::parse(input)?;
+        if rem.len() > 0 {
+            return Err(trailing_input(input, rem));
+        }
+        Ok(email)
+    }
+
+    /// Parse an email as `Email::parse` does, but with the given `limits`
+    /// on comment nesting, address count and header field count in place of
+    /// the defaults. Input exceeding a limit fails with
+    /// `ParseError::LimitExceeded`.
+    pub fn parse_with_limits<'a>(input: &'a [u8], limits: &ParseLimits)
+                                 -> Result<(Email, &'a [u8]), ParseError>
+    {
+        limits::with_limits(*limits, || Email::parse(input))
+    }
+
+    /// Check the email for problems that its grammar does not catch: the
+    /// `Date` and `From` fields must be present, `Sender` is required when
+    /// `From` has more than one mailbox (RFC 5322 section 3.6.2), and fields
+    /// other than `Comments`, `Keywords` and optional fields must not be
+    /// repeated (section 3.6). All problems found are returned.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+        let mut has_date: bool = false;
+        let mut from_mailboxes: Option<usize> = None;
+        let mut has_sender: bool = false;
+        for field in self.message.fields.fields.iter() {
+            match *field {
+                Field::OrigDate(_) => has_date = true,
+                Field::From(ref from) => from_mailboxes = Some(((from.0).0).len()),
+                Field::Sender(_) => has_sender = true,
+                _ => { },
+            }
+        }
+        if !has_date {
+            errors.push(ValidationError::MissingDate);
+        }
+        match from_mailboxes {
+            None => errors.push(ValidationError::MissingFrom),
+            Some(n) if n > 1 && !has_sender => errors.push(ValidationError::MissingSender),
+            _ => { },
+        }
+        errors.extend(repeated_fields(&self.message.fields.fields).into_iter()
+                      .map(ValidationError::DuplicateField));
+        if errors.len() == 0 { Ok(()) } else { Err(errors) }
+    }
+
+    /// Make the structured header fields (such as those of addresses, dates
+    /// and message ids) as compact as they may legally be, by removing
+    /// comments and folding, reducing other whitespace to a single space,
+    /// and removing spaces beside `,` `<` `>` `:` `;` and `@` where the field
+    /// still parses without them. Quoted strings and domain literals are
+    /// kept as they are, as are the unstructured fields (`Subject`,
+    /// `Comments` and optional fields) and the trace blocks. Any bytes kept
+    /// by `Email::parse_verbatim()` are discarded.
+    pub fn minify(&mut self) {
+        self.raw = None;
+        for field in self.message.fields.fields.iter_mut() {
+            match *field {
+                Field::Subject(_) | Field::Comments(_) | Field::OptionalField(_) => continue,
+                _ => { },
+            }
+            let mut bytes: Vec<u8> = Vec::new();
+            let _ = field.stream(&mut bytes); // no IoError ought to occur.
+            let name_len = field.canonical_name().len() + 1;
+            let value = &bytes[name_len .. bytes.len() - 2];
+            for &aggressive in &[true, false] {
+                let mut minified: Vec<u8> = bytes[..name_len].to_vec();
+                minified.extend(minify_value(value, aggressive));
+                minified.extend_from_slice(b"\r\n");
+                if minified.len() >= bytes.len() {
+                    continue;
+                }
+                if let Ok((new_field, rem)) = Field::parse(&minified) {
+                    if rem.is_empty() && mem::discriminant(&new_field) == mem::discriminant(field) {
+                        *field = new_field;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Create a reply to this email, from `from` and dated `date`. It is
+    /// addressed to the `Reply-To` of this email, or else its `From`. Its
+    /// `Subject` is this subject prefixed with `Re: ` (unless already so
+    /// prefixed). If this email has a `Message-ID`, the reply is `In-Reply-To`
+    /// it and it is appended to the `References` (RFC 5322 section 3.6.4).
+    pub fn reply<F,D>(&self, from: F, date: D) -> Result<Email, ParseError>
+        where From: TryFrom<F, Error=ParseError>, OrigDate: TryFrom<D, Error=ParseError>
+    {
+        let mut reply = Email::new(from, date)?;
+        let to = match (self.reply_to_ref(), self.from_ref()) {
+            (Some(reply_to), _) => reply_to.0.clone(),
+            (None, Some(from)) => AddressList((from.0).0.iter().cloned()
+                                              .map(Address::Mailbox).collect()),
+            (None, None) => return Err(ParseError::NotFound("From")),
+        };
+        reply.set_to(To(to))?;
+
+        let subject = self.subject_ref().map(|s| s.text()).unwrap_or_default();
+        let subject = subject.trim();
+        if subject.len() == 0 {
+            reply.set_subject("Re:")?;
+        } else if subject.get(..3).map_or(false, |p| p.eq_ignore_ascii_case("re:")) {
+            reply.set_subject(subject)?;
+        } else {
+            reply.set_subject(&*format!("Re: {}", subject))?;
+        }
+
+        // If there is no References field, a single msg-id In-Reply-To
+        // field stands in for it
+        let mut references: Vec<MsgId> = match (self.references_ref(), self.in_reply_to_ref()) {
+            (Some(references), _) => references.0.clone(),
+            (None, Some(in_reply_to)) if in_reply_to.0.len() == 1 => in_reply_to.0.clone(),
+            _ => Vec::new(),
+        };
+        if let Some(message_id) = self.message_id_ref() {
+            reply.set_in_reply_to(vec![message_id.0.clone()])?;
+            references.push(message_id.0.clone());
+        }
+        if references.len() > 0 {
+            reply.set_references(references)?;
+        }
+        Ok(reply)
+    }
+
+    /// Create a forward of this email, from `from` and dated `date`, with no
+    /// recipients yet. Its `Subject` is this subject prefixed with `Fwd: `,
+    /// and its body is this email (less any `Bcc` field) quoted with `> `.
+    /// A line too long to quote within the 998 octet limit is continued on
+    /// further quoted lines.
+    pub fn forward<F,D>(&self, from: F, date: D) -> Result<Email, ParseError>
+        where From: TryFrom<F, Error=ParseError>, OrigDate: TryFrom<D, Error=ParseError>
+    {
+        let mut forward = Email::new(from, date)?;
+
+        let subject = self.subject_ref().map(|s| s.text()).unwrap_or_default();
+        let subject = subject.trim();
+        if subject.len() == 0 {
+            forward.set_subject("Fwd:")?;
+        } else {
+            forward.set_subject(&*format!("Fwd: {}", subject))?;
+        }
+
+        let mut original = self.clone();
+        original.clear_bcc();
+        let mut bytes = original.as_bytes();
+        if bytes.ends_with(b"\r\n") {
+            let len = bytes.len() - 2;
+            bytes.truncate(len); // or it would quote an empty last line
+        }
+        let mut body: Vec<u8> = Vec::new();
+        for line in bytes.split(|&c| c == b'\n') {
+            let mut line = if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line };
+            loop {
+                let len = ::std::cmp::min(line.len(), 998 - 2);
+                body.extend_from_slice(b"> ");
+                body.extend_from_slice(&line[..len]);
+                body.extend_from_slice(b"\r\n");
+                line = &line[len..];
+                if line.is_empty() { break; }
+            }
+        }
+        forward.set_body(&*body)?;
+        Ok(forward)
+    }
+
+    /// Stream the email into a byte vector and return that
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        let _ = self.stream(&mut output); // no IoError ought to occur.
+        output
+    }
+
+    /// Stream just the header of the email into a byte vector: the trace
+    /// blocks and header fields, each ending in CRLF, without the blank line
+    /// and body that follow them
+    pub fn headers_bytes(&self) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        let _ = self.stream_header(&mut output); // no IoError ought to occur.
+        output
+    }
+
+    /// The number of bytes the email streams to, counted without building
+    /// the whole byte vector as `as_bytes()` does
+    pub fn byte_len(&self) -> usize {
+        let mut sink = CountingSink::new();
+        let _ = self.stream(&mut sink); // CountingSink never errors
+        sink.count()
+    }
+
+    /// Stream the email into a byte vector, convert to a String, and
+    /// return that
+    pub fn as_string(&self) -> String {
+        let mut vec: Vec<u8> = Vec::new();
+        let _ = self.stream(&mut vec); // no IoError ought to occur.
+        unsafe {
+            // rfc5322 formatted emails fall within utf8, so this should not be
+            // able to fail
+            String::from_utf8_unchecked(vec)
+        }
+    }
+
+    /// Compare the logical content of two emails, where `==` compares their
+    /// structure exactly. The following differences are ignored:
+    ///
+    /// * folding, and the amount of whitespace wherever whitespace appears
+    /// * the case of field names
+    /// * comments in structured fields (all but `Subject`, `Comments` and
+    ///   optional fields, where parentheses are ordinary text)
+    /// * RFC 2047 encoding of `Subject` and `Comments`
+    /// * whitespace at the end of body lines and empty lines at the end of
+    ///   the body
+    ///
+    /// Everything else is significant, including the order of fields, the
+    /// case of addresses (local parts and domains) and of field values.
+    pub fn semantically_eq(&self, other: &Email) -> bool {
+        let trace = |email: &Email| -> Vec<Vec<u8>> {
+            email.message.fields.trace_blocks.iter().map(|tb| {
+                let mut bytes: Vec<u8> = Vec::new();
+                let _ = tb.stream(&mut bytes); // no IoError ought to occur.
+                collapse_whitespace(&strip_comments(&bytes))
+            }).collect()
+        };
+        let fields = |email: &Email| -> Vec<Vec<u8>> {
+            email.message.fields.fields.iter().map(semantic_field).collect()
+        };
+        let body = |email: &Email| -> Vec<u8> { email.dkim_canonical_body(Canonicalization::Relaxed) };
+        trace(self) == trace(other) && fields(self) == fields(other) && body(self) == body(other)
+    }
+
+    /// The header and body canonicalized for DKIM (RFC 6376 section 3.4),
+    /// ready to be hashed by a signer or verifier. The canonical header
+    /// fields are followed by an empty line and the canonical body. An email
+    /// from `Email::parse_verbatim()` is canonicalized from its original bytes.
+    pub fn dkim_canonicalize(&self, mode: Canonicalization) -> Vec<u8> {
+        let mut output = self.dkim_canonical_header(mode);
+        output.extend_from_slice(b"\r\n");
+        output.extend(self.dkim_canonical_body(mode));
+        output
+    }
+
+    /// The header fields canonicalized for DKIM
+    pub fn dkim_canonical_header(&self, mode: Canonicalization) -> Vec<u8> {
+        dkim::canonicalize_header(&self.headers_bytes(), mode)
+    }
+
+    /// The body canonicalized for DKIM
+    pub fn dkim_canonical_body(&self, mode: Canonicalization) -> Vec<u8> {
+        match self.message.body {
+            Some(ref body) => dkim::canonicalize_body(&body.0, mode),
+            None => dkim::canonicalize_body(b"", mode),
+        }
+    }
+
+    /// Create a `lettre::SendableEmail` from this Email.
+    ///
+    /// We require `&mut self` because we temporarily strip off the Bcc line
+    /// when we generate the message, and then we put it back afterwards to
+    /// leave `self` in a functionally unmodified state.
+    #[cfg(feature="lettre")]
+    pub fn as_sendable_email(&mut self) ->
+        Result<::lettre::SendableEmail, &'static str>
+    {
+        use lettre::{SendableEmail, EmailAddress, Envelope};
+        use rfc5322::types::Address;
+
+        let mut rfc_recipients: Vec<Address> = Vec::new();
+        if let Some(to) = self.get_to() {
+            rfc_recipients.extend((to.0).0);
+        }
+        if let Some(cc) = self.get_cc() {
+            rfc_recipients.extend((cc.0).0);
+        }
+        if let Some(bcc) = self.get_bcc() {
+            if let Bcc::AddressList(al) = bcc {
+                rfc_recipients.extend(al.0);
+            }
+        }
+
         // Remove duplicates
         rfc_recipients.dedup();
 
@@ -645,7 +2209,10 @@ impl Email {
             lettre_recipients.push(rfc_address_to_lettre(address)?);
         }
 
-        let from_addr = rfc_from_to_lettre(self.get_from())?;
+        let from_addr = match self.get_from() {
+            Some(from) => rfc_from_to_lettre(from)?,
+            None => return Err("email has no From"),
+        };
 
         let message_id = match self.get_message_id() {
             Some(mid) => format!("{}@{}", mid.0.id_left, mid.0.id_right),
@@ -669,21 +2236,495 @@ impl Email {
             .map_err(|_| "Invalid envelope")?;
         Ok(SendableEmail::new(envelope, message_id, message.as_bytes().to_vec()))
     }
+
+    /// Create the envelope and message bytes needed to send this Email with
+    /// lettre 0.10 or later. As with `as_sendable_email()`, the message is
+    /// written out without its Bcc line, and a `Message-ID` is required.
+    #[cfg(feature="lettre010")]
+    pub fn as_lettre_sendable(&self) -> Result<LettreSendable, &'static str> {
+        use lettre010::Address as LettreAddress;
+        use lettre010::address::Envelope;
+
+        let to_lettre = |a: &EmailAddress| -> Result<LettreAddress, &'static str> {
+            LettreAddress::new(&*a.local_part, &*a.domain)
+                .map_err(|_| "Invalid email to address")
+        };
+
+        let mut recipients: Vec<EmailAddress> = self.to_addresses();
+        recipients.extend(self.cc_addresses());
+        if let Some(&Bcc::AddressList(ref al)) = self.bcc_ref() {
+            recipients.extend(EmailAddress::from_addresses(al));
+        }
+        let mut lettre_recipients: Vec<LettreAddress> = Vec::with_capacity(recipients.len());
+        for address in &recipients {
+            let address = to_lettre(address)?;
+            if !lettre_recipients.contains(&address) {
+                lettre_recipients.push(address);
+            }
+        }
+
+        let from_addr = match self.from_addresses().first() {
+            Some(from) => LettreAddress::new(&*from.local_part, &*from.domain)
+                .map_err(|_| "Invalid email from address")?,
+            None => return Err("email has no From"),
+        };
+
+        let message_id = match self.message_id_ref() {
+            Some(mid) => mid.0.id(),
+            None => return Err("email has no Message-ID"),
+        };
+
+        // Remove Bcc header before creating the message (RFC 5321 section 7.2)
+        let mut email = self.clone();
+        email.clear_bcc();
+
+        let envelope = Envelope::new(Some(from_addr), lettre_recipients)
+            .map_err(|_| "Invalid envelope")?;
+        Ok(LettreSendable {
+            envelope: envelope,
+            message_id: message_id,
+            message: email.as_bytes(),
+        })
+    }
+}
+
+/// An email ready to send with lettre 0.10 or later, as created by
+/// `Email::as_lettre_sendable()`
+#[cfg(feature="lettre010")]
+#[derive(Debug, Clone)]
+pub struct LettreSendable {
+    /// The sender and recipients, taken from the `From`, `To`, `Cc` and `Bcc`
+    /// fields
+    pub envelope: ::lettre010::address::Envelope,
+    /// The `Message-ID`, without angle brackets
+    pub message_id: String,
+    /// The message as written out, without its `Bcc` field
+    pub message: Vec<u8>,
+}
+
+// The header of a message whose first line ends in a bare LF rather than
+// CRLF, as happens when a message is mangled in transit, with each line up to
+// and including the blank line ending in CRLF instead, followed by the rest
+// of the input unchanged. The body accepts either line ending itself.
+struct LfHeader {
+    bytes: Vec<u8>,
+    // the offsets in `bytes` of the CRs inserted
+    inserted: Vec<usize>,
+}
+impl LfHeader {
+    fn new(input: &[u8]) -> Option<LfHeader> {
+        match input.iter().position(|&c| c == b'\n') {
+            Some(p) if p == 0 || input[p - 1] != b'\r' => {},
+            _ => return None,
+        }
+        let mut bytes: Vec<u8> = Vec::with_capacity(input.len() + input.len() / 40);
+        let mut inserted: Vec<usize> = Vec::new();
+        let mut line_start: usize = 0;
+        for (i, &c) in input.iter().enumerate() {
+            if c != b'\n' { continue; }
+            let line = &input[line_start..i];
+            if line.ends_with(b"\r") {
+                bytes.extend_from_slice(&input[line_start..i + 1]);
+            } else {
+                bytes.extend_from_slice(line);
+                inserted.push(bytes.len());
+                bytes.extend_from_slice(b"\r\n");
+            }
+            line_start = i + 1;
+            if line.is_empty() || line == b"\r" {
+                break; // the blank line ending the header
+            }
+        }
+        bytes.extend_from_slice(&input[line_start..]);
+        Some(LfHeader { bytes: bytes, inserted: inserted })
+    }
+
+    // The offset in the input of `offset` in `bytes`
+    fn input_offset(&self, offset: usize) -> usize {
+        offset - self.inserted.iter().take_while(|&&p| p < offset).count()
+    }
+}
+
+// The error for input left over at `rem` within `input` after the header:
+// why the next field failed to parse, located there
+fn trailing_input(input: &[u8], rem: &[u8]) -> ParseError {
+    let inner = match Field::parse(rem) {
+        Err(e) => e,
+        Ok(_) => ParseError::TrailingInput("Email", input.len() - rem.len()),
+    };
+    ParseError::at(input, rem, inner)
+}
+
+// The number of mailboxes in a `Bcc` field, with groups expanded
+fn bcc_count(bcc: Option<&Bcc>) -> usize {
+    match bcc {
+        Some(&Bcc::AddressList(ref al)) => EmailAddress::from_addresses(al).len(),
+        _ => 0,
+    }
+}
+
+// Remove the mailboxes in `list` matching `addr`, returning whether any were
+// removed
+fn remove_address(list: &mut AddressList, addr: &EmailAddress) -> bool {
+    let before = list.0.len();
+    list.0.retain(|a| {
+        if let Address::Mailbox(ref mbox) = *a {
+            !EmailAddress::from_mailbox(mbox).same_address(addr)
+        } else {
+            true
+        }
+    });
+    list.0.len() < before
+}
+
+// Remove the comments from a structured field `value`, and reduce each run
+// of whitespace (including folds) to a single space. A comment separates
+// what is either side of it as whitespace does. If `aggressive`, spaces
+// beside the specials that may have CFWS either side of them are removed
+// too. Quoted strings and domain literals are copied as they are.
+fn minify_value(value: &[u8], aggressive: bool) -> Vec<u8> {
+    let is_separator = |c: u8| c==b',' || c==b'<' || c==b'>' || c==b':' || c==b';' || c==b'@';
+    let mut output: Vec<u8> = Vec::with_capacity(value.len());
+    let mut pending_space: bool = false;
+    let mut comment_depth: usize = 0;
+    // The byte closing the quoted string or domain literal we are within
+    let mut closer: Option<u8> = None;
+    let mut i: usize = 0;
+    while i < value.len() {
+        let c = value[i];
+        if let Some(close) = closer {
+            output.push(c);
+            if c == b'\\' && i + 1 < value.len() {
+                output.push(value[i + 1]);
+                i += 1;
+            } else if c == close {
+                closer = None;
+            }
+        } else if comment_depth > 0 {
+            match c {
+                b'\\' => i += 1,
+                b'(' => comment_depth += 1,
+                b')' => comment_depth -= 1,
+                _ => { },
+            }
+        } else if c == b'(' {
+            comment_depth = 1;
+            pending_space = true;
+        } else if is_wsp(c) || c == b'\r' || c == b'\n' {
+            pending_space = true;
+        } else {
+            if pending_space && !output.is_empty() {
+                let beside_separator = is_separator(c)
+                    || output.last().map_or(false, |&l| is_separator(l));
+                if !(aggressive && beside_separator) {
+                    output.push(b' ');
+                }
+            }
+            pending_space = false;
+            output.push(c);
+            match c {
+                b'"' => closer = Some(b'"'),
+                b'[' => closer = Some(b']'),
+                _ => { },
+            }
+        }
+        i += 1;
+    }
+    output
+}
+
+// Convert each lone LF or CR in `text` into CRLF
+fn to_crlf(text: &str) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(text.len() + text.len() / 40);
+    let mut bytes = text.bytes().peekable();
+    while let Some(c) = bytes.next() {
+        match c {
+            b'\r' => {
+                if bytes.peek() == Some(&b'\n') {
+                    bytes.next();
+                }
+                output.extend_from_slice(b"\r\n");
+            },
+            b'\n' => output.extend_from_slice(b"\r\n"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+// Append `line` to `output`, replacing spaces with line breaks so that no
+// line is longer than `width` octets, and breaking words that are longer
+// than that
+fn wrap_line(line: &str, width: usize, output: &mut String) {
+    let mut len: usize = 0;
+    for word in line.split(' ') {
+        if len > 0 && len + 1 + word.len() <= width {
+            output.push(' ');
+            len += 1;
+        } else if len > 0 {
+            output.push('\n');
+            len = 0;
+        }
+        let mut word = word;
+        while word.len() > width - len {
+            let mut split = width - len;
+            while !word.is_char_boundary(split) { split -= 1; }
+            if split == 0 {
+                // a character wider than the space left on the line
+                split = word.chars().next().map_or(0, |c| c.len_utf8());
+            }
+            output.push_str(&word[..split]);
+            output.push('\n');
+            word = &word[split..];
+            len = 0;
+        }
+        output.push_str(word);
+        len += word.len();
+    }
+}
+
+// The name and logical value of a field, as `Email::semantically_eq()`
+// compares it
+fn semantic_field(field: &Field) -> Vec<u8> {
+    let mut output: Vec<u8> = field.canonical_name().to_ascii_lowercase().into_bytes();
+    output.push(b':');
+    let value: Vec<u8> = match *field {
+        Field::Subject(ref x) => decoded_text(&x.0.to_text()),
+        Field::Comments(ref x) => decoded_text(&x.0.to_text()),
+        Field::OptionalField(ref x) => x.value.to_text().into_bytes(),
+        _ => {
+            let mut bytes: Vec<u8> = Vec::new();
+            let _ = field.stream(&mut bytes); // no IoError ought to occur.
+            let colon = bytes.iter().position(|&c| c == b':').map_or(0, |p| p + 1);
+            strip_comments(&bytes[colon..])
+        },
+    };
+    output.extend(collapse_whitespace(&value));
+    output
+}
+
+// Decode any RFC 2047 encoded-words in `text`
+fn decoded_text(text: &str) -> Vec<u8> {
+    match encoded_word::decode(text.as_bytes()) {
+        Ok(decoded) => decoded.into_bytes(),
+        Err(_) => text.as_bytes().to_vec(),
+    }
+}
+
+// Remove the comments from a structured field value, leaving quoted-strings
+// (which may contain parentheses) alone
+fn strip_comments(input: &[u8]) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    let mut depth: usize = 0;
+    let mut quoted: bool = false;
+    let mut escaped: bool = false;
+    for &c in input {
+        if escaped {
+            escaped = false;
+            if depth == 0 { output.push(c); }
+            continue;
+        }
+        match c {
+            b'\\' => {
+                escaped = true;
+                if depth == 0 { output.push(c); }
+            },
+            b'"' if depth == 0 => {
+                quoted = !quoted;
+                output.push(c);
+            },
+            b'(' if !quoted => {
+                // a comment separates what is either side of it
+                if depth == 0 { output.push(b' '); }
+                depth += 1;
+            },
+            b')' if !quoted && depth > 0 => depth -= 1,
+            _ => if depth == 0 { output.push(c); },
+        }
+    }
+    output
+}
+
+// Unfold, reduce each run of whitespace to a single space, and trim
+fn collapse_whitespace(input: &[u8]) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    for word in input.split(|&c| c == b' ' || c == b'\t' || c == b'\r' || c == b'\n')
+        .filter(|w| !w.is_empty())
+    {
+        if !output.is_empty() { output.push(b' '); }
+        output.extend_from_slice(word);
+    }
+    output
+}
+
+// The names of fields which may appear at most once but are repeated, in
+// the order of their first repetition
+fn repeated_fields(fields: &[Field]) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    let mut repeated: Vec<String> = Vec::new();
+    for field in fields {
+        match *field {
+            Field::Comments(_) | Field::Keywords(_) | Field::OptionalField(_) => continue,
+            _ => { },
+        }
+        let name = field.canonical_name().to_owned();
+        if !seen.contains(&name) {
+            seen.push(name);
+        } else if !repeated.contains(&name) {
+            repeated.push(name);
+        }
+    }
+    repeated
+}
+
+impl Email {
+    // The bytes each field (outside of trace blocks) streams as: those it was
+    // parsed from if kept by `parse_verbatim()` and unchanged, otherwise the
+    // folded field
+    fn wire_fields(&self) -> Result<Vec<Vec<u8>>, IoError> {
+        let mut output: Vec<Vec<u8>> = Vec::with_capacity(self.message.fields.fields.len());
+        let mut used: Vec<bool> = match self.raw {
+            Some(ref raw) => vec![false; raw.fields.len()],
+            None => Vec::new(),
+        };
+        for field in &self.message.fields.fields {
+            let kept = self.raw.as_ref().and_then(|raw| {
+                raw.fields.iter().enumerate()
+                    .position(|(i, &(ref r, _))| !used[i] && r == field)
+                    .map(|i| { used[i] = true; raw.fields[i].1.clone() })
+            });
+            match kept {
+                Some(bytes) => output.push(bytes),
+                None => {
+                    let mut bytes: Vec<u8> = Vec::new();
+                    field.stream(&mut bytes)?;
+                    output.push(fold(&bytes));
+                },
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Parse `data` as an email and, if that succeeds, stream it back out. This
+/// is the entry point for fuzzing (see the `fuzz` directory), and must
+/// return for any input without panicking.
+#[doc(hidden)]
+pub fn fuzz_parse(data: &[u8]) {
+    if let Ok((email, _)) = Email::parse(data) {
+        let _ = email.as_bytes();
+    }
 }
 
 impl Parsable for Email {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
-        let mut rem = input;
-        match Message::parse(rem).map(|(value, r)| { rem = r; value }) {
-            Ok(message) => Ok((Email { message: message}, rem)),
-            Err(e) => Err(ParseError::Parse("Email", Box::new(e)))
-        }
+        // Message::parse() locates any failure with ParseError::At. A header
+        // with bare LF line endings is parsed as if they were CRLF, with the
+        // offsets mapped back to `input`.
+        limits::checked(|| {
+            let (message, rem) = match LfHeader::new(input) {
+                None => Message::parse(input)?,
+                Some(lf) => match Message::parse(&lf.bytes) {
+                    Ok((message, rem)) => {
+                        (message, &input[lf.input_offset(lf.bytes.len() - rem.len())..])
+                    },
+                    Err(ParseError::At { offset, inner, .. }) => {
+                        return Err(ParseError::at(input, &input[lf.input_offset(offset)..],
+                                                  *inner));
+                    },
+                    Err(e) => return Err(e),
+                },
+            };
+            Ok((Email { message: message, raw: None, max_recipients: None }, rem))
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Email {
+    type Error = ParseError;
+    fn try_from(input: &'a [u8]) -> Result<Email, ParseError> {
+        Email::parse_complete(input)
+    }
+}
+impl<'a> TryFrom<&'a str> for Email {
+    type Error = ParseError;
+    fn try_from(input: &'a str) -> Result<Email, ParseError> {
+        Email::parse_complete(input.as_bytes())
+    }
+}
+impl TryFrom<String> for Email {
+    type Error = ParseError;
+    fn try_from(input: String) -> Result<Email, ParseError> {
+        Email::parse_complete(input.as_bytes())
+    }
+}
+
+/// Parses as `Email::parse_complete()` does, so trailing input is an error
+impl FromStr for Email {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Email, ParseError> {
+        Email::parse_complete(s.as_bytes())
     }
 }
 
 impl Streamable for Email {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
-        self.message.stream(w)
+        let mut count: usize = self.stream_header(w)?;
+        if self.message.body.is_some() {
+            count += w.write(b"\r\n")?;
+            count += self.stream_body(w)?;
+        }
+        Ok(count)
+    }
+}
+
+impl Email {
+    // Stream the trace blocks and header fields, each ending in CRLF, but
+    // not the blank line which separates them from the body
+    fn stream_header<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let raw = match self.raw {
+            Some(ref raw) => raw,
+            None => return self.message.fields.stream(w),
+        };
+        let mut header: Vec<u8> = Vec::new();
+        let mut used: Vec<bool> = vec![false; raw.trace_blocks.len()];
+        for tb in &self.message.fields.trace_blocks {
+            match raw.trace_blocks.iter().enumerate()
+                .position(|(i, &(ref r, _))| !used[i] && r == tb)
+            {
+                Some(i) => {
+                    used[i] = true;
+                    header.extend_from_slice(&raw.trace_blocks[i].1);
+                },
+                None => {
+                    let mut bytes: Vec<u8> = Vec::new();
+                    tb.stream(&mut bytes)?;
+                    header.extend(fold(&bytes));
+                },
+            }
+        }
+        for bytes in self.wire_fields()? {
+            header.extend(bytes);
+        }
+        w.write_all(&header)?;
+        Ok(header.len())
+    }
+
+    // Stream the body, if there is one, as the bytes it was parsed from if
+    // those were kept and it is unchanged
+    fn stream_body<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let body = match self.message.body {
+            Some(ref body) => body,
+            None => return Ok(0),
+        };
+        let raw_body = self.raw.as_ref().and_then(|raw| raw.body.as_ref());
+        match raw_body {
+            Some(&(ref b, ref bytes)) if b == body => {
+                w.write_all(bytes)?;
+                Ok(bytes.len())
+            },
+            _ => body.stream(w),
+        }
     }
 }
 
@@ -697,3 +2738,25 @@ impl fmt::Display for Email {
         }
     }
 }
+
+/// Equality is structural, comparing the parsed representation as `Message`
+/// does. Two emails that differ only in whitespace or folding are therefore
+/// not equal, even if they mean the same thing.
+impl PartialEq for Email {
+    fn eq(&self, other: &Email) -> bool {
+        self.message == other.message
+    }
+}
+
+impl Eq for Email { }
+
+/// Hashes the bytes the email streams to in canonical form (ignoring any
+/// kept by `Email::parse_verbatim()`). Structurally equal emails stream
+/// identically, so this agrees with `PartialEq`.
+impl Hash for Email {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut bytes: Vec<u8> = Vec::new();
+        let _ = self.message.stream(&mut bytes); // no IoError ought to occur.
+        bytes.hash(state);
+    }
+}
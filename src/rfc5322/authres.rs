@@ -0,0 +1,310 @@
+// Parsing of the Authentication-Results header field, as defined in
+// RFC 8601 section 2.2
+
+use std::io::Write;
+use std::io::Error as IoError;
+use super::{Parsable, Streamable, ParseError};
+use super::types::{CFWS, ParameterValue, QuotedString, is_token};
+
+// RFC 8601, 2.2
+// Keyword = ldh-str (from RFC 5321, letters, digits and hyphens)
+fn keyword(input: &[u8]) -> Result<(String, &[u8]), ParseError> {
+    let len = input.iter().take_while(|&&c| c.is_ascii_alphanumeric() || c==b'-').count();
+    if len == 0 {
+        if input.len() == 0 { return Err(ParseError::Eof("Keyword")); }
+        return Err(ParseError::NotFound("Keyword"));
+    }
+    Ok((String::from_utf8_lossy(&input[..len]).into_owned(), &input[len..]))
+}
+
+// 1*DIGIT
+fn version(input: &[u8]) -> Result<(u32, &[u8]), ParseError> {
+    let len = input.iter().take_while(|&&c| c.is_ascii_digit()).count();
+    if len == 0 {
+        return Err(ParseError::NotFound("Version"));
+    }
+    match ::std::str::from_utf8(&input[..len]).ok().and_then(|s| s.parse::<u32>().ok()) {
+        Some(v) => Ok((v, &input[len..])),
+        None => Err(ParseError::NotFound("Version")),
+    }
+}
+
+// A character of a property value which is not quoted
+#[inline]
+fn is_pvalue_char(c: u8) -> bool {
+    c>32 && c<127 && c!=b';' && c!=b'(' && c!=b')' && c!=b'"' && c!=b'\\'
+}
+
+// pvalue = [CFWS] ( value / [ [ local-part ] "@" ] domain-name ) [CFWS]
+// A value which is not quoted runs until whitespace, a comment or the next
+// ";", which also covers the address forms.
+fn pvalue(input: &[u8]) -> Result<(String, &[u8]), ParseError> {
+    if let Ok((qs, rem)) = QuotedString::parse(input) {
+        return Ok((qs.text(), rem));
+    }
+    let len = input.iter().take_while(|&&c| is_pvalue_char(c)).count();
+    if len == 0 {
+        if input.len() == 0 { return Err(ParseError::Eof("Property Value")); }
+        return Err(ParseError::NotFound("Property Value"));
+    }
+    Ok((String::from_utf8_lossy(&input[..len]).into_owned(), &input[len..]))
+}
+
+// Skip over optional CFWS
+fn skip_cfws(input: &[u8]) -> &[u8] {
+    match CFWS::parse(input) {
+        Ok((_, rem)) => rem,
+        Err(_) => input,
+    }
+}
+
+/// A property of the message checked by a method, such as `smtp.mailfrom`
+/// or `header.d`, and its value (RFC 8601, 2.2)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    /// The property type, such as `smtp` or `header`
+    pub ptype: String,
+    /// The property, such as `mailfrom` or `d`
+    pub property: String,
+    /// The value, with any quoting removed
+    pub value: String,
+}
+impl Parsable for Property {
+    // propspec = ptype [CFWS] "." [CFWS] property [CFWS] "=" pvalue
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Property")); }
+        let mut rem = input;
+        let (ptype, r) = keyword(rem)?;
+        rem = skip_cfws(r);
+        req!(rem, b".", input);
+        rem = skip_cfws(rem);
+        let (property, r) = keyword(rem)?;
+        rem = skip_cfws(r);
+        req!(rem, b"=", input);
+        rem = skip_cfws(rem);
+        let (value, r) = pvalue(rem)?;
+        Ok((Property {
+            ptype: ptype,
+            property: property,
+            value: value,
+        }, r))
+    }
+}
+impl Streamable for Property {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += w.write(self.ptype.as_bytes())?;
+        count += w.write(b".")?;
+        count += w.write(self.property.as_bytes())?;
+        count += w.write(b"=")?;
+        count += stream_value(&self.value, is_pvalue_char, w)?;
+        Ok(count)
+    }
+}
+impl_display!(Property);
+
+// Stream a value as is if all of it is made of `plain` characters, otherwise
+// as a quoted-string
+fn stream_value<W: Write>(value: &str, plain: fn(u8) -> bool, w: &mut W)
+                          -> Result<usize, IoError>
+{
+    let plain = !value.is_empty() && value.bytes().all(plain);
+    if plain {
+        return Ok(w.write(value.as_bytes())?);
+    }
+    let mut count: usize = w.write(b"\"")?;
+    for c in value.bytes() {
+        if c==b'"' || c==b'\\' {
+            count += w.write(b"\\")?;
+        }
+        count += w.write(&[c])?;
+    }
+    count += w.write(b"\"")?;
+    Ok(count)
+}
+
+/// The result of one authentication method, such as `spf=pass` or
+/// `dkim=fail` (RFC 8601, 2.2)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodResult {
+    /// The method, such as `spf`, `dkim` or `dmarc`
+    pub method: String,
+    pub method_version: Option<u32>,
+    /// The result, such as `pass`, `fail` or `none`
+    pub result: String,
+    /// The reason given for the result, with any quoting removed
+    pub reason: Option<String>,
+    pub properties: Vec<Property>,
+}
+impl Parsable for MethodResult {
+    // methodspec = [CFWS] method [CFWS] "=" [CFWS] result
+    // method = Keyword [ [CFWS] "/" [CFWS] method-version ]
+    // reasonspec = "reason" [CFWS] "=" [CFWS] value
+    // resinfo = [CFWS] ";" methodspec [ CFWS reasonspec ]
+    //           [ CFWS 1*propspec ]
+    // (this parses what follows the ";")
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Method Result")); }
+        let mut rem = skip_cfws(input);
+        let (method, r) = keyword(rem)?;
+        rem = skip_cfws(r);
+        let mut method_version: Option<u32> = None;
+        if rem.starts_with(b"/") {
+            let (v, r) = version(skip_cfws(&rem[1..]))?;
+            method_version = Some(v);
+            rem = skip_cfws(r);
+        }
+        req!(rem, b"=", input);
+        rem = skip_cfws(rem);
+        let (result, r) = keyword(rem)?;
+        rem = r;
+
+        let mut reason: Option<String> = None;
+        {
+            let mut rem2 = skip_cfws(rem);
+            if let Ok((word, r)) = keyword(rem2) {
+                let r = skip_cfws(r);
+                if word.eq_ignore_ascii_case("reason") && r.starts_with(b"=") {
+                    rem2 = skip_cfws(&r[1..]);
+                    if let Ok(value) = parse!(ParameterValue, rem2) {
+                        reason = Some(value.text());
+                        rem = rem2;
+                    }
+                }
+            }
+        }
+        let mut properties: Vec<Property> = Vec::new();
+        loop {
+            let mut rem2 = skip_cfws(rem);
+            if let Ok(p) = parse!(Property, rem2) {
+                properties.push(p);
+                rem = rem2;
+            } else {
+                break;
+            }
+        }
+        Ok((MethodResult {
+            method: method,
+            method_version: method_version,
+            result: result,
+            reason: reason,
+            properties: properties,
+        }, rem))
+    }
+}
+impl Streamable for MethodResult {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += w.write(self.method.as_bytes())?;
+        if let Some(v) = self.method_version {
+            count += w.write(format!("/{}", v).as_bytes())?;
+        }
+        count += w.write(b"=")?;
+        count += w.write(self.result.as_bytes())?;
+        if let Some(ref reason) = self.reason {
+            count += w.write(b" reason=")?;
+            count += stream_value(reason, is_token, w)?;
+        }
+        for p in &self.properties {
+            count += w.write(b" ")?;
+            count += p.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl MethodResult {
+    /// Fetch the value of the property `ptype.property`, such as
+    /// `smtp.mailfrom` (compared case-insensitively)
+    pub fn property(&self, ptype: &str, property: &str) -> Option<&str> {
+        self.properties.iter()
+            .find(|p| p.ptype.eq_ignore_ascii_case(ptype)
+                  && p.property.eq_ignore_ascii_case(property))
+            .map(|p| &*p.value)
+    }
+}
+impl_display!(MethodResult);
+
+/// The value of an `Authentication-Results` field (RFC 8601), which reports
+/// the results of checks such as SPF, DKIM and DMARC made by the server
+/// `authserv_id`. The results are empty if the field reports `none`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticationResults {
+    pub authserv_id: String,
+    pub version: Option<u32>,
+    pub results: Vec<MethodResult>,
+}
+impl Parsable for AuthenticationResults {
+    // authres-payload = [CFWS] authserv-id
+    //                   [ CFWS authres-version ]
+    //                   ( no-result / 1*resinfo ) [CFWS] CRLF
+    // authserv-id = value
+    // authres-version = 1*DIGIT [CFWS]
+    // no-result = [CFWS] ";" [CFWS] "none"
+    // (this parses up to but not including the CRLF)
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Authentication Results")); }
+        let mut rem = skip_cfws(input);
+        let authserv_id = match parse!(ParameterValue, rem) {
+            Ok(v) => v.text(),
+            Err(_) => return Err(ParseError::NotFound("Authserv-id")),
+        };
+        rem = skip_cfws(rem);
+        let mut authres_version: Option<u32> = None;
+        if let Ok((v, r)) = version(rem) {
+            authres_version = Some(v);
+            rem = skip_cfws(r);
+        }
+        let mut results: Vec<MethodResult> = Vec::new();
+        loop {
+            let rem2 = skip_cfws(rem);
+            if !rem2.starts_with(b";") {
+                break;
+            }
+            match MethodResult::parse(&rem2[1..]) {
+                Ok((result, r)) => {
+                    results.push(result);
+                    rem = r;
+                },
+                Err(_) => break,
+            }
+        }
+        if results.is_empty() {
+            let mut rem2 = skip_cfws(rem);
+            req!(rem2, b";", input);
+            match keyword(skip_cfws(rem2)) {
+                Ok((ref word, r)) if word.eq_ignore_ascii_case("none") => rem = r,
+                _ => return Err(ParseError::NotFound("Authentication Results")),
+            }
+        }
+        Ok((AuthenticationResults {
+            authserv_id: authserv_id,
+            version: authres_version,
+            results: results,
+        }, skip_cfws(rem)))
+    }
+}
+impl Streamable for AuthenticationResults {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += stream_value(&self.authserv_id, is_token, w)?;
+        if let Some(v) = self.version {
+            count += w.write(format!(" {}", v).as_bytes())?;
+        }
+        if self.results.is_empty() {
+            count += w.write(b"; none")?;
+        }
+        for result in &self.results {
+            count += w.write(b"; ")?;
+            count += result.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl AuthenticationResults {
+    /// Fetch the result of the first check made with `method`, such as
+    /// `dkim` (compared case-insensitively)
+    pub fn result(&self, method: &str) -> Option<&MethodResult> {
+        self.results.iter().find(|r| r.method.eq_ignore_ascii_case(method))
+    }
+}
+impl_display!(AuthenticationResults);
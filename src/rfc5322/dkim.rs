@@ -0,0 +1,120 @@
+// Header and body canonicalization for DKIM signatures, as defined in
+// RFC 6376 section 3.4
+
+use super::types::is_wsp;
+
+/// A DKIM canonicalization algorithm (RFC 6376, 3.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicalization {
+    /// Tolerates almost no modification
+    Simple,
+    /// Tolerates common modifications such as whitespace replacement and
+    /// header field line rewrapping
+    Relaxed,
+}
+
+// Split a header block into its fields, each including its folded lines
+// and final CRLF
+fn split_fields(header: &[u8]) -> Vec<&[u8]> {
+    let mut fields: Vec<&[u8]> = Vec::new();
+    let mut start: usize = 0;
+    let mut pos: usize = 0;
+    while pos < header.len() {
+        let line_end = match header[pos..].windows(2).position(|w| w == b"\r\n") {
+            Some(p) => pos + p + 2,
+            None => header.len(),
+        };
+        if line_end < header.len() && !is_wsp(header[line_end]) {
+            fields.push(&header[start..line_end]);
+            start = line_end;
+        }
+        pos = line_end;
+    }
+    if start < header.len() {
+        fields.push(&header[start..]);
+    }
+    fields
+}
+
+// RFC 6376, 3.4.2
+fn relaxed_field(field: &[u8]) -> Vec<u8> {
+    let colon = match field.iter().position(|&c| c == b':') {
+        Some(p) => p,
+        None => return field.to_vec(),
+    };
+    let mut output: Vec<u8> = Vec::with_capacity(field.len());
+    let name = &field[..colon];
+    let name_end = name.iter().rposition(|&c| !is_wsp(c)).map_or(0, |p| p + 1);
+    output.extend(name[..name_end].to_ascii_lowercase());
+    output.push(b':');
+    let mut pending_space: bool = false;
+    for &c in &field[colon + 1..] {
+        if c == b'\r' || c == b'\n' {
+            continue;
+        }
+        if is_wsp(c) {
+            pending_space = true;
+            continue;
+        }
+        if pending_space && output.len() > name_end + 1 {
+            output.push(b' ');
+        }
+        pending_space = false;
+        output.push(c);
+    }
+    output.extend_from_slice(b"\r\n");
+    output
+}
+
+/// Canonicalize a block of header fields, each ending in CRLF
+pub fn canonicalize_header(header: &[u8], mode: Canonicalization) -> Vec<u8> {
+    match mode {
+        Canonicalization::Simple => header.to_vec(),
+        Canonicalization::Relaxed => {
+            let mut output: Vec<u8> = Vec::with_capacity(header.len());
+            for field in split_fields(header) {
+                output.extend(relaxed_field(field));
+            }
+            output
+        },
+    }
+}
+
+/// Canonicalize a body. Empty lines at its end are removed, and under
+/// `Relaxed` whitespace at the end of lines is removed and other runs of
+/// whitespace are reduced to a single space.
+pub fn canonicalize_body(body: &[u8], mode: Canonicalization) -> Vec<u8> {
+    let mut lines: Vec<Vec<u8>> = body.split(|&c| c == b'\n')
+        .map(|line| if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line })
+        .map(|line| match mode {
+            Canonicalization::Simple => line.to_vec(),
+            Canonicalization::Relaxed => {
+                let mut output: Vec<u8> = Vec::with_capacity(line.len());
+                let mut pending_space: bool = false;
+                for &c in line {
+                    if is_wsp(c) {
+                        pending_space = true;
+                        continue;
+                    }
+                    if pending_space { output.push(b' '); }
+                    pending_space = false;
+                    output.push(c);
+                }
+                output
+            },
+        })
+        .collect();
+    while lines.last().map_or(false, |l| l.is_empty()) {
+        lines.pop();
+    }
+    let mut output: Vec<u8> = Vec::with_capacity(body.len() + 2);
+    for line in lines {
+        output.extend(line);
+        output.extend_from_slice(b"\r\n");
+    }
+    // 3.4.3: an empty body is canonicalized as a single CRLF under "simple"
+    if output.is_empty() && mode == Canonicalization::Simple {
+        output.extend_from_slice(b"\r\n");
+    }
+    output
+}
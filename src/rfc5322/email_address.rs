@@ -1,14 +1,21 @@
 
+use super::{Parsable, ParseError};
+use super::encoded_word;
 use super::types::{AddressList, Address, Mailbox, Group, NameAddr, AddrSpec,
-                   GroupList, MailboxList};
+                   GroupList, MailboxList, AngleAddr, DisplayName, is_atext};
 
 /// This type represents an Email Address in a way that is simpler and more
 /// directly useful than the ABNF-based rfc5322 types. It is not used by the
 /// main parser, but may be useful to consumers of this library.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EmailAddress {
     pub display_name: Option<String>,
     pub local_part: String,
     pub domain: String,
+    /// The comments within the address, such as `Work` in
+    /// `john@example.com (Work)`. These are only kept by
+    /// `from_addr_spec_with_comments()`.
+    pub comments: Vec<String>,
 }
 
 impl EmailAddress {
@@ -42,7 +49,8 @@ impl EmailAddress {
         let mut email_address = EmailAddress::from_addr_spec(
             &name_addr.angle_addr.addr_spec);
         if let Some(ref display_name) = name_addr.display_name {
-            email_address.display_name = Some(format!("{}", display_name));
+            // The phrase includes any surrounding whitespace
+            email_address.display_name = Some(format!("{}", display_name).trim().to_owned());
         }
         email_address
     }
@@ -51,8 +59,17 @@ impl EmailAddress {
     {
         EmailAddress {
             display_name: None,
-            local_part: format!("{}", addr_spec.local_part),
-            domain: format!("{}", addr_spec.domain),
+            local_part: addr_spec.local_part_str(),
+            domain: addr_spec.domain_str(),
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn from_addr_spec_with_comments(addr_spec: &AddrSpec) -> EmailAddress
+    {
+        EmailAddress {
+            comments: addr_spec.comments(),
+            ..EmailAddress::from_addr_spec(addr_spec)
         }
     }
 
@@ -80,4 +97,72 @@ impl EmailAddress {
         }
         output
     }
+
+    /// Convert back into a `Mailbox`, quoting (or RFC 2047 encoding) the
+    /// display name and quoting the local part as needed. Fails if the
+    /// resulting address does not parse.
+    pub fn to_mailbox(&self) -> Result<Mailbox, ParseError>
+    {
+        let dot_atom = self.local_part.split('.')
+            .all(|w| w.len() > 0 && w.bytes().all(is_atext));
+        let addr = if dot_atom {
+            format!("{}@{}", self.local_part, self.domain)
+        } else {
+            format!("{}@{}", quote(&*self.local_part), self.domain)
+        };
+        mailbox_from_parts(self.display_name.as_ref().map(|d| &**d), &*addr)
+    }
+
+    /// Whether `other` is the same address, comparing the domain
+    /// case-insensitively but the local part case-sensitively. Display
+    /// names and comments are ignored.
+    pub fn same_address(&self, other: &EmailAddress) -> bool {
+        self.local_part == other.local_part && self.domain.eq_ignore_ascii_case(&*other.domain)
+    }
+}
+
+// Build a mailbox from a display name, which is quoted or encoded as needed,
+// and an addr-spec
+pub(crate) fn mailbox_from_parts(display: Option<&str>, addr: &str) -> Result<Mailbox, ParseError> {
+    let (addr_spec, rem) = AddrSpec::parse(addr.as_bytes())?;
+    if rem.len() > 0 {
+        return Err(ParseError::TrailingInput("AddrSpec", addr.len() - rem.len()));
+    }
+    let display = match display {
+        Some(d) if d.trim().len() > 0 => d.trim(),
+        _ => return Ok(Mailbox::AddrSpec(addr_spec)),
+    };
+    let mut phrase: Vec<u8> = if !display.is_ascii() {
+        encoded_word::encode(display)
+    } else if display.split(' ').all(|w| w.len() > 0 && w.bytes().all(is_atext)) {
+        display.as_bytes().to_vec()
+    } else {
+        quote(display).into_bytes()
+    };
+    phrase.push(b' ');
+    let (display_name, rem) = DisplayName::parse(&phrase)?;
+    if rem.len() > 0 {
+        return Err(ParseError::TrailingInput("DisplayName", phrase.len() - rem.len()));
+    }
+    Ok(Mailbox::NameAddr(NameAddr {
+        display_name: Some(display_name),
+        angle_addr: AngleAddr {
+            pre_cfws: None,
+            addr_spec: addr_spec,
+            post_cfws: None,
+        },
+    }))
+}
+
+// Make a quoted-string of `text`
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
 }
@@ -0,0 +1,259 @@
+// Encoded-words as defined in RFC 2047, which carry non-ASCII text
+// within header fields
+
+use super::ParseError;
+use super::encoding::base64_encode;
+use super::encoding::base64_decode;
+use super::encoding::hex_value;
+
+// RFC 2047, 2
+// An 'encoded-word' may not be more than 75 characters long, including
+// 'charset', 'encoding', 'encoded-text', and delimiters.
+const MAX_ENCODED_WORD_LEN: usize = 75;
+const PREFIX: &'static [u8] = b"=?UTF-8?B?";
+const SUFFIX: &'static [u8] = b"?=";
+
+#[inline]
+fn is_ws(c: u8) -> bool { c == b' ' || c == b'\t' || c == b'\r' || c == b'\n' }
+
+/// Encode `text` as a sequence of UTF-8 "B" encoded-words separated by
+/// spaces. Each encoded-word holds whole characters and is at most 75
+/// characters long.
+pub fn encode(text: &str) -> Vec<u8> {
+    // Four base64 characters carry three octets
+    let max_octets = (MAX_ENCODED_WORD_LEN - PREFIX.len() - SUFFIX.len()) / 4 * 3;
+    let mut output: Vec<u8> = Vec::new();
+    let mut start: usize = 0;
+    for (i, c) in text.char_indices() {
+        if i + c.len_utf8() - start > max_octets {
+            push_encoded_word(&mut output, &text.as_bytes()[start..i]);
+            start = i;
+        }
+    }
+    if start < text.len() {
+        push_encoded_word(&mut output, &text.as_bytes()[start..]);
+    }
+    output
+}
+
+/// Encode each run of adjacent non-ASCII words within `text`, leaving ASCII
+/// words and the whitespace around them untouched. A run is encoded as a
+/// whole so that the whitespace between its words survives decoding.
+pub fn encode_words(text: &str) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(text.len());
+    let mut copied: usize = 0;
+    let mut run: Option<(usize, usize)> = None;
+    let bytes = text.as_bytes();
+    let mut pos: usize = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == b' ' || bytes[pos] == b'\t' {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < bytes.len() && bytes[pos] != b' ' && bytes[pos] != b'\t' { pos += 1; }
+        if text[start..pos].is_ascii() {
+            if let Some((run_start, run_end)) = run.take() {
+                output.extend_from_slice(&bytes[copied..run_start]);
+                output.extend(encode(&text[run_start..run_end]));
+                copied = run_end;
+            }
+        } else {
+            run = match run {
+                Some((run_start, _)) => Some((run_start, pos)),
+                None => Some((start, pos)),
+            };
+        }
+    }
+    if let Some((run_start, run_end)) = run {
+        output.extend_from_slice(&bytes[copied..run_start]);
+        output.extend(encode(&text[run_start..run_end]));
+        copied = run_end;
+    }
+    output.extend_from_slice(&bytes[copied..]);
+    output
+}
+
+/// Encode the non-ASCII display names within a mailbox or address list such
+/// as `"Hans Müller <hm@example.de>, Zoë <z@example.com>"`. Angle-addrs and
+/// comments are copied untouched, and a quoted-string holding non-ASCII text
+/// is replaced by encoded-words, as those may not appear within quotes.
+pub fn encode_display_names(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    let mut segment: usize = 0;
+    let mut pos: usize = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' => {
+                output.extend(encode_words(&input[segment..pos]));
+                let mut inner = String::new();
+                let mut end = pos + 1;
+                let mut chars = input[end..].chars();
+                while let Some(c) = chars.next() {
+                    end += c.len_utf8();
+                    match c {
+                        '"' => break,
+                        '\\' => if let Some(e) = chars.next() {
+                            end += e.len_utf8();
+                            inner.push(e);
+                        },
+                        c => inner.push(c),
+                    }
+                }
+                if input[pos..end].is_ascii() {
+                    output.extend_from_slice(&bytes[pos..end]);
+                } else {
+                    output.extend(encode(&inner));
+                }
+                pos = end;
+                segment = end;
+            },
+            b'<' | b'(' => {
+                output.extend(encode_words(&input[segment..pos]));
+                let open = bytes[pos];
+                let close = if open == b'<' { b'>' } else { b')' };
+                let mut depth: usize = 0;
+                let mut end = pos;
+                while end < bytes.len() {
+                    let c = bytes[end];
+                    end += 1;
+                    if c == b'\\' {
+                        end += 1;
+                    } else if c == open {
+                        depth += 1;
+                    } else if c == close {
+                        depth -= 1;
+                        if depth == 0 { break; }
+                    }
+                }
+                let end = ::std::cmp::min(end, bytes.len());
+                output.extend_from_slice(&bytes[pos..end]);
+                pos = end;
+                segment = end;
+            },
+            b',' | b':' | b';' => {
+                output.extend(encode_words(&input[segment..pos]));
+                output.push(bytes[pos]);
+                pos += 1;
+                segment = pos;
+            },
+            _ => pos += 1,
+        }
+    }
+    output.extend(encode_words(&input[segment..]));
+    output
+}
+
+fn push_encoded_word(output: &mut Vec<u8>, octets: &[u8]) {
+    if output.len() > 0 {
+        output.push(b' ');
+    }
+    output.extend_from_slice(PREFIX);
+    output.extend(base64_encode(octets));
+    output.extend_from_slice(SUFFIX);
+}
+
+// RFC 2047, 4.2
+// The "Q" encoding: "=" followed by two hexadecimal digits, or "_" for
+// a space, or the character itself.
+fn q_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    let mut pos: usize = 0;
+    while pos < input.len() {
+        match input[pos] {
+            b'_' => output.push(b' '),
+            b'=' => {
+                if pos + 2 >= input.len() { return None; }
+                match (hex_value(input[pos + 1]), hex_value(input[pos + 2])) {
+                    (Some(hi), Some(lo)) => output.push(hi << 4 | lo),
+                    _ => return None,
+                }
+                pos += 2;
+            },
+            c => output.push(c),
+        }
+        pos += 1;
+    }
+    Some(output)
+}
+
+// RFC 2047, 2
+// encoded-word = "=?" charset "?" encoding "?" encoded-text "?="
+// Returns None if `word` is not a well formed encoded-word in a
+// charset we understand.
+fn decode_word(word: &[u8]) -> Option<String> {
+    if word.len() < 8 || &word[..2] != b"=?" || &word[word.len() - 2..] != b"?=" {
+        return None;
+    }
+    let inner = &word[2..word.len() - 2];
+    let mut parts = inner.splitn(3, |&c| c == b'?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let text = parts.next()?;
+    if text.iter().any(|&c| c == b'?' || is_ws(c)) {
+        return None;
+    }
+    // RFC 2231 allows a language to follow the charset: charset "*" language
+    let charset = charset.split(|&c| c == b'*').next()?.to_ascii_lowercase();
+    let octets = match encoding {
+        b"B" | b"b" => base64_decode(text).ok()?,
+        b"Q" | b"q" => q_decode(text)?,
+        _ => return None,
+    };
+    decode_charset(&charset, octets)
+}
+
+// Convert `octets` in `charset` (lowercased) into a String. Returns None if
+// the charset is not understood or the octets are not valid in it.
+pub(crate) fn decode_charset(charset: &[u8], octets: Vec<u8>) -> Option<String> {
+    match charset {
+        b"utf-8" => String::from_utf8(octets).ok(),
+        // ISO-8859-1 octets are the first 256 unicode code points
+        b"iso-8859-1" | b"latin1" => Some(octets.iter().map(|&c| c as char).collect()),
+        b"us-ascii" => {
+            if octets.is_ascii() { String::from_utf8(octets).ok() } else { None }
+        },
+        _ => None,
+    }
+}
+
+/// Decode any encoded-words within `input`, such as the streamed value of a
+/// `Comments` or `OptionalField` header. The UTF-8, ISO-8859-1 and US-ASCII
+/// charsets are understood. Whitespace separating two adjacent encoded-words
+/// is removed, other whitespace is unfolded, and anything that is not a well
+/// formed encoded-word (including one in an unknown charset) is kept as
+/// literal text. Fails only if the literal text is not UTF-8.
+pub fn decode(input: &[u8]) -> Result<String, ParseError> {
+    let mut output = String::with_capacity(input.len());
+    let mut pos: usize = 0;
+    let mut previous_encoded: bool = false;
+    while pos < input.len() {
+        let ws_start = pos;
+        while pos < input.len() && is_ws(input[pos]) { pos += 1; }
+        let word_start = pos;
+        while pos < input.len() && !is_ws(input[pos]) { pos += 1; }
+        let ws = &input[ws_start..word_start];
+        let word = &input[word_start..pos];
+        let decoded = decode_word(word);
+        if !(previous_encoded && decoded.is_some()) {
+            for &c in ws {
+                if c != b'\r' && c != b'\n' { output.push(c as char); }
+            }
+        }
+        match decoded {
+            Some(text) => {
+                output.push_str(&*text);
+                previous_encoded = true;
+            },
+            None => {
+                match ::std::str::from_utf8(word) {
+                    Ok(s) => output.push_str(s),
+                    Err(_) => return Err(ParseError::ExpectedType("UTF-8")),
+                }
+                previous_encoded = false;
+            }
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,133 @@
+// Content transfer encodings defined in RFC 2045, for bodies which
+// cannot be sent as 7-bit text
+
+use super::ParseError;
+use super::types::is_wsp;
+
+// RFC 2045, 6.8  Table 1: The Base64 Alphabet
+const BASE64_ALPHABET: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[inline]
+fn base64_value(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[inline]
+pub(crate) fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        b'a'..=b'f' => Some(c - b'a' + 10), // lowercase is tolerated when decoding
+        _ => None,
+    }
+}
+
+/// Base64 encode `input`, without any line wrapping
+pub fn base64_encode(input: &[u8]) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity((input.len() + 2) / 3 * 4);
+    for chunk in input.chunks(3) {
+        let b1 = if chunk.len() > 1 { chunk[1] as u32 } else { 0 };
+        let b2 = if chunk.len() > 2 { chunk[2] as u32 } else { 0 };
+        let n: u32 = ((chunk[0] as u32) << 16) | (b1 << 8) | b2;
+        output.push(BASE64_ALPHABET[(n >> 18) as usize & 63]);
+        output.push(BASE64_ALPHABET[(n >> 12) as usize & 63]);
+        if chunk.len() > 1 {
+            output.push(BASE64_ALPHABET[(n >> 6) as usize & 63]);
+        } else {
+            output.push(b'=');
+        }
+        if chunk.len() > 2 {
+            output.push(BASE64_ALPHABET[n as usize & 63]);
+        } else {
+            output.push(b'=');
+        }
+    }
+    output
+}
+
+/// Decode base64 `input`. Line breaks and whitespace are ignored.
+pub fn base64_decode(input: &[u8]) -> Result<Vec<u8>, ParseError> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len() / 4 * 3);
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let mut padding: usize = 0;
+    for &c in input {
+        if c == b'\r' || c == b'\n' || is_wsp(c) {
+            continue;
+        }
+        if c == b'=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            return Err(ParseError::ExpectedType("Base64"));
+        }
+        let v = match base64_value(c) {
+            Some(v) => v,
+            None => return Err(ParseError::ExpectedType("Base64")),
+        };
+        acc = (acc << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // A lone trailing sextet cannot encode a whole octet
+    if bits == 6 || padding > 2 {
+        return Err(ParseError::ExpectedType("Base64"));
+    }
+    Ok(output)
+}
+
+/// Decode quoted-printable `input`, with lines separated by CRLF. Trailing
+/// whitespace on a line is transport padding and is dropped, and a trailing
+/// "=" marks a soft line break.
+pub fn quoted_printable_decode(input: &[u8]) -> Result<Vec<u8>, ParseError> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    let mut lines = input.split(|&c| c == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        let mut end = line.len();
+        if end > 0 && line[end - 1] == b'\r' {
+            end -= 1;
+        }
+        while end > 0 && is_wsp(line[end - 1]) {
+            end -= 1;
+        }
+        let line = &line[..end];
+        let mut soft_break: bool = false;
+        let mut pos: usize = 0;
+        while pos < line.len() {
+            if line[pos] != b'=' {
+                output.push(line[pos]);
+                pos += 1;
+                continue;
+            }
+            if pos + 1 == line.len() {
+                soft_break = true;
+                break;
+            }
+            if pos + 2 >= line.len() {
+                return Err(ParseError::ExpectedType("Quoted-Printable"));
+            }
+            match (hex_value(line[pos + 1]), hex_value(line[pos + 2])) {
+                (Some(hi), Some(lo)) => output.push(hi << 4 | lo),
+                _ => return Err(ParseError::ExpectedType("Quoted-Printable")),
+            }
+            pos += 3;
+        }
+        if !soft_break && lines.peek().is_some() {
+            output.extend_from_slice(b"\r\n");
+        }
+    }
+    Ok(output)
+}
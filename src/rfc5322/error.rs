@@ -11,9 +11,32 @@ pub enum ParseError {
     Io(IoError),
     InvalidBodyChar(u8),
     LineTooLong(usize),
+    LineExceedsRecommended(usize),
     TrailingInput(&'static str, usize),
     InternalError,
     Parse(&'static str, Box<ParseError>),
+    At { offset: usize, context: String, inner: Box<ParseError> },
+    DuplicateField(String),
+    LimitExceeded(&'static str),
+    HeaderInjection(usize),
+}
+
+// How many bytes either side of a failure to include in its context
+const CONTEXT_LEN: usize = 16;
+
+impl ParseError {
+    // Wrap `inner`, which occurred where `rem` begins within `input`, with its
+    // byte offset and the text surrounding it
+    pub(crate) fn at(input: &[u8], rem: &[u8], inner: ParseError) -> ParseError {
+        let offset = input.len() - rem.len();
+        let start = offset.saturating_sub(CONTEXT_LEN);
+        let end = ::std::cmp::min(input.len(), offset + CONTEXT_LEN);
+        ParseError::At {
+            offset: offset,
+            context: String::from_utf8_lossy(&input[start..end]).into_owned(),
+            inner: Box::new(inner),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -27,9 +50,17 @@ impl fmt::Display for ParseError {
             ParseError::Io(ref e) => write!(f, "I/O Error: {}", e),
             ParseError::InvalidBodyChar(ref c) => write!(f, "Invalid Body Character: {} is not 7-bit ASCII", c),
             ParseError::LineTooLong(ref l) => write!(f, "Line {} is too long", l),
+            ParseError::LineExceedsRecommended(ref l) =>
+                write!(f, "Line {} is longer than the recommended 78 characters", l),
             ParseError::TrailingInput(ref field, ref c) => write!(f, "Trailing input at byte {} in {}", c, field),
             ParseError::InternalError => write!(f, "Internal error"),
             ParseError::Parse(ref field, ref inner) => write!(f, "Unable to parse {}: {}", field, inner),
+            ParseError::At { ref offset, ref context, ref inner } =>
+                write!(f, "Failed at offset {} near {:?}: {}", offset, context, inner),
+            ParseError::DuplicateField(ref name) => write!(f, "Duplicate {} field", name),
+            ParseError::LimitExceeded(ref what) => write!(f, "Parse limit exceeded: {}", what),
+            ParseError::HeaderInjection(ref c) =>
+                write!(f, "Line terminator at byte {} of a header field value", c),
         }
     }
 }
@@ -41,4 +72,48 @@ impl fmt::Debug for ParseError {
     }
 }
 
-impl StdError for ParseError { }
+impl From<IoError> for ParseError {
+    fn from(e: IoError) -> ParseError {
+        ParseError::Io(e)
+    }
+}
+
+impl StdError for ParseError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            ParseError::Io(ref e) => Some(e),
+            ParseError::Parse(_, ref inner) => Some(&**inner),
+            ParseError::At { ref inner, .. } => Some(&**inner),
+            _ => None,
+        }
+    }
+}
+
+/// A semantic problem with an `Email` that parsing alone does not catch,
+/// as reported by `Email::validate()`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// There is no `Date` field
+    MissingDate,
+    /// There is no `From` field
+    MissingFrom,
+    /// `From` has more than one mailbox, but there is no `Sender` field
+    MissingSender,
+    /// A field that may appear at most once appears more than once
+    DuplicateField(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error>
+    {
+        match *self {
+            ValidationError::MissingDate => write!(f, "Missing Date field"),
+            ValidationError::MissingFrom => write!(f, "Missing From field"),
+            ValidationError::MissingSender =>
+                write!(f, "Missing Sender field, required when From has multiple mailboxes"),
+            ValidationError::DuplicateField(ref name) => write!(f, "Duplicate {} field", name),
+        }
+    }
+}
+
+impl StdError for ValidationError { }
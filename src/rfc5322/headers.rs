@@ -2,9 +2,39 @@
 use std::io::Write;
 use std::io::Error as IoError;
 use ::TryFrom;
-use super::{Parsable, ParseError, Streamable};
+use super::{Parsable, ParseError, Streamable, FOLD_LINE_LEN, check_line_terminators,
+            check_line_length};
+use super::email_address::EmailAddress;
 use super::types::{DateTime, MailboxList, Mailbox, AddressList, CFWS, MsgId,
-                   Unstructured, Phrase, ReceivedToken, Path, FieldName};
+                   Unstructured, Phrase, ReceivedToken, Path, FieldName, MediaType,
+                   Mechanism, Address, Disposition, LanguageTag, is_digit, is_token,
+                   is_wsp};
+
+// Implements TryFrom<Vec<EmailAddress>> for an address list header type,
+// failing on an empty list, and TryFrom<EmailAddress> for a list of one
+macro_rules! impl_try_from_email_addresses {
+    ($list:ident, $item:expr, $to:ident) => {
+        impl TryFrom<EmailAddress> for $to {
+            type Error = ParseError;
+            fn try_from(input: EmailAddress) -> Result<$to, ParseError> {
+                TryFrom::try_from(vec![input])
+            }
+        }
+        impl TryFrom<Vec<EmailAddress>> for $to {
+            type Error = ParseError;
+            fn try_from(input: Vec<EmailAddress>) -> Result<$to, ParseError> {
+                if input.len() == 0 {
+                    return Err(ParseError::NotFound("EmailAddress"));
+                }
+                let mut items = Vec::with_capacity(input.len());
+                for addr in &input {
+                    items.push($item(addr.to_mailbox()?));
+                }
+                TryFrom::try_from($list(items))
+            }
+        }
+    }
+}
 
 macro_rules! req_name {
     ($rem:ident, $str:expr) => {
@@ -35,11 +65,12 @@ macro_rules! impl_try_from {
         impl<'a> TryFrom<&'a [u8]> for $to {
             type Error = ParseError;
             fn try_from(input: &'a [u8]) -> Result<$to, ParseError> {
+                check_line_terminators(input)?;
                 let (out,rem) = $from::parse(input)?;
                 if rem.len() > 0 {
                     return Err(ParseError::TrailingInput("$to", input.len() - rem.len()));
                 }
-                Ok($to(out))
+                TryFrom::try_from(out)
             }
         }
         impl<'a> TryFrom<&'a str> for $to {
@@ -51,7 +82,9 @@ macro_rules! impl_try_from {
         impl<'a> TryFrom<$from> for $to {
             type Error = ParseError;
             fn try_from(input: $from) -> Result<$to, ParseError> {
-                Ok($to(input))
+                let out = $to(input);
+                check_line_length(&out)?;
+                Ok(out)
             }
         }
     }
@@ -104,6 +137,72 @@ impl<'a, Tz: ::chrono::TimeZone> TryFrom<&'a ::chrono::DateTime<Tz>> for OrigDat
         TryFrom::try_from(s.as_bytes())
     }
 }
+#[cfg(feature="time")]
+impl OrigDate {
+    /// Convert into a `time::Tm`, with `tm_utcoff` set from the zone of the
+    /// field. The weekday and day of the year are computed from the date.
+    pub fn to_tm(&self) -> Result<::time::Tm, ParseError> {
+        use super::types::{days_in_month, day_of_week};
+
+        let date = &(self.0).date;
+        let time = &(self.0).time;
+        let year = date.year.0;
+        let month = date.month.0;
+        let day = date.day.0;
+        if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) {
+            return Err(ParseError::ExpectedType("Date"));
+        }
+        let yday = (1..month).map(|m| days_in_month(year, m) as i32).sum::<i32>() + day as i32 - 1;
+        let wday = day_of_week(year, month, day) as i32 - 1;
+
+        Ok(::time::Tm {
+            tm_sec: time.time_of_day.second.as_ref().map_or(0, |s| s.0 as i32),
+            tm_min: time.time_of_day.minute.0 as i32,
+            tm_hour: time.time_of_day.hour.0 as i32,
+            tm_mday: day as i32,
+            tm_mon: month as i32 - 1,
+            tm_year: year as i32 - 1900,
+            tm_wday: wday,
+            tm_yday: yday,
+            tm_isdst: 0,
+            tm_utcoff: time.zone.offset_seconds(),
+            tm_nsec: 0,
+        })
+    }
+}
+#[cfg(feature="chrono")]
+impl OrigDate {
+    /// Convert into a `chrono::DateTime`, keeping the zone offset of the field
+    pub fn to_chrono(&self) -> Result<::chrono::DateTime<::chrono::FixedOffset>, ParseError> {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+        let date = &(self.0).date;
+        let time = &(self.0).time;
+        let offset = match FixedOffset::east_opt(time.zone.offset_seconds()) {
+            Some(offset) => offset,
+            None => return Err(ParseError::ExpectedType("Zone")),
+        };
+        let naive_date = match NaiveDate::from_ymd_opt(date.year.0 as i32,
+                                                       date.month.0 as u32,
+                                                       date.day.0 as u32) {
+            Some(d) => d,
+            None => return Err(ParseError::ExpectedType("Date")),
+        };
+        let hour = time.time_of_day.hour.0 as u32;
+        let minute = time.time_of_day.minute.0 as u32;
+        let second = time.time_of_day.second.as_ref().map_or(0, |s| s.0 as u32);
+        let naive = if second == 60 {
+            // chrono represents a leap second as an overlong 59th second
+            naive_date.and_hms_milli_opt(hour, minute, 59, 1000)
+        } else {
+            naive_date.and_hms_opt(hour, minute, second)
+        };
+        match naive.and_then(|n| offset.from_local_datetime(&n).single()) {
+            Some(dt) => Ok(dt),
+            None => Err(ParseError::ExpectedType("Time")),
+        }
+    }
+}
 impl_display!(OrigDate);
 
 // 3.6.2
@@ -132,6 +231,7 @@ impl Streamable for From {
     }
 }
 impl_try_from!(MailboxList, From);
+impl_try_from_email_addresses!(MailboxList, ::std::convert::identity, From);
 impl_display!(From);
 
 // 3.6.2
@@ -160,6 +260,12 @@ impl Streamable for Sender {
     }
 }
 impl_try_from!(Mailbox, Sender);
+impl TryFrom<EmailAddress> for Sender {
+    type Error = ParseError;
+    fn try_from(input: EmailAddress) -> Result<Sender, ParseError> {
+        TryFrom::try_from(input.to_mailbox()?)
+    }
+}
 impl_display!(Sender);
 
 // 3.6.2
@@ -188,6 +294,7 @@ impl Streamable for ReplyTo {
     }
 }
 impl_try_from!(AddressList, ReplyTo);
+impl_try_from_email_addresses!(AddressList, Address::Mailbox, ReplyTo);
 impl_display!(ReplyTo);
 
 // 3.6.3
@@ -216,6 +323,7 @@ impl Streamable for To {
     }
 }
 impl_try_from!(AddressList, To);
+impl_try_from_email_addresses!(AddressList, Address::Mailbox, To);
 impl_display!(To);
 
 // 3.6.3
@@ -244,6 +352,7 @@ impl Streamable for Cc {
     }
 }
 impl_try_from!(AddressList, Cc);
+impl_try_from_email_addresses!(AddressList, Address::Mailbox, Cc);
 impl_display!(Cc);
 
 // 3.6.3
@@ -287,11 +396,12 @@ impl Streamable for Bcc {
 impl<'a> TryFrom<&'a [u8]> for Bcc {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<Bcc, ParseError> {
+        check_line_terminators(input)?;
         let (out,rem) = AddressList::parse(input)?;
         if rem.len() > 0 {
             return Err(ParseError::TrailingInput("Bcc", input.len() - rem.len()));
         }
-        Ok(Bcc::AddressList(out))
+        TryFrom::try_from(out)
     }
 }
 impl<'a> TryFrom<&'a str> for Bcc {
@@ -303,7 +413,9 @@ impl<'a> TryFrom<&'a str> for Bcc {
 impl<'a> TryFrom<AddressList> for Bcc {
     type Error = ParseError;
     fn try_from(input: AddressList) -> Result<Bcc, ParseError> {
-        Ok(Bcc::AddressList(input))
+        let out = Bcc::AddressList(input);
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(Bcc);
@@ -336,6 +448,68 @@ impl Streamable for MessageId {
 impl_try_from!(MsgId, MessageId);
 impl_display!(MessageId);
 
+// Give each id with nothing between it and the next a single space after it,
+// so that the space is part of the value rather than added when streaming,
+// and the field parses back as it was set
+fn separate_msg_ids(mut msgids: Vec<MsgId>) -> Vec<MsgId> {
+    for i in 1..msgids.len() {
+        if msgids[i - 1].post_cfws.is_none() && msgids[i].pre_cfws.is_none() {
+            msgids[i - 1].post_cfws = Some(CFWS { comments: Vec::new(), trailing_ws: true });
+        }
+    }
+    msgids
+}
+
+// Stream `name` and then `msgids`, folding between them so that lines stay
+// within 78 characters where possible. Whitespace between two ids is
+// replaced by a single space or a fold. Comments between them are kept, and
+// ids with nothing between them are streamed with nothing between them.
+fn stream_msg_ids<W: Write>(name: &[u8], msgids: &[MsgId], w: &mut W)
+                            -> Result<usize, IoError>
+{
+    fn cfws_bytes(cfws: &Option<CFWS>) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        if let Some(ref cfws) = *cfws {
+            let _ = cfws.stream(&mut output); // no IoError ought to occur.
+        }
+        output
+    }
+    // Write `bytes`, keeping track of the length of the current line
+    fn write<W: Write>(bytes: &[u8], line_len: &mut usize, w: &mut W)
+                       -> Result<usize, IoError>
+    {
+        match bytes.iter().rposition(|&c| c == b'\n') {
+            Some(p) => *line_len = bytes.len() - p - 1,
+            None => *line_len += bytes.len(),
+        }
+        Ok(w.write(bytes)?)
+    }
+    let mut count: usize = w.write(name)?;
+    let mut line_len: usize = name.len();
+    for (i, msgid) in msgids.iter().enumerate() {
+        let id = format!("<{}@{}>", msgid.id_left, msgid.id_right).into_bytes();
+        if i == 0 {
+            count += write(&cfws_bytes(&msgid.pre_cfws), &mut line_len, w)?;
+        } else {
+            let mut gap = cfws_bytes(&msgids[i - 1].post_cfws);
+            gap.extend(cfws_bytes(&msgid.pre_cfws));
+            if !gap.is_empty() && gap.iter().all(|&c| is_wsp(c) || c == b'\r' || c == b'\n') {
+                gap = if line_len + 1 + id.len() > FOLD_LINE_LEN {
+                    b"\r\n ".to_vec()
+                } else {
+                    b" ".to_vec()
+                };
+            }
+            count += write(&gap, &mut line_len, w)?;
+        }
+        count += write(&id, &mut line_len, w)?;
+    }
+    if let Some(msgid) = msgids.last() {
+        count += write(&cfws_bytes(&msgid.post_cfws), &mut line_len, w)?;
+    }
+    Ok(count)
+}
+
 // 3.6.4
 // in-reply-to     =   "In-Reply-To:" 1*msg-id CRLF
 #[derive(Debug, Clone, PartialEq)]
@@ -357,16 +531,13 @@ impl Parsable for InReplyTo {
             return Err(ParseError::Parse("In-Reply-To", Box::new(err)));
         }
         req_crlf!(rem);
-        Ok((InReplyTo(contents), rem))
+        Ok((InReplyTo(separate_msg_ids(contents)), rem))
     }
 }
 impl Streamable for InReplyTo {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
         let mut count: usize = 0;
-        count += w.write(b"In-Reply-To:")?;
-        for msgid in &self.0 {
-            count += msgid.stream(w)?
-        }
+        count += stream_msg_ids(b"In-Reply-To:", &self.0, w)?;
         count += w.write(b"\r\n")?;
         Ok(count)
     }
@@ -374,6 +545,7 @@ impl Streamable for InReplyTo {
 impl<'a> TryFrom<&'a [u8]> for InReplyTo {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<InReplyTo, ParseError> {
+        check_line_terminators(input)?;
         let mut msgids: Vec<MsgId> = Vec::new();
         let mut rem = input;
         while let Ok(x) = parse!(MsgId, rem) {
@@ -382,7 +554,7 @@ impl<'a> TryFrom<&'a [u8]> for InReplyTo {
         if rem.len() > 0 {
             Err(ParseError::TrailingInput("In-Reply-To", input.len() - rem.len()))
         } else {
-            Ok(InReplyTo(msgids))
+            TryFrom::try_from(msgids)
         }
     }
 }
@@ -395,10 +567,18 @@ impl<'a> TryFrom<&'a str> for InReplyTo {
 impl<'a> TryFrom<Vec<MsgId>> for InReplyTo {
     type Error = ParseError;
     fn try_from(input: Vec<MsgId>) -> Result<InReplyTo, ParseError> {
-        Ok(InReplyTo(input))
+        let out = InReplyTo(separate_msg_ids(input));
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(InReplyTo);
+impl InReplyTo {
+    /// Each message id in the field, as `MsgId::id()` gives it
+    pub fn ids(&self) -> Vec<String> {
+        self.0.iter().map(|id| id.id()).collect()
+    }
+}
 
 // 3.6.4
 // references      =   "References:" 1*msg-id CRLF
@@ -421,16 +601,13 @@ impl Parsable for References {
             return Err(ParseError::Parse("References", Box::new(err)));
         }
         req_crlf!(rem);
-        Ok((References(contents), rem))
+        Ok((References(separate_msg_ids(contents)), rem))
     }
 }
 impl Streamable for References {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
         let mut count: usize = 0;
-        count += w.write(b"References:")?;
-        for msgid in &self.0 {
-            count += msgid.stream(w)?
-        }
+        count += stream_msg_ids(b"References:", &self.0, w)?;
         count += w.write(b"\r\n")?;
         Ok(count)
     }
@@ -438,6 +615,7 @@ impl Streamable for References {
 impl<'a> TryFrom<&'a [u8]> for References {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<References, ParseError> {
+        check_line_terminators(input)?;
         let mut msgids: Vec<MsgId> = Vec::new();
         let mut rem = input;
         while let Ok(x) = parse!(MsgId, rem) {
@@ -446,7 +624,7 @@ impl<'a> TryFrom<&'a [u8]> for References {
         if rem.len() > 0 {
             Err(ParseError::TrailingInput("References", input.len() - rem.len()))
         } else {
-            Ok(References(msgids))
+            TryFrom::try_from(msgids)
         }
     }
 }
@@ -459,10 +637,18 @@ impl<'a> TryFrom<&'a str> for References {
 impl<'a> TryFrom<Vec<MsgId>> for References {
     type Error = ParseError;
     fn try_from(input: Vec<MsgId>) -> Result<References, ParseError> {
-        Ok(References(input))
+        let out = References(separate_msg_ids(input));
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(References);
+impl References {
+    /// Each message id in the field, as `MsgId::id()` gives it
+    pub fn ids(&self) -> Vec<String> {
+        self.0.iter().map(|id| id.id()).collect()
+    }
+}
 
 // 3.6.5
 // subject         =   "Subject:" unstructured CRLF
@@ -491,6 +677,21 @@ impl Streamable for Subject {
 }
 impl_try_from!(Unstructured, Subject);
 impl_display!(Subject);
+impl Subject {
+    /// The text of the field, as `Unstructured::to_text()` gives it
+    pub fn text(&self) -> String {
+        self.0.to_text()
+    }
+
+    /// A copy of this subject without whitespace before or after its text
+    pub fn trimmed(&self) -> Subject {
+        Subject(Unstructured {
+            leading_ws: false,
+            parts: self.0.parts.clone(),
+            trailing_ws: false,
+        })
+    }
+}
 
 // 3.6.5
 // comments        =   "Comments:" unstructured CRLF
@@ -519,9 +720,17 @@ impl Streamable for Comments {
 }
 impl_try_from!(Unstructured, Comments);
 impl_display!(Comments);
+impl Comments {
+    /// The text of the field, as `Unstructured::to_text()` gives it
+    pub fn text(&self) -> String {
+        self.0.to_text()
+    }
+}
 
 // 3.6.5
 // keywords        =   "Keywords:" phrase *("," phrase) CRLF
+// Empty entries, such as those left by a trailing comma or holding only a
+// comment, are tolerated and skipped.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Keywords(pub Vec<Phrase>);
 impl Parsable for Keywords {
@@ -529,21 +738,38 @@ impl Parsable for Keywords {
         if input.len() == 0 { return Err(ParseError::Eof("Keywords")); }
         let mut rem = input;
         req_name!(rem, "keywords:");
-        let mut output: Vec<Phrase> = Vec::new();
-        let err;
-        loop {
-            match parse!(Phrase, rem) {
-                Ok(x) => output.push(x),
-                Err(e) => { err = e; break; }
-            }
-        }
+        let (output, r) = parse_keyword_list(rem);
+        rem = r;
         if output.len()==0 {
-            return Err(ParseError::Parse("Keywords", Box::new(err)));
+            return Err(ParseError::Parse("Keywords", Box::new(ParseError::NotFound("Phrase"))));
         }
         req_crlf!(rem);
         Ok((Keywords(output), rem))
     }
 }
+impl Keywords {
+    /// The keywords as plain text, as `Phrase::text()` gives them
+    pub fn words(&self) -> Vec<String> {
+        self.0.iter().map(|phrase| phrase.text()).collect()
+    }
+}
+// Parse phrases separated by commas, skipping empty entries
+fn parse_keyword_list(input: &[u8]) -> (Vec<Phrase>, &[u8]) {
+    let mut rem = input;
+    let mut output: Vec<Phrase> = Vec::new();
+    loop {
+        if let Ok(x) = parse!(Phrase, rem) {
+            output.push(x);
+        }
+        let mut rem2 = rem;
+        let _ = parse!(CFWS, rem2);
+        if rem2.len() > 0 && rem2[0] == b',' {
+            rem = &rem2[1..];
+            continue;
+        }
+        return (output, rem2);
+    }
+}
 impl Streamable for Keywords {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
         let mut count: usize = 0;
@@ -563,15 +789,12 @@ impl Streamable for Keywords {
 impl<'a> TryFrom<&'a [u8]> for Keywords {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<Keywords, ParseError> {
-        let mut msgids: Vec<Phrase> = Vec::new();
-        let mut rem = input;
-        while let Ok(x) = parse!(Phrase, rem) {
-            msgids.push(x);
-        }
+        check_line_terminators(input)?;
+        let (phrases, rem) = parse_keyword_list(input);
         if rem.len() > 0 {
             Err(ParseError::TrailingInput("Keywords", input.len() - rem.len()))
         } else {
-            Ok(Keywords(msgids))
+            TryFrom::try_from(phrases)
         }
     }
 }
@@ -584,7 +807,9 @@ impl<'a> TryFrom<&'a str> for Keywords {
 impl<'a> TryFrom<Vec<Phrase>> for Keywords {
     type Error = ParseError;
     fn try_from(input: Vec<Phrase>) -> Result<Keywords, ParseError> {
-        Ok(Keywords(input))
+        let out = Keywords(input);
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(Keywords);
@@ -770,11 +995,12 @@ impl Streamable for ResentBcc {
 impl<'a> TryFrom<&'a [u8]> for ResentBcc {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<ResentBcc, ParseError> {
+        check_line_terminators(input)?;
         let (out,rem) = AddressList::parse(input)?;
         if rem.len() > 0 {
             return Err(ParseError::TrailingInput("Resent-Bcc", input.len() - rem.len()));
         }
-        Ok(ResentBcc::AddressList(out))
+        TryFrom::try_from(out)
     }
 }
 impl<'a> TryFrom<&'a str> for ResentBcc {
@@ -786,7 +1012,9 @@ impl<'a> TryFrom<&'a str> for ResentBcc {
 impl<'a> TryFrom<AddressList> for ResentBcc {
     type Error = ParseError;
     fn try_from(input: AddressList) -> Result<ResentBcc, ParseError> {
-        Ok(ResentBcc::AddressList(input))
+        let out = ResentBcc::AddressList(input);
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(ResentBcc);
@@ -892,15 +1120,16 @@ impl Streamable for Received {
 impl<'a> TryFrom<&'a [u8]> for Received {
     type Error = ParseError;
     fn try_from(input: &'a [u8]) -> Result<Received, ParseError> {
+        check_line_terminators(input)?;
         let mut fudged_input: Vec<u8> = "Received:".as_bytes().to_owned();
         fudged_input.extend(&*input);
         fudged_input.extend("\r\n".as_bytes());
-        let (out,rem) = Received::parse(input)?;
+        let (out,rem) = Received::parse(&*fudged_input)?;
         if rem.len() > 0 {
-            return Err(ParseError::TrailingInput("Received", input.len() - rem.len()));
-        } else {
-            Ok(out)
+            return Err(ParseError::TrailingInput("Received", fudged_input.len() - rem.len()));
         }
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl<'a> TryFrom<&'a str> for Received {
@@ -912,9 +1141,11 @@ impl<'a> TryFrom<&'a str> for Received {
 impl<'a> TryFrom<(ReceivedTokens, DateTime)> for Received {
     type Error = ParseError;
     fn try_from(input: (ReceivedTokens, DateTime)) -> Result<Received, ParseError> {
-        Ok(Received {
+        let out = Received {
             received_tokens: input.0,
-            date_time: input.1 })
+            date_time: input.1 };
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl_display!(Received);
@@ -986,14 +1217,18 @@ impl Streamable for OptionalField {
 impl<'a> TryFrom<(FieldName, Unstructured)> for OptionalField {
     type Error = ParseError;
     fn try_from(input: (FieldName, Unstructured)) -> Result<OptionalField, ParseError> {
-        Ok(OptionalField {
+        let out = OptionalField {
             name: input.0,
-            value: input.1 })
+            value: input.1 };
+        check_line_length(&out)?;
+        Ok(out)
     }
 }
 impl<'a,'b> TryFrom<(&'a [u8], &'b [u8])> for OptionalField {
     type Error = ParseError;
     fn try_from(input: (&'a [u8], &'b [u8])) -> Result<OptionalField, ParseError> {
+        check_line_terminators(input.0)?;
+        check_line_terminators(input.1)?;
         let (name,rem) = FieldName::parse(input.0)?;
         if rem.len() > 0 {
             return Err(ParseError::TrailingInput("Optional Field", input.0.len() - rem.len()));
@@ -1002,10 +1237,7 @@ impl<'a,'b> TryFrom<(&'a [u8], &'b [u8])> for OptionalField {
         if rem.len() > 0 {
             return Err(ParseError::TrailingInput("Optional Field", input.1.len() - rem.len()));
         }
-        Ok(OptionalField {
-            name: name,
-            value: value,
-        })
+        TryFrom::try_from((name, value))
     }
 }
 impl<'a,'b> TryFrom<(&'a str, &'b str)> for OptionalField {
@@ -1015,3 +1247,369 @@ impl<'a,'b> TryFrom<(&'a str, &'b str)> for OptionalField {
     }
 }
 impl_display!(OptionalField);
+
+// RFC 2045, 5.1
+// content         =   "Content-Type" ":" type "/" subtype
+//                     *(";" parameter)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentType(pub MediaType);
+impl Parsable for ContentType {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Content-Type")); }
+        let mut rem = input;
+        req_name!(rem, "content-type:");
+        match parse!(MediaType, rem) {
+            Ok(x) => {
+                req_crlf!(rem);
+                return Ok((ContentType(x), rem));
+            },
+            Err(e) => Err(ParseError::Parse("Content-Type", Box::new(e))),
+        }
+    }
+}
+impl Streamable for ContentType {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        Ok(w.write(b"Content-Type:")?
+           + self.0.stream(w)?
+           + w.write(b"\r\n")?)
+    }
+}
+impl_try_from!(MediaType, ContentType);
+impl_display!(ContentType);
+
+// RFC 2183, 2
+// disposition := "Content-Disposition" ":"
+//                disposition-type
+//                *(";" disposition-parm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentDisposition(pub Disposition);
+impl Parsable for ContentDisposition {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Content-Disposition")); }
+        let mut rem = input;
+        req_name!(rem, "content-disposition:");
+        match parse!(Disposition, rem) {
+            Ok(x) => {
+                req_crlf!(rem);
+                return Ok((ContentDisposition(x), rem));
+            },
+            Err(e) => Err(ParseError::Parse("Content-Disposition", Box::new(e))),
+        }
+    }
+}
+impl Streamable for ContentDisposition {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        Ok(w.write(b"Content-Disposition:")?
+           + self.0.stream(w)?
+           + w.write(b"\r\n")?)
+    }
+}
+impl ContentDisposition {
+    /// An `attachment` disposition with a `filename` parameter. A filename
+    /// that is not printable ASCII is encoded as RFC 2231 describes, split
+    /// into continuations if it is long.
+    pub fn attachment(filename: &str) -> Result<ContentDisposition, ParseError> {
+        let mut value = String::from("attachment");
+        if filename.bytes().all(|c| c >= 0x20 && c < 0x7f) {
+            value.push_str("; filename=\"");
+            for c in filename.chars() {
+                if c == '"' || c == '\\' { value.push('\\'); }
+                value.push(c);
+            }
+            value.push('"');
+        } else {
+            // RFC 2231, 7
+            // attribute-char := <any (US-ASCII) CHAR except SPACE, CTLs,
+            //                   "*", "'", "%", or tspecials>
+            let mut units: Vec<String> = Vec::new();
+            for c in filename.bytes() {
+                if is_token(c) && c != b'*' && c != b'\'' && c != b'%' {
+                    units.push((c as char).to_string());
+                } else {
+                    units.push(format!("%{:02X}", c));
+                }
+            }
+            let mut sections: Vec<String> = vec![String::from("utf-8''")];
+            for unit in units {
+                if sections.last().map_or(0, |s| s.len()) + unit.len() > 60 {
+                    sections.push(String::new());
+                }
+                if let Some(section) = sections.last_mut() {
+                    section.push_str(&*unit);
+                }
+            }
+            if sections.len() == 1 {
+                value.push_str(&*format!("; filename*={}", sections[0]));
+            } else {
+                for (i, section) in sections.iter().enumerate() {
+                    value.push_str(&*format!("; filename*{}*={}", i, section));
+                }
+            }
+        }
+        TryFrom::try_from(&*value)
+    }
+}
+impl_try_from!(Disposition, ContentDisposition);
+impl_display!(ContentDisposition);
+
+// RFC 3282, 2
+// Content-Language = "Content-Language" ":" 1#Language-tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLanguage(pub Vec<LanguageTag>);
+impl ContentLanguage {
+    // 1#Language-tag, as a comma separated list
+    fn parse_value(input: &[u8]) -> Result<(ContentLanguage, &[u8]), ParseError> {
+        let mut rem = input;
+        let mut tags: Vec<LanguageTag> = Vec::new();
+        tags.push(parse!(LanguageTag, rem)?);
+        while rem.len() > 0 && rem[0]==b',' {
+            let mut rem2 = &rem[1..];
+            match parse!(LanguageTag, rem2) {
+                Ok(tag) => {
+                    tags.push(tag);
+                    rem = rem2;
+                },
+                Err(_) => break,
+            }
+        }
+        Ok((ContentLanguage(tags), rem))
+    }
+
+    /// The language tags, such as `en-US`, in the order given
+    pub fn tags(&self) -> Vec<String> {
+        self.0.iter().map(|t| t.tag.clone()).collect()
+    }
+}
+impl Parsable for ContentLanguage {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Content-Language")); }
+        let mut rem = input;
+        req_name!(rem, "content-language:");
+        match ContentLanguage::parse_value(rem) {
+            Ok((x, r)) => {
+                rem = r;
+                req_crlf!(rem);
+                Ok((x, rem))
+            },
+            Err(e) => Err(ParseError::Parse("Content-Language", Box::new(e))),
+        }
+    }
+}
+impl Streamable for ContentLanguage {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += w.write(b"Content-Language:")?;
+        for (i, tag) in self.0.iter().enumerate() {
+            if i > 0 {
+                count += w.write(b",")?;
+            }
+            count += tag.stream(w)?;
+        }
+        count += w.write(b"\r\n")?;
+        Ok(count)
+    }
+}
+impl<'a> TryFrom<&'a [u8]> for ContentLanguage {
+    type Error = ParseError;
+    fn try_from(input: &'a [u8]) -> Result<ContentLanguage, ParseError> {
+        check_line_terminators(input)?;
+        let (out, rem) = ContentLanguage::parse_value(input)?;
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("Content-Language", input.len() - rem.len()));
+        }
+        check_line_length(&out)?;
+        Ok(out)
+    }
+}
+impl<'a> TryFrom<&'a str> for ContentLanguage {
+    type Error = ParseError;
+    fn try_from(input: &'a str) -> Result<ContentLanguage, ParseError> {
+        TryFrom::try_from(input.as_bytes())
+    }
+}
+impl_display!(ContentLanguage);
+
+// RFC 2045, 6.1
+// encoding        =   "Content-Transfer-Encoding" ":" mechanism
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentTransferEncoding {
+    pub pre_cfws: Option<CFWS>,
+    pub mechanism: Mechanism,
+    pub post_cfws: Option<CFWS>,
+}
+impl Parsable for ContentTransferEncoding {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Content-Transfer-Encoding")); }
+        let mut rem = input;
+        req_name!(rem, "content-transfer-encoding:");
+        let pre_cfws = parse!(CFWS, rem);
+        match parse!(Mechanism, rem) {
+            Ok(x) => {
+                let post_cfws = parse!(CFWS, rem);
+                req_crlf!(rem);
+                return Ok((ContentTransferEncoding {
+                    pre_cfws: pre_cfws.ok(),
+                    mechanism: x,
+                    post_cfws: post_cfws.ok(),
+                }, rem));
+            },
+            Err(e) => Err(ParseError::Parse("Content-Transfer-Encoding", Box::new(e))),
+        }
+    }
+}
+impl Streamable for ContentTransferEncoding {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += w.write(b"Content-Transfer-Encoding:")?;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += self.mechanism.stream(w)?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += w.write(b"\r\n")?;
+        Ok(count)
+    }
+}
+impl<'a> TryFrom<&'a [u8]> for ContentTransferEncoding {
+    type Error = ParseError;
+    fn try_from(input: &'a [u8]) -> Result<ContentTransferEncoding, ParseError> {
+        check_line_terminators(input)?;
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        let mechanism = parse!(Mechanism, rem)?;
+        let post_cfws = parse!(CFWS, rem);
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("Content-Transfer-Encoding",
+                                                 input.len() - rem.len()));
+        }
+        Ok(ContentTransferEncoding {
+            pre_cfws: pre_cfws.ok(),
+            mechanism: mechanism,
+            post_cfws: post_cfws.ok(),
+        })
+    }
+}
+impl<'a> TryFrom<&'a str> for ContentTransferEncoding {
+    type Error = ParseError;
+    fn try_from(input: &'a str) -> Result<ContentTransferEncoding, ParseError> {
+        TryFrom::try_from(input.as_bytes())
+    }
+}
+impl TryFrom<Mechanism> for ContentTransferEncoding {
+    type Error = ParseError;
+    fn try_from(input: Mechanism) -> Result<ContentTransferEncoding, ParseError> {
+        Ok(ContentTransferEncoding {
+            pre_cfws: None,
+            mechanism: input,
+            post_cfws: None,
+        })
+    }
+}
+impl_display!(ContentTransferEncoding);
+
+// RFC 2045, 4
+// version := "MIME-Version" ":" 1*DIGIT "." 1*DIGIT
+// (comments may appear before and after the version number)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimeVersion {
+    pub pre_cfws: Option<CFWS>,
+    pub major: u32,
+    pub minor: u32,
+    pub post_cfws: Option<CFWS>,
+}
+// 1*DIGIT
+fn version_number(input: &[u8]) -> Result<(u32, &[u8]), ParseError> {
+    let len = input.iter().take_while(|&&c| is_digit(c)).count();
+    if len == 0 {
+        return Err(ParseError::NotFound("MIME Version Number"));
+    }
+    let mut value: u32 = 0;
+    for &c in &input[..len] {
+        value = match value.checked_mul(10).and_then(|v| v.checked_add((c - b'0') as u32)) {
+            Some(v) => v,
+            None => return Err(ParseError::ExpectedType("MIME Version Number")),
+        };
+    }
+    Ok((value, &input[len..]))
+}
+impl MimeVersion {
+    // [CFWS] 1*DIGIT "." 1*DIGIT [CFWS]
+    fn parse_value(input: &[u8]) -> Result<(MimeVersion, &[u8]), ParseError> {
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        let (major, r) = version_number(rem)?;
+        rem = r;
+        req!(rem, b".", input);
+        let (minor, r) = version_number(rem)?;
+        rem = r;
+        let post_cfws = parse!(CFWS, rem);
+        Ok((MimeVersion {
+            pre_cfws: pre_cfws.ok(),
+            major: major,
+            minor: minor,
+            post_cfws: post_cfws.ok(),
+        }, rem))
+    }
+}
+/// MIME version 1.0, the only version defined
+impl Default for MimeVersion {
+    fn default() -> MimeVersion {
+        MimeVersion { pre_cfws: None, major: 1, minor: 0, post_cfws: None }
+    }
+}
+impl Parsable for MimeVersion {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("MIME-Version")); }
+        let mut rem = input;
+        req_name!(rem, "mime-version:");
+        match MimeVersion::parse_value(rem) {
+            Ok((x, r)) => {
+                rem = r;
+                req_crlf!(rem);
+                Ok((x, rem))
+            },
+            Err(e) => Err(ParseError::Parse("MIME-Version", Box::new(e))),
+        }
+    }
+}
+impl Streamable for MimeVersion {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        count += w.write(b"MIME-Version:")?;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += w.write(format!("{}.{}", self.major, self.minor).as_bytes())?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += w.write(b"\r\n")?;
+        Ok(count)
+    }
+}
+impl<'a> TryFrom<&'a [u8]> for MimeVersion {
+    type Error = ParseError;
+    fn try_from(input: &'a [u8]) -> Result<MimeVersion, ParseError> {
+        check_line_terminators(input)?;
+        let (version, rem) = MimeVersion::parse_value(input)?;
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("MIME-Version", input.len() - rem.len()));
+        }
+        Ok(version)
+    }
+}
+impl<'a> TryFrom<&'a str> for MimeVersion {
+    type Error = ParseError;
+    fn try_from(input: &'a str) -> Result<MimeVersion, ParseError> {
+        TryFrom::try_from(input.as_bytes())
+    }
+}
+impl TryFrom<(u32, u32)> for MimeVersion {
+    type Error = ParseError;
+    fn try_from(input: (u32, u32)) -> Result<MimeVersion, ParseError> {
+        Ok(MimeVersion { pre_cfws: None, major: input.0, minor: input.1, post_cfws: None })
+    }
+}
+impl_display!(MimeVersion);
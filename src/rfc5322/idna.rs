@@ -0,0 +1,188 @@
+// Internationalized domain names, converted to and from their
+// ASCII-compatible encoding as defined in RFC 3490 and RFC 3492
+
+use super::ParseError;
+
+// RFC 3490, 5
+// The ACE prefix ... is the string "xn--"
+const ACE_PREFIX: &'static str = "xn--";
+
+// RFC 3492, 5
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+const DELIMITER: char = '-';
+
+// RFC 3492, 6.1
+fn adapt(mut delta: u32, numpoints: u32, firsttime: bool) -> u32 {
+    delta = if firsttime { delta / DAMP } else { delta / 2 };
+    delta += delta / numpoints;
+    let mut k: u32 = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+    k + (((BASE - TMIN + 1) * delta) / (delta + SKEW))
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias { TMIN }
+    else if k >= bias + TMAX { TMAX }
+    else { k - bias }
+}
+
+fn encode_digit(d: u32) -> char {
+    if d < 26 { (b'a' + d as u8) as char } else { (b'0' + (d - 26) as u8) as char }
+}
+
+fn decode_digit(c: char) -> Option<u32> {
+    if c>='a' && c<='z' { Some(c as u32 - 'a' as u32) }
+    else if c>='A' && c<='Z' { Some(c as u32 - 'A' as u32) }
+    else if c>='0' && c<='9' { Some(c as u32 - '0' as u32 + 26) }
+    else { None }
+}
+
+// RFC 3492, 6.3
+fn punycode_encode(input: &str) -> Option<String> {
+    let input: Vec<u32> = input.chars().map(|c| c as u32).collect();
+    let mut output: String = input.iter().filter(|&&c| c < 0x80)
+        .map(|&c| c as u8 as char).collect();
+    let basic = output.len() as u32;
+    let mut handled = basic;
+    if basic > 0 {
+        output.push(DELIMITER);
+    }
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    while (handled as usize) < input.len() {
+        let m = *input.iter().filter(|&&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+        for &c in &input {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t { break; }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+// RFC 3492, 6.2
+fn punycode_decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind(DELIMITER) {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = extended.chars();
+    loop {
+        let mut c = match digits.next() {
+            Some(c) => c,
+            None => break,
+        };
+        let oldi = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = decode_digit(c)?;
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = threshold(k, bias);
+            if digit < t { break; }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+            c = digits.next()?;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - oldi, len, oldi == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, ::std::char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+/// Convert a domain such as `例え.jp` into its ASCII-compatible form
+/// `xn--r8jz45g.jp`, so that it may be used within an addr-spec. Labels
+/// which are not ASCII are lowercased and punycode encoded, others are kept
+/// as they are. No other nameprep mapping or normalization is done.
+pub fn to_ascii(domain: &str) -> Result<String, ParseError> {
+    let mut labels: Vec<String> = Vec::new();
+    for label in domain.split('.') {
+        if label.is_ascii() {
+            labels.push(label.to_owned());
+        } else {
+            match punycode_encode(&*label.to_lowercase()) {
+                Some(encoded) => labels.push(format!("{}{}", ACE_PREFIX, encoded)),
+                None => return Err(ParseError::ExpectedType("IDNA domain")),
+            }
+        }
+    }
+    Ok(labels.join("."))
+}
+
+/// Convert a domain in ASCII-compatible form such as `xn--r8jz45g.jp` back
+/// into unicode for display, e.g. `例え.jp`. Fails if an `xn--` label is not
+/// valid punycode.
+pub fn from_ascii(domain: &str) -> Result<String, ParseError> {
+    let mut labels: Vec<String> = Vec::new();
+    for label in domain.split('.') {
+        let is_ace = label.len() > ACE_PREFIX.len() && label.get(..ACE_PREFIX.len())
+            .map_or(false, |prefix| prefix.eq_ignore_ascii_case(ACE_PREFIX));
+        if is_ace {
+            match punycode_decode(&label[ACE_PREFIX.len()..]) {
+                Some(decoded) => labels.push(decoded),
+                None => return Err(ParseError::ExpectedType("IDNA domain")),
+            }
+        } else {
+            labels.push(label.to_owned());
+        }
+    }
+    Ok(labels.join("."))
+}
+
+/// Convert the domain of each address within a mailbox or address list
+/// such as `"User <user@例え.jp>"` into its ASCII-compatible form. The
+/// domain is taken to run from an `@` up to whitespace or one of `> , ; )`.
+pub fn addresses_to_ascii(input: &str) -> Result<String, ParseError> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(at) = rest.find('@') {
+        output.push_str(&rest[..at + 1]);
+        rest = &rest[at + 1..];
+        let end = rest.find(|c: char| c.is_whitespace() || ">,;)".contains(c))
+            .unwrap_or(rest.len());
+        output.push_str(&*to_ascii(&rest[..end])?);
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
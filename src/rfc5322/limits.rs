@@ -0,0 +1,106 @@
+// Limits on the size of parsed input, so that pathological messages fail
+// with an error rather than exhausting the stack or memory
+
+use std::cell::Cell;
+use super::ParseError;
+
+/// Limits enforced while parsing, as used by `Email::parse_with_limits()`.
+/// `Email::parse()` uses the defaults, which are generous enough for any
+/// real message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// How deeply comments may nest within one another
+    pub max_comment_depth: usize,
+    /// How many addresses a single mailbox or address list may hold
+    pub max_addresses: usize,
+    /// How many header fields a message may have, counting each trace block
+    /// as one
+    pub max_fields: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_comment_depth: 64,
+            max_addresses: 10_000,
+            max_fields: 10_000,
+        }
+    }
+}
+
+thread_local! {
+    static LIMITS: Cell<ParseLimits> = Cell::new(ParseLimits::default());
+    static COMMENT_DEPTH: Cell<usize> = Cell::new(0);
+    // The first limit exceeded. Parsers which fall back to another
+    // alternative on failure would otherwise lose the error.
+    static EXCEEDED: Cell<Option<&'static str>> = Cell::new(None);
+}
+
+pub(crate) fn current() -> ParseLimits {
+    LIMITS.with(|l| l.get())
+}
+
+// Record that the limit on `what` was exceeded, and return the error for it
+pub(crate) fn exceeded(what: &'static str) -> ParseError {
+    EXCEEDED.with(|e| if e.get().is_none() { e.set(Some(what)) });
+    ParseError::LimitExceeded(what)
+}
+
+// The first limit exceeded so far, if any
+pub(crate) fn tripped() -> Option<&'static str> {
+    EXCEEDED.with(|e| e.get())
+}
+
+// Fail with the limit exceeded while parsing from `rem` within `input`,
+// located there, if one has been exceeded since `tripped()` returned `before`
+pub(crate) fn check_at(input: &[u8], rem: &[u8], before: Option<&'static str>)
+                       -> Result<(), ParseError>
+{
+    match (before, tripped()) {
+        (None, Some(what)) => Err(ParseError::at(input, rem, ParseError::LimitExceeded(what))),
+        _ => Ok(()),
+    }
+}
+
+// Enter a comment, failing if that nests comments too deeply. Each
+// successful call must be matched by a call to `leave_comment()`.
+pub(crate) fn enter_comment() -> Result<(), ParseError> {
+    let depth = COMMENT_DEPTH.with(|d| d.get());
+    if depth >= current().max_comment_depth {
+        return Err(exceeded("Comment Depth"));
+    }
+    COMMENT_DEPTH.with(|d| d.set(depth + 1));
+    Ok(())
+}
+
+pub(crate) fn leave_comment() {
+    COMMENT_DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+}
+
+// Run the parse `f`, failing with `ParseError::LimitExceeded` if any limit
+// was exceeded along the way, even where `f` itself recovered. A failure
+// which `f` has already located with `check_at()` is kept as it is.
+pub(crate) fn checked<T, F>(f: F) -> Result<T, ParseError>
+    where F: FnOnce() -> Result<T, ParseError>
+{
+    let outer = EXCEEDED.with(|e| e.replace(None));
+    let result = f();
+    let what = EXCEEDED.with(|e| e.replace(outer.or(e.get())));
+    match what {
+        Some(what) => match result {
+            Err(e @ ParseError::At { .. }) => Err(e),
+            _ => Err(ParseError::LimitExceeded(what)),
+        },
+        None => result,
+    }
+}
+
+// Run `f` with `limits` in force
+pub(crate) fn with_limits<T, F>(limits: ParseLimits, f: F) -> T
+    where F: FnOnce() -> T
+{
+    let previous = LIMITS.with(|l| l.replace(limits));
+    let result = f();
+    LIMITS.with(|l| l.set(previous));
+    result
+}
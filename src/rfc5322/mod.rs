@@ -73,12 +73,19 @@ macro_rules! impl_display {
 }
 
 pub mod error;
-pub use self::error::ParseError;
+pub use self::error::{ParseError, ValidationError};
 pub mod types;
 pub mod headers;
 pub mod email_address;
+pub mod encoding;
+pub mod encoded_word;
+pub mod idna;
+pub mod dkim;
+pub mod multipart;
+pub mod limits;
+pub mod authres;
 
-use std::io::Write;
+use std::io::{Write, BufRead};
 use std::io::Error as IoError;
 use buf_read_ext::BufReadExt;
 use ::TryFrom;
@@ -87,6 +94,9 @@ use self::headers::{ResentDate, ResentFrom, ResentSender, ResentTo, ResentCc, Re
                     ResentMessageId};
 use self::headers::{OrigDate, From, Sender, ReplyTo, To, Cc, Bcc, MessageId, InReplyTo,
                     References, Subject, Comments, Keywords, OptionalField};
+use self::headers::{ContentType, ContentDisposition, ContentTransferEncoding, MimeVersion,
+                    ContentLanguage};
+use self::types::is_wsp;
 
 pub trait Parsable: Sized {
     /// Parse the object off of the beginning of the `input`.  If found, returns Some object,
@@ -99,13 +109,47 @@ pub trait Streamable {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError>;
 }
 
+/// A writer which counts the bytes written to it and discards them, so that
+/// the length of streamed output can be measured without allocating it.
+///
+/// ```
+/// use std::io::Write;
+/// use email_format::rfc5322::CountingSink;
+///
+/// let mut sink = CountingSink::new();
+/// sink.write_all(b"Subject: Hello\r\n").unwrap();
+/// assert_eq!(sink.count(), 16);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CountingSink {
+    count: usize,
+}
+impl CountingSink {
+    pub fn new() -> CountingSink {
+        CountingSink { count: 0 }
+    }
+    /// The number of bytes written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> Result<(), IoError> {
+        Ok(())
+    }
+}
+
 // 3.6.7
 // trace           =   [return]
 //                     1*received
 #[derive(Debug, Clone, PartialEq)]
 pub struct Trace {
-    return_path: Option<Return>,
-    received: Vec<Received>
+    pub return_path: Option<Return>,
+    pub received: Vec<Received>
 }
 impl Parsable for Trace {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
@@ -205,54 +249,84 @@ pub enum Field {
     Subject(Subject),
     Comments(Comments),
     Keywords(Keywords),
+    ContentType(ContentType),
+    ContentDisposition(ContentDisposition),
+    ContentTransferEncoding(ContentTransferEncoding),
+    MimeVersion(MimeVersion),
+    ContentLanguage(ContentLanguage),
     OptionalField(OptionalField),
 }
 impl Parsable for Field {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
-        let mut rem = input;
-        if let Ok(x) = parse!(OrigDate, rem) {
-            return Ok((Field::OrigDate(x), rem));
-        }
-        if let Ok(x) = parse!(From, rem) {
-            return Ok((Field::From(x), rem));
-        }
-        if let Ok(x) = parse!(Sender, rem) {
-            return Ok((Field::Sender(x), rem));
-        }
-        if let Ok(x) = parse!(ReplyTo, rem) {
-            return Ok((Field::ReplyTo(x), rem));
-        }
-        if let Ok(x) = parse!(To, rem) {
-            return Ok((Field::To(x), rem));
-        }
-        if let Ok(x) = parse!(Cc, rem) {
-            return Ok((Field::Cc(x), rem));
-        }
-        if let Ok(x) = parse!(Bcc, rem) {
-            return Ok((Field::Bcc(x), rem));
-        }
-        if let Ok(x) = parse!(MessageId, rem) {
-            return Ok((Field::MessageId(x), rem));
-        }
-        if let Ok(x) = parse!(InReplyTo, rem) {
-            return Ok((Field::InReplyTo(x), rem));
-        }
-        if let Ok(x) = parse!(References, rem) {
-            return Ok((Field::References(x), rem));
-        }
-        if let Ok(x) = parse!(Subject, rem) {
-            return Ok((Field::Subject(x), rem));
-        }
-        if let Ok(x) = parse!(Comments, rem) {
-            return Ok((Field::Comments(x), rem));
-        }
-        if let Ok(x) = parse!(Keywords, rem) {
-            return Ok((Field::Keywords(x), rem));
-        }
-        if let Ok(x) = parse!(OptionalField, rem) {
-            return Ok((Field::OptionalField(x), rem));
-        }
-        Err(ParseError::NotFound("Field"))
+        let (field, rem) = parse_field(input)?;
+        check_line_length(&field)?;
+        Ok((field, rem))
+    }
+}
+
+// Parse a field of whichever kind `input` begins with
+fn parse_field(input: &[u8]) -> Result<(Field, &[u8]), ParseError> {
+    let mut rem = input;
+    if let Ok(x) = parse!(OrigDate, rem) {
+        return Ok((Field::OrigDate(x), rem));
+    }
+    if let Ok(x) = parse!(From, rem) {
+        return Ok((Field::From(x), rem));
+    }
+    if let Ok(x) = parse!(Sender, rem) {
+        return Ok((Field::Sender(x), rem));
+    }
+    if let Ok(x) = parse!(ReplyTo, rem) {
+        return Ok((Field::ReplyTo(x), rem));
+    }
+    if let Ok(x) = parse!(To, rem) {
+        return Ok((Field::To(x), rem));
+    }
+    if let Ok(x) = parse!(Cc, rem) {
+        return Ok((Field::Cc(x), rem));
+    }
+    if let Ok(x) = parse!(Bcc, rem) {
+        return Ok((Field::Bcc(x), rem));
+    }
+    if let Ok(x) = parse!(MessageId, rem) {
+        return Ok((Field::MessageId(x), rem));
+    }
+    if let Ok(x) = parse!(InReplyTo, rem) {
+        return Ok((Field::InReplyTo(x), rem));
+    }
+    if let Ok(x) = parse!(References, rem) {
+        return Ok((Field::References(x), rem));
+    }
+    if let Ok(x) = parse!(Subject, rem) {
+        return Ok((Field::Subject(x), rem));
+    }
+    if let Ok(x) = parse!(Comments, rem) {
+        return Ok((Field::Comments(x), rem));
+    }
+    if let Ok(x) = parse!(Keywords, rem) {
+        return Ok((Field::Keywords(x), rem));
+    }
+    if let Ok(x) = parse!(ContentType, rem) {
+        return Ok((Field::ContentType(x), rem));
+    }
+    if let Ok(x) = parse!(ContentDisposition, rem) {
+        return Ok((Field::ContentDisposition(x), rem));
+    }
+    if let Ok(x) = parse!(ContentTransferEncoding, rem) {
+        return Ok((Field::ContentTransferEncoding(x), rem));
+    }
+    if let Ok(x) = parse!(MimeVersion, rem) {
+        return Ok((Field::MimeVersion(x), rem));
+    }
+    if let Ok(x) = parse!(ContentLanguage, rem) {
+        return Ok((Field::ContentLanguage(x), rem));
+    }
+    // Any header name is a valid optional-field name, so an error in its
+    // value explains why no field could be parsed
+    match parse!(OptionalField, rem) {
+        Ok(x) => Ok((Field::OptionalField(x), rem)),
+        Err(e @ ParseError::Parse(..)) => Err(e),
+        Err(_) => Err(ParseError::NotFound("Field")),
     }
 }
 impl Streamable for Field {
@@ -271,11 +345,45 @@ impl Streamable for Field {
             Field::Subject(ref x) => x.stream(w),
             Field::Comments(ref x) => x.stream(w),
             Field::Keywords(ref x) => x.stream(w),
+            Field::ContentType(ref x) => x.stream(w),
+            Field::ContentDisposition(ref x) => x.stream(w),
+            Field::ContentTransferEncoding(ref x) => x.stream(w),
+            Field::MimeVersion(ref x) => x.stream(w),
+            Field::ContentLanguage(ref x) => x.stream(w),
             Field::OptionalField(ref x) => x.stream(w),
         }
     }
 }
 impl_display!(Field);
+impl Field {
+    /// The name the field is streamed with, in its conventional casing, such
+    /// as `Message-ID` or `MIME-Version`. An optional field keeps the name it
+    /// was given.
+    pub fn canonical_name(&self) -> &str {
+        match *self {
+            Field::OrigDate(_) => "Date",
+            Field::From(_) => "From",
+            Field::Sender(_) => "Sender",
+            Field::ReplyTo(_) => "Reply-To",
+            Field::To(_) => "To",
+            Field::Cc(_) => "Cc",
+            Field::Bcc(_) => "Bcc",
+            Field::MessageId(_) => "Message-ID",
+            Field::InReplyTo(_) => "In-Reply-To",
+            Field::References(_) => "References",
+            Field::Subject(_) => "Subject",
+            Field::Comments(_) => "Comments",
+            Field::Keywords(_) => "Keywords",
+            Field::ContentType(_) => "Content-Type",
+            Field::ContentDisposition(_) => "Content-Disposition",
+            Field::ContentTransferEncoding(_) => "Content-Transfer-Encoding",
+            Field::MimeVersion(_) => "MIME-Version",
+            Field::ContentLanguage(_) => "Content-Language",
+            // ftext is printable ASCII
+            Field::OptionalField(ref x) => ::std::str::from_utf8(&(x.name.0).0).unwrap_or(""),
+        }
+    }
+}
 
 // 3.6
 // a sub part of the Fields definition
@@ -329,17 +437,27 @@ impl Parsable for OptTraceBlock {
         let mut rem = input;
         if let Ok(t) = parse!(Trace, rem) {
             let mut fields: Vec<OptionalField> = Vec::new();
-            while let Ok(f) = parse!(OptionalField, rem) {
-                fields.push(f);
-            }
-            if fields.len() == 0 {
-                Err(ParseError::ExpectedType("Optional Field"))
-            } else {
-                Ok((OptTraceBlock {
-                    trace: t,
-                    opt_fields: fields
-                }, rem))
+            loop {
+                // Every field name is valid as an optional-field, so stop at
+                // the start of the next trace block or at any field which
+                // parses as something more specific.
+                if Return::parse(rem).is_ok() || Received::parse(rem).is_ok()
+                    || ResentField::parse(rem).is_ok()
+                {
+                    break;
+                }
+                match Field::parse(rem) {
+                    Ok((Field::OptionalField(f), r)) => {
+                        fields.push(f);
+                        rem = r;
+                    },
+                    _ => break,
+                }
             }
+            Ok((OptTraceBlock {
+                trace: t,
+                opt_fields: fields
+            }, rem))
         } else {
             Err(ParseError::NotFound("Opt Trace Block"))
         }
@@ -367,15 +485,17 @@ pub enum TraceBlock {
 impl Parsable for TraceBlock {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         let mut rem = input;
-        if let Ok(block) = parse!(ResentTraceBlock, rem) {
-            Ok((TraceBlock::Resent(block), rem))
+        let block = if let Ok(block) = parse!(ResentTraceBlock, rem) {
+            TraceBlock::Resent(block)
         }
         else if let Ok(block) = parse!(OptTraceBlock, rem) {
-            Ok((TraceBlock::Opt(block), rem))
+            TraceBlock::Opt(block)
         }
         else {
-            Err(ParseError::NotFound("Trace Block"))
-        }
+            return Err(ParseError::NotFound("Trace Block"));
+        };
+        check_line_length(&block)?;
+        Ok((block, rem))
     }
 }
 impl Streamable for TraceBlock {
@@ -421,12 +541,36 @@ impl Parsable for Fields {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         let mut rem = input;
         let mut trace_blocks: Vec<TraceBlock> = Vec::new();
-        while let Ok(tb) = parse!(TraceBlock, rem) {
-            trace_blocks.push(tb);
+        let max_fields = limits::current().max_fields;
+        let tripped = limits::tripped();
+        loop {
+            let start = rem;
+            let tb = parse!(TraceBlock, rem);
+            limits::check_at(input, start, tripped)?;
+            match tb {
+                Ok(tb) => {
+                    if trace_blocks.len() >= max_fields {
+                        return Err(ParseError::at(input, start, limits::exceeded("Header Fields")));
+                    }
+                    trace_blocks.push(tb);
+                },
+                Err(_) => break,
+            }
         }
         let mut fields: Vec<Field> = Vec::new();
-        while let Ok(f) = parse!(Field, rem) {
-            fields.push(f);
+        loop {
+            let start = rem;
+            let f = parse!(Field, rem);
+            limits::check_at(input, start, tripped)?;
+            match f {
+                Ok(f) => {
+                    if trace_blocks.len() + fields.len() >= max_fields {
+                        return Err(ParseError::at(input, start, limits::exceeded("Header Fields")));
+                    }
+                    fields.push(f);
+                },
+                Err(_) => break,
+            }
         }
         Ok((Fields {
             trace_blocks: trace_blocks,
@@ -436,18 +580,108 @@ impl Parsable for Fields {
 }
 impl Streamable for Fields {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
-        let mut count: usize = 0;
+        let mut header: Vec<u8> = Vec::new();
         for tb in &self.trace_blocks {
-            count += tb.stream(w)?;
+            tb.stream(&mut header)?;
         }
         for f in &self.fields {
-            count += f.stream(w)?;
+            f.stream(&mut header)?;
         }
-        Ok(count)
+        let folded = fold(&header);
+        w.write_all(&folded)?;
+        Ok(folded.len())
     }
 }
 impl_display!(Fields);
 
+// A CR or LF within a header field value is only allowed as part of a fold
+// (CRLF followed by whitespace). Any other would end the field early, and
+// let the rest of the value be read as a field of its own, such as an added
+// "Bcc:". Fails with the offset of the first such line terminator.
+pub(crate) fn check_line_terminators(input: &[u8]) -> Result<(), ParseError> {
+    let mut i: usize = 0;
+    while i < input.len() {
+        match input[i] {
+            b'\r' if input[i + 1..].starts_with(b"\n")
+                && input.get(i + 2).map_or(false, |&c| is_wsp(c)) => i += 3,
+            b'\r' | b'\n' => return Err(ParseError::HeaderInjection(i)),
+            _ => i += 1,
+        }
+    }
+    Ok(())
+}
+
+// 2.1.1
+// Each line of characters MUST be no more than 998 characters, and SHOULD
+// be no more than 78 characters, excluding the CRLF.
+const FOLD_LINE_LEN: usize = 78;
+
+// Fail with `LineTooLong` if `header` still has a line longer than 998
+// characters once folded, as when a value has no whitespace to fold at
+pub(crate) fn check_line_length<T: Streamable>(header: &T) -> Result<(), ParseError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let _ = header.stream(&mut bytes); // no IoError ought to occur.
+    let folded = fold(&bytes);
+    match folded.split(|&c| c == b'\n')
+        .map(|line| if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line })
+        .position(|line| line.len() > 998)
+    {
+        Some(i) => Err(ParseError::LineTooLong(i + 1)),
+        None => Ok(()),
+    }
+}
+
+// 2.2.3
+// Fold header fields by inserting a CRLF before whitespace within their
+// bodies, so that lines stay within 78 characters wherever whitespace
+// allows. Whitespace is only streamed where the grammar permits folding
+// white space, so the result parses back to the same fields.
+pub(crate) fn fold(input: &[u8]) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(input.len() + input.len() / 32);
+    let mut line_len: usize = 0;
+    let mut break_at: Option<usize> = None;
+    let mut in_body: bool = false;
+    let mut seen_colon: bool = false;
+    for (i, &c) in input.iter().enumerate() {
+        if c == b'\r' || c == b'\n' {
+            output.push(c);
+            line_len = 0;
+            break_at = None;
+            // A line beginning with whitespace continues the field
+            if c == b'\n' && !input.get(i + 1).map_or(false, |&n| is_wsp(n)) {
+                in_body = false;
+                seen_colon = false;
+            }
+            continue;
+        }
+        if is_wsp(c) {
+            // Break before the first of a run of whitespace, but never where
+            // it would leave a line of only whitespace
+            let prev_is_wsp = i > 0 && is_wsp(input[i - 1]);
+            let rest = &input[i..];
+            let next_is_text = rest.iter().find(|&&n| !is_wsp(n))
+                .map_or(false, |&n| n != b'\r' && n != b'\n');
+            if in_body && line_len > 0 && !prev_is_wsp && next_is_text {
+                break_at = Some(output.len());
+            }
+        } else if seen_colon {
+            in_body = true;
+        } else if c == b':' {
+            seen_colon = true;
+        }
+        output.push(c);
+        line_len += 1;
+        if line_len > FOLD_LINE_LEN {
+            if let Some(at) = break_at.take() {
+                output.insert(at, b'\n');
+                output.insert(at, b'\r');
+                line_len = output.len() - at - 2;
+            }
+        }
+    }
+    output
+}
+
 // 3.5
 // text            =   %d1-9 /            ; Characters excluding CR
 //                     %d11 /             ;  and LF
@@ -465,32 +699,73 @@ def_cclass!(Text, is_text);
 // for performance/memory reasons, we store as a Vec<u8>
 // rather than Vec<Line> where Line is Vec<Text>.
 pub struct Body(pub Vec<u8>);
-impl Parsable for Body {
-    fn parse(mut input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+impl Body {
+    /// Parse a body from `input` line by line until end of input, so that it
+    /// need not be held in memory beforehand. Lines may end in CRLF or, as
+    /// happens when a message is mangled in transit, in a bare LF. Either
+    /// way they are kept, and so streamed, with CRLF.
+    pub fn parse_from_reader<R: BufRead>(input: &mut R) -> Result<Body, ParseError> {
         let mut body: Vec<u8> = Vec::new();
         let mut line_number: usize = 0;
         loop {
             line_number += 1;
             let mut line: Vec<u8> = Vec::new();
-            match input.stream_until_token(b"\r\n", &mut line) {
-                Err(e) => return Err(ParseError::Io(e)),
-                Ok((_, found)) => {
-                    let mut rem = &*line;
-                    if let Ok(text) = parse!(Text, rem) {
-                        if rem.len() > 0 {
-                            return Err(ParseError::InvalidBodyChar(rem[0]));
-                        }
-                        if text.0.len() > 998 {
-                            return Err(ParseError::LineTooLong(line_number));
-                        }
-                        body.extend(text.0.clone());
-                    }
-                    if !found { break; } // end of input
-                    else { body.extend_from_slice(b"\r\n"); }
+            let (_, found) = input.stream_until_token(b"\n", &mut line)?;
+            if found && line.ends_with(b"\r") {
+                line.pop();
+            }
+            let mut rem = &*line;
+            if let Ok(text) = parse!(Text, rem) {
+                if rem.len() > 0 {
+                    return Err(ParseError::InvalidBodyChar(rem[0]));
+                }
+                if text.0.len() > 998 {
+                    return Err(ParseError::LineTooLong(line_number));
                 }
+                body.extend(text.0.clone());
+            }
+            if !found { break; } // end of input
+            else { body.extend_from_slice(b"\r\n"); }
+        }
+        Ok(Body(body))
+    }
+
+    /// Parse a body as `parse()` does, but also fail with
+    /// `LineExceedsRecommended` on the first line longer than the 78
+    /// characters RFC 5322 section 2.1.1 says lines SHOULD be limited to.
+    pub fn parse_strict_78(input: &[u8]) -> Result<(Body, &[u8]), ParseError> {
+        let (body, rem) = Body::parse(input)?;
+        for (i, line) in body.0.split(|&c| c == b'\n').enumerate() {
+            let len = if line.ends_with(b"\r") { line.len() - 1 } else { line.len() };
+            if len > 78 {
+                return Err(ParseError::LineExceedsRecommended(i + 1));
             }
         }
-        Ok((Body(body), input))
+        Ok((body, rem))
+    }
+}
+impl Parsable for Body {
+    fn parse(mut input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let body = Body::parse_from_reader(&mut input)?;
+        Ok((body, input))
+    }
+}
+
+// Parse the body which begins at `rem` within `input`, locating a failure at
+// the start of the line it occurred in
+pub(crate) fn parse_body<'a>(input: &[u8], rem: &'a [u8])
+                             -> Result<(Body, &'a [u8]), ParseError>
+{
+    let mut r = rem;
+    match Body::parse_from_reader(&mut r) {
+        Ok(body) => Ok((body, r)),
+        Err(e) => {
+            // everything up to the end of the failing line has been read
+            let read = &rem[..rem.len() - r.len()];
+            let line = read[..read.len().saturating_sub(1)].iter()
+                .rposition(|&c| c == b'\n').map_or(0, |p| p + 1);
+            Err(ParseError::at(input, &rem[line..], e))
+        },
     }
 }
 impl Streamable for Body {
@@ -528,21 +803,17 @@ pub struct Message {
 impl Parsable for Message {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         let mut rem = input;
-        if let Ok(fields) = parse!(Fields, rem) {
-            if &rem[..2] != b"\r\n" {
-                return Ok((Message {
-                    fields: fields,
-                    body: None,
-                }, rem));
-            }
-            rem = &rem[2..];
-            parse!(Body, rem).map(|b| (Message {
+        let fields = parse!(Fields, rem)?;
+        if !rem.starts_with(b"\r\n") {
+            return Ok((Message {
                 fields: fields,
-                body: Some(b),
-            }, rem))
-        } else {
-            Err(ParseError::NotFound("Message"))
+                body: None,
+            }, rem));
         }
+        parse_body(input, &rem[2..]).map(|(b, rem)| (Message {
+            fields: fields,
+            body: Some(b),
+        }, rem))
     }
 }
 impl Streamable for Message {
@@ -0,0 +1,197 @@
+// Splitting of multipart bodies as defined in RFC 2046 section 5.1
+
+use super::ParseError;
+use super::types::is_wsp;
+
+/// One body part of a multipart body, as raw bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawPart {
+    /// The header fields of the part, each ending in CRLF. This is empty if
+    /// the part has no header fields.
+    pub header: Vec<u8>,
+    /// Whether the blank line separating the header fields from the body is
+    /// present. It is always written if the body is not empty.
+    pub blank_line: bool,
+    /// The body of the part
+    pub body: Vec<u8>,
+}
+
+impl RawPart {
+    /// A part with the header fields `header` and the body `body`, separated
+    /// by a blank line
+    pub fn new(header: Vec<u8>, body: Vec<u8>) -> RawPart {
+        RawPart {
+            header: header,
+            blank_line: true,
+            body: body,
+        }
+    }
+
+    /// The part as it appears between delimiters: its header fields, the
+    /// blank line, then the body
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::with_capacity(self.header.len() + self.body.len() + 2);
+        output.extend_from_slice(&self.header);
+        if self.blank_line || !self.body.is_empty() {
+            output.extend_from_slice(b"\r\n");
+        }
+        output.extend_from_slice(&self.body);
+        output
+    }
+
+    fn from_bytes(input: &[u8]) -> RawPart {
+        // RFC 2046, 5.1.1
+        // body-part := MIME-part-headers [CRLF *OCTET]
+        if input.starts_with(b"\r\n") {
+            return RawPart::new(Vec::new(), input[2..].to_vec());
+        }
+        match input.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(p) => RawPart::new(input[..p + 2].to_vec(), input[p + 4..].to_vec()),
+            None => RawPart {
+                header: input.to_vec(),
+                blank_line: false,
+                body: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A multipart body split into its parts, keeping the text before the first
+/// delimiter and after the close delimiter, and the layout of the delimiter
+/// lines, so that it can be rebuilt exactly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multipart {
+    /// The text before the first delimiter, without the CRLF that belongs to
+    /// the delimiter. Mail readers which do not understand MIME show this.
+    /// This is `None` if the body begins with the first delimiter.
+    pub preamble: Option<Vec<u8>>,
+    /// The body parts
+    pub parts: Vec<RawPart>,
+    /// The transport padding (trailing whitespace) of each delimiter line, in
+    /// order, the last being that of the close delimiter. Missing entries are
+    /// taken as empty.
+    pub padding: Vec<Vec<u8>>,
+    /// The text after the CRLF following the close delimiter. This is `None`
+    /// if the close delimiter is not followed by a CRLF.
+    pub epilogue: Option<Vec<u8>>,
+}
+
+impl Multipart {
+    /// Rebuild the body with delimiter lines built from `boundary`
+    pub fn to_bytes(&self, boundary: &str) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        if let Some(ref preamble) = self.preamble {
+            output.extend_from_slice(preamble);
+            output.extend_from_slice(b"\r\n");
+        }
+        let padding = |i: usize| self.padding.get(i).map(|p| &**p).unwrap_or(b"");
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 { output.extend_from_slice(b"\r\n"); }
+            output.extend(format!("--{}", boundary).into_bytes());
+            output.extend_from_slice(padding(i));
+            output.extend_from_slice(b"\r\n");
+            output.extend(part.to_bytes());
+        }
+        if !self.parts.is_empty() { output.extend_from_slice(b"\r\n"); }
+        output.extend(format!("--{}--", boundary).into_bytes());
+        output.extend_from_slice(padding(self.parts.len()));
+        if let Some(ref epilogue) = self.epilogue {
+            output.extend_from_slice(b"\r\n");
+            output.extend_from_slice(epilogue);
+        }
+        output
+    }
+}
+
+/// Generate a boundary which does not occur within any of `parts`, so that
+/// they may be joined with `Multipart::to_bytes()`. The boundary is made of
+/// token characters only, so needs no quoting as a parameter value.
+pub fn generate_boundary(parts: &[RawPart]) -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let bytes: Vec<Vec<u8>> = parts.iter().map(|p| p.to_bytes()).collect();
+    loop {
+        // Each RandomState is randomly keyed, so this hash is our random part
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+        let boundary = format!("boundary-{:016x}", hasher.finish());
+        let dash_boundary = format!("--{}", boundary).into_bytes();
+        let collides = bytes.iter().any(|b| {
+            b.windows(dash_boundary.len()).any(|w| w == &*dash_boundary)
+        });
+        if !collides {
+            return boundary;
+        }
+    }
+}
+
+// RFC 2046, 5.1.1
+// dash-boundary := "--" boundary
+// delimiter := CRLF dash-boundary
+// close-delimiter := delimiter "--"
+// Either may be followed by transport-padding (LWSP) and CRLF.
+// Returns whether `line` is a delimiter line, and if so whether it closes
+// and its transport padding.
+fn delimiter<'a>(line: &'a [u8], dash_boundary: &[u8]) -> Option<(bool, &'a [u8])> {
+    if !line.starts_with(dash_boundary) {
+        return None;
+    }
+    let mut rest = &line[dash_boundary.len()..];
+    let close = rest.starts_with(b"--");
+    if close {
+        rest = &rest[2..];
+    }
+    if rest.iter().all(|&c| is_wsp(c)) { Some((close, rest)) } else { None }
+}
+
+/// Split a multipart `body` into its parts on delimiter lines built from
+/// `boundary`, along with the preamble before the first delimiter and the
+/// epilogue after the close delimiter. The CRLF before each delimiter
+/// belongs to the delimiter rather than the part before it, so is dropped.
+pub fn split(body: &[u8], boundary: &str) -> Result<Multipart, ParseError> {
+    let dash_boundary = format!("--{}", boundary).into_bytes();
+    let mut preamble: Option<Vec<u8>> = None;
+    let mut parts: Vec<RawPart> = Vec::new();
+    let mut padding: Vec<Vec<u8>> = Vec::new();
+    // Start of the current part, once the first delimiter has been seen
+    let mut part_start: Option<usize> = None;
+    let mut pos: usize = 0;
+    while pos < body.len() {
+        let line_end = match body[pos..].windows(2).position(|w| w == b"\r\n") {
+            Some(p) => pos + p,
+            None => body.len(),
+        };
+        if let Some((close, pad)) = delimiter(&body[pos..line_end], &dash_boundary) {
+            padding.push(pad.to_vec());
+            match part_start {
+                Some(start) => {
+                    // The CRLF before the delimiter is not part of the body part
+                    let end = if pos >= start + 2 { pos - 2 } else { start };
+                    parts.push(RawPart::from_bytes(&body[start..end]));
+                },
+                None if pos > 0 => preamble = Some(body[..pos - 2].to_vec()),
+                None => {},
+            }
+            if close {
+                let epilogue = body.get(line_end + 2..).map(|e| e.to_vec());
+                return Ok(Multipart {
+                    preamble: preamble,
+                    parts: parts,
+                    padding: padding,
+                    epilogue: epilogue,
+                });
+            }
+            part_start = Some(::std::cmp::min(line_end + 2, body.len()));
+        }
+        pos = line_end + 2;
+    }
+    if part_start.is_none() {
+        Err(ParseError::NotFound("Multipart Delimiter"))
+    } else {
+        Err(ParseError::NotFound("Multipart Close Delimiter"))
+    }
+}
@@ -1,7 +1,15 @@
+// Obsolete syntax (RFC 5322 section 4) is mostly not accepted.  The
+// exceptions, obs-phrase and obs-zone, are common enough in archived mail
+// that we accept them even without the `obsolete` feature, which only adds
+// obs-domain and obs-utext.
 
+use std::collections::HashMap;
 use std::io::Write;
 use std::io::Error as IoError;
-use super::{Parsable, Streamable, ParseError};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use super::{Parsable, Streamable, ParseError, check_line_terminators};
+use super::limits;
+use ::TryFrom;
 
 // RFC 5234, B.1  Core Rules
 //const CR: u8 = 0x0D;     //   CR             =  %x0D      ; carriage return
@@ -93,7 +101,7 @@ impl Parsable for FWS {
 }
 impl Streamable for FWS {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
-        Ok(w.write(b" ")?) // FIXME - fold?
+        Ok(w.write(b" ")?) // long lines are folded by Fields::stream
     }
 }
 impl_display!(FWS);
@@ -155,6 +163,17 @@ impl Parsable for Comment {
         let mut rem: &[u8] = input;
         if rem.len() == 0 { return Err(ParseError::Eof("Comment")); }
         req!(rem, b"(", input);
+        // Comments nest recursively, so bound the depth to protect the stack
+        limits::enter_comment()?;
+        let result = Comment::parse_rest(rem);
+        limits::leave_comment();
+        result
+    }
+}
+impl Comment {
+    // Parse the comment following its opening parenthesis
+    fn parse_rest(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let mut rem: &[u8] = input;
         let mut ccontent: Vec<(bool, CContent)> = Vec::new();
         let mut ws: bool = false;
         while rem.len() > 0 {
@@ -187,6 +206,25 @@ impl Streamable for Comment {
     }
 }
 impl_display!(Comment);
+impl Comment {
+    /// The text of the comment without its parentheses, with quoted-pairs
+    /// unescaped. Nested comments keep their parentheses. Whitespace between
+    /// the parts of the comment, including any folding, becomes a single
+    /// space.
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        for &(ws, ref cc) in &self.ccontent {
+            if ws { output.push(' '); }
+            match *cc {
+                CContent::CText(ref t) => output.push_str(&*String::from_utf8_lossy(&t.0)),
+                CContent::QuotedPair(ref qp) => output.push(qp.0 as char),
+                CContent::Comment(ref c) => output.push_str(&*format!("({})", c.text())),
+            }
+        }
+        if self.trailing_ws { output.push(' '); }
+        output
+    }
+}
 
 // 3.2.2
 // CFWS            =   (1*([FWS] comment) [FWS]) / FWS
@@ -488,13 +526,31 @@ impl Streamable for QuotedString {
     }
 }
 impl_display!(QuotedString);
+impl QuotedString {
+    /// The content of the quoted string, without its quotes and with
+    /// quoted-pairs unescaped
+    pub fn text(&self) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        for &(ws, ref qc) in &self.qcontent {
+            if ws { output.push(b' '); }
+            match *qc {
+                QContent::QText(ref t) => output.extend(&t.0),
+                QContent::QuotedPair(ref qp) => output.push(qp.0),
+            }
+        }
+        if self.trailing_ws { output.push(b' '); }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+}
 
 // 3.2.5
 // word            =   atom / quoted-string
+// Word::parse() never gives a `Dot`, which only occurs within a Phrase.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Word {
     Atom(Atom),
     QuotedString(QuotedString),
+    Dot(Dot),
 }
 impl Parsable for Word {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
@@ -515,13 +571,40 @@ impl Streamable for Word {
         match *self {
             Word::Atom(ref x) => x.stream(w),
             Word::QuotedString(ref x) => x.stream(w),
+            Word::Dot(ref x) => x.stream(w),
         }
     }
 }
 impl_display!(Word);
 
+// 4.1
+// A "." between the words of an obs-phrase, with the CFWS around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dot {
+    pub pre_cfws: Option<CFWS>,
+    pub post_cfws: Option<CFWS>,
+}
+impl Streamable for Dot {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += w.write(b".")?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl_display!(Dot);
+
 // 3.2.5
 // phrase          =   1*word / obs-phrase
+// 4.1
+// obs-phrase      =   word *(word / "." / CFWS)
+// Each "." is kept as a Word::Dot so that it streams as it was.  This allows
+// display names such as "J. Random Hacker".
 #[derive(Debug, Clone, PartialEq)]
 pub struct Phrase(pub Vec<Word>);
 impl Parsable for Phrase {
@@ -529,8 +612,22 @@ impl Parsable for Phrase {
         if input.len() == 0 { return Err(ParseError::Eof("Phrase")); }
         let mut rem = input;
         let mut output: Vec<Word> = Vec::new();
-        while let Ok(word) = parse!(Word, rem) {
-            output.push(word);
+        loop {
+            if let Ok(word) = parse!(Word, rem) {
+                output.push(word);
+                continue;
+            }
+            if output.len() == 0 { break; }
+            let mut rem2 = rem;
+            let pre_cfws = parse!(CFWS, rem2);
+            if rem2.len() == 0 || rem2[0] != b'.' { break; }
+            rem2 = &rem2[1..];
+            let post_cfws = parse!(CFWS, rem2);
+            output.push(Word::Dot(Dot {
+                pre_cfws: pre_cfws.ok(),
+                post_cfws: post_cfws.ok(),
+            }));
+            rem = rem2;
         }
         if output.len() == 0 {
             Err(ParseError::NotFound("Phrase"))
@@ -549,6 +646,32 @@ impl Streamable for Phrase {
     }
 }
 impl_display!(Phrase);
+impl Phrase {
+    /// The words of the phrase with quoted strings unquoted and comments
+    /// removed. Words which were separated by whitespace or comments are
+    /// separated by a single space.
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        let mut separated: bool = false;
+        for word in &self.0 {
+            let (pre, text, post) = match *word {
+                Word::Atom(ref a) => (a.pre_cfws.is_some(),
+                                      String::from_utf8_lossy(&a.atext.0).into_owned(),
+                                      a.post_cfws.is_some()),
+                Word::QuotedString(ref qs) => (qs.pre_cfws.is_some(), qs.text(),
+                                               qs.post_cfws.is_some()),
+                Word::Dot(ref d) => (d.pre_cfws.is_some(), ".".to_owned(),
+                                     d.post_cfws.is_some()),
+            };
+            if (separated || pre) && !output.is_empty() {
+                output.push(' ');
+            }
+            output.push_str(&*text);
+            separated = post;
+        }
+        output
+    }
+}
 
 // 3.2.5
 // unstructured    = (*([FWS] VCHAR) *WSP) / obs-unstruct
@@ -570,6 +693,14 @@ impl Parsable for Unstructured {
                 Ok((_, rem2)) => rem2,
                 Err(_) => rem,
             };
+            #[cfg(feature="obsolete")]
+            {
+                if let Ok((vchar, r)) = parse_obs_utext(rem2) {
+                    rem = r;
+                    output.extend(vchar);
+                    continue;
+                }
+            }
             if let Ok(vchar) = parse!(VChar, rem2) {
                 rem = rem2;
                 output.push(vchar);
@@ -604,6 +735,38 @@ impl Streamable for Unstructured {
 }
 impl_display!(Unstructured);
 
+// 4.1
+// obs-utext       =   %d0 / obs-NO-WS-CTL / VCHAR
+// obs-NO-WS-CTL   =   %d1-8 / %d11 / %d12 / %d14-31 / %d127
+// The control characters are dropped, so that the text streams back as
+// VCHARs. The bare CRs and LFs which obs-unstruct also allows are not
+// accepted.
+#[cfg(feature="obsolete")]
+fn parse_obs_utext(input: &[u8]) -> Result<(Option<VChar>, &[u8]), ParseError> {
+    let is_obs_utext = |c: u8| is_vchar(c) || c <= 8 || c == 11 || c == 12
+        || (c >= 14 && c <= 31) || c == 127;
+    let len = input.iter().take_while(|&&c| is_obs_utext(c)).count();
+    if len == 0 { return Err(ParseError::NotFound("obs-utext")); }
+    let text: Vec<u8> = input[..len].iter().cloned().filter(|&c| is_vchar(c)).collect();
+    let vchar = if text.is_empty() { None } else { Some(VChar(text)) };
+    Ok((vchar, &input[len..]))
+}
+impl Unstructured {
+    /// The logical text, with the parts joined by single spaces and a space
+    /// before or after them if the value had leading or trailing whitespace.
+    /// Unlike streaming, this never folds.
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        if self.leading_ws { output.push(' '); }
+        for (i, vc) in self.parts.iter().enumerate() {
+            if i > 0 { output.push(' '); }
+            output.push_str(&*String::from_utf8_lossy(&vc.0));
+        }
+        if self.trailing_ws { output.push(' '); }
+        output
+    }
+}
+
 // 3.4.1
 // local-part      =   dot-atom / quoted-string / obs-local-part
 #[derive(Debug, Clone, PartialEq)]
@@ -699,6 +862,24 @@ impl Streamable for DomainLiteral {
     }
 }
 impl_display!(DomainLiteral);
+impl DomainLiteral {
+    /// The IP address within an address literal, as RFC 5321 section 4.1.3
+    /// defines them: a dotted-quad IPv4 address such as `[192.0.2.1]`, or an
+    /// IPv6 address tagged as in `[IPv6:2001:db8::1]`. Any other literal,
+    /// though it parses, gives `None`.
+    pub fn as_ip_addr(&self) -> Option<IpAddr> {
+        let mut text = String::new();
+        for &(ws, ref dt) in &self.dtext {
+            if ws { text.push(' '); }
+            text.push_str(&*String::from_utf8_lossy(&dt.0));
+        }
+        if text.len() > 5 && text[..5].eq_ignore_ascii_case("IPv6:") {
+            text[5..].parse::<Ipv6Addr>().ok().map(IpAddr::V6)
+        } else {
+            text.parse::<Ipv4Addr>().ok().map(IpAddr::V4)
+        }
+    }
+}
 
 // 3.4.1
 // domain          =   dot-atom / domain-literal / obs-domain
@@ -710,6 +891,12 @@ pub enum Domain {
 impl Parsable for Domain {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         if input.len() == 0 { return Err(ParseError::Eof("Domain")); }
+        #[cfg(feature="obsolete")]
+        {
+            if let Ok((x, rem)) = parse_obs_domain(input) {
+                return Ok((Domain::DotAtom(x), rem));
+            }
+        }
         if let Ok((x, rem)) = DotAtom::parse(input) {
             Ok((Domain::DotAtom(x), rem))
         }
@@ -731,6 +918,33 @@ impl Streamable for Domain {
 }
 impl_display!(Domain);
 
+// 4.4
+// obs-domain      =   atom *("." atom)
+// This is normalized into a dot-atom, dropping any CFWS between the atoms.
+#[cfg(feature="obsolete")]
+fn parse_obs_domain(input: &[u8]) -> Result<(DotAtom, &[u8]), ParseError> {
+    let mut rem = input;
+    let first = parse!(Atom, rem)?;
+    let pre_cfws = first.pre_cfws;
+    let mut post_cfws = first.post_cfws;
+    let mut parts: Vec<AText> = vec![first.atext];
+    while rem.len() > 0 && rem[0] == b'.' {
+        match Atom::parse(&rem[1..]) {
+            Ok((atom, r)) => {
+                rem = r;
+                parts.push(atom.atext);
+                post_cfws = atom.post_cfws;
+            },
+            Err(_) => break,
+        }
+    }
+    Ok((DotAtom {
+        pre_cfws: pre_cfws,
+        dot_atom_text: DotAtomText(parts),
+        post_cfws: post_cfws,
+    }, rem))
+}
+
 // 3.4.1
 // addr-spec       =   local-part "@" domain
 #[derive(Debug, Clone, PartialEq)]
@@ -762,6 +976,77 @@ impl Streamable for AddrSpec {
     }
 }
 impl_display!(AddrSpec);
+impl AddrSpec {
+    /// The logical value of the local part, without surrounding CFWS. A
+    /// quoted local part is unquoted, so `"john doe"` gives `john doe`.
+    pub fn local_part_str(&self) -> String {
+        match self.local_part {
+            LocalPart::DotAtom(ref x) => format!("{}", x.dot_atom_text),
+            LocalPart::QuotedString(ref x) => x.text(),
+        }
+    }
+
+    /// The text of each comment around the local part and the domain, in
+    /// order, as `Comment::text()` gives it
+    pub fn comments(&self) -> Vec<String> {
+        let (local_pre, local_post) = match self.local_part {
+            LocalPart::DotAtom(ref x) => (&x.pre_cfws, &x.post_cfws),
+            LocalPart::QuotedString(ref x) => (&x.pre_cfws, &x.post_cfws),
+        };
+        let (domain_pre, domain_post) = match self.domain {
+            Domain::DotAtom(ref x) => (&x.pre_cfws, &x.post_cfws),
+            Domain::DomainLiteral(ref x) => (&x.pre_cfws, &x.post_cfws),
+        };
+        let mut output: Vec<String> = Vec::new();
+        for cfws in &[local_pre, local_post, domain_pre, domain_post] {
+            if let Some(ref cfws) = **cfws {
+                output.extend(cfws.comments.iter().map(|&(_, ref c)| c.text()));
+            }
+        }
+        output
+    }
+
+    /// The domain without surrounding CFWS, such as `example.com` or
+    /// `[192.0.2.1]`
+    pub fn domain_str(&self) -> String {
+        match self.domain {
+            Domain::DotAtom(ref x) => format!("{}", x.dot_atom_text),
+            Domain::DomainLiteral(ref x) => {
+                let mut output = String::from("[");
+                for &(ws, ref dt) in &x.dtext {
+                    if ws { output.push(' '); }
+                    output.push_str(&*format!("{}", dt));
+                }
+                output.push(']');
+                output
+            },
+        }
+    }
+
+    /// The address as `local@domain` with no comments or folding. Unlike
+    /// `local_part_str()`, a quoted local part keeps its quotes so that the
+    /// result is still a valid addr-spec.
+    pub fn to_string_bare(&self) -> String {
+        let local_part = match self.local_part {
+            LocalPart::DotAtom(ref x) => format!("{}", x.dot_atom_text),
+            LocalPart::QuotedString(ref x) => format!("{}", QuotedString {
+                pre_cfws: None,
+                qcontent: x.qcontent.clone(),
+                trailing_ws: x.trailing_ws,
+                post_cfws: None,
+            }),
+        };
+        format!("{}@{}", local_part, self.domain_str())
+    }
+
+    /// Whether `other` is the same mailbox, ignoring CFWS. The local part is
+    /// compared exactly and the domain case-insensitively. Only ASCII case is
+    /// folded; no Unicode normalization is done.
+    pub fn same_mailbox(&self, other: &AddrSpec) -> bool {
+        self.local_part_str() == other.local_part_str()
+            && self.domain_str().eq_ignore_ascii_case(&*other.domain_str())
+    }
+}
 
 // 3.4
 // angle-addr      =   [CFWS] "<" addr-spec ">" [CFWS] /
@@ -897,7 +1182,11 @@ impl Parsable for MailboxList {
         let mut rem = input;
         let mut output: Vec<Mailbox> = Vec::new();
         let mut savedrem = rem;
+        let max_addresses = limits::current().max_addresses;
         while let Ok(mailbox) = parse!(Mailbox, rem) {
+            if output.len() >= max_addresses {
+                return Err(limits::exceeded("Addresses"));
+            }
             savedrem = rem;
             output.push(mailbox);
             if rem.len()==0 || rem[0]!=b',' {
@@ -1032,6 +1321,24 @@ impl Streamable for Address {
         }
     }
 }
+impl<'a> TryFrom<&'a [u8]> for Address {
+    type Error = ParseError;
+    fn try_from(input: &'a [u8]) -> Result<Address, ParseError> {
+        check_line_terminators(input)?;
+        let (out,rem) = Address::parse(input)?;
+        if rem.len() > 0 {
+            Err(ParseError::TrailingInput("Address", input.len() - rem.len()))
+        } else {
+            Ok(out)
+        }
+    }
+}
+impl<'a> TryFrom<&'a str> for Address {
+    type Error = ParseError;
+    fn try_from(input: &'a str) -> Result<Address, ParseError> {
+        TryFrom::try_from(input.as_bytes())
+    }
+}
 impl_display!(Address);
 
 // 3.4
@@ -1044,7 +1351,11 @@ impl Parsable for AddressList {
         let mut rem = input;
         let mut output: Vec<Address> = Vec::new();
         let mut savedrem = rem;
+        let max_addresses = limits::current().max_addresses;
         while let Ok(mailbox) = parse!(Address, rem) {
+            if output.len() >= max_addresses {
+                return Err(limits::exceeded("Addresses"));
+            }
             savedrem = rem;
             output.push(mailbox);
             if rem.len()==0 || rem[0]!=b',' {
@@ -1075,6 +1386,23 @@ impl Streamable for AddressList {
     }
 }
 impl_display!(AddressList);
+impl AddressList {
+    /// Every mailbox in the list, with groups expanded into their members
+    pub fn mailboxes(&self) -> Vec<Mailbox> {
+        let mut output: Vec<Mailbox> = Vec::new();
+        for address in &self.0 {
+            match *address {
+                Address::Mailbox(ref mailbox) => output.push(mailbox.clone()),
+                Address::Group(ref group) => {
+                    if let Some(GroupList::MailboxList(ref mbl)) = group.group_list {
+                        output.extend(mbl.0.iter().cloned());
+                    }
+                },
+            }
+        }
+        output
+    }
+}
 
 // 3.3
 // zone            =   (FWS ( "+" / "-" ) 4DIGIT) / obs-zone
@@ -1082,24 +1410,7 @@ impl_display!(AddressList);
 pub struct Zone(pub i32);
 impl Parsable for Zone {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
-        if input.len() == 0 { return Err(ParseError::Eof("Zone")); }
-        let mut rem = input;
-        let fws = parse!(FWS, rem);
-        if fws.is_err() { return Err(ParseError::NotFound("Zone")); }
-        if rem.len() < 5 { return Err(ParseError::NotFound("Zone")); }
-        let sign: i32 = match rem[0] {
-            b'+' => 1,
-            b'-' => -1,
-            _ => return Err(ParseError::NotFound("Zone")),
-        };
-        if !is_digit(rem[1]) || !is_digit(rem[2]) || !is_digit(rem[3]) || !is_digit(rem[4]) {
-            return Err(ParseError::NotFound("Zone"));
-        }
-        let v: i32 = (1000 * ((rem[1]-48) as i32)
-                      + 100 * ((rem[2]-48) as i32)
-                      + 10 * ((rem[3]-48) as i32)
-                      + ((rem[4]-48) as i32)) * sign;
-        Ok((Zone(v), &rem[5..]))
+        parse_zone(input).map(|(zone, _, rem)| (zone, rem))
     }
 }
 impl Streamable for Zone {
@@ -1115,8 +1426,73 @@ impl Streamable for Zone {
         Ok(6)
     }
 }
+impl Zone {
+    /// The offset from UTC in seconds, e.g. -46800 for -1300
+    pub fn offset_seconds(&self) -> i32 {
+        let hhmm = self.0.abs();
+        self.0.signum() * ((hhmm / 100) * 3600 + (hhmm % 100) * 60)
+    }
+}
 impl_display!(Zone);
 
+// Parse a zone, and whether it is "-0000" (or an obsolete military zone),
+// meaning that the time is in UTC but the local zone is unknown
+fn parse_zone(input: &[u8]) -> Result<(Zone, bool, &[u8]), ParseError> {
+    if input.len() == 0 { return Err(ParseError::Eof("Zone")); }
+    let mut rem = input;
+    let fws = parse!(FWS, rem);
+    if fws.is_err() { return Err(ParseError::NotFound("Zone")); }
+    if let Ok(x) = parse_obs_zone(rem) {
+        return Ok(x);
+    }
+    if rem.len() < 5 { return Err(ParseError::NotFound("Zone")); }
+    let sign: i32 = match rem[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return Err(ParseError::NotFound("Zone")),
+    };
+    if !is_digit(rem[1]) || !is_digit(rem[2]) || !is_digit(rem[3]) || !is_digit(rem[4]) {
+        return Err(ParseError::NotFound("Zone"));
+    }
+    let v: i32 = (1000 * ((rem[1]-48) as i32)
+                  + 100 * ((rem[2]-48) as i32)
+                  + 10 * ((rem[3]-48) as i32)
+                  + ((rem[4]-48) as i32)) * sign;
+    Ok((Zone(v), v == 0 && sign < 0, &rem[5..]))
+}
+
+// 4.3
+// obs-zone        =   "UT" / "GMT" /     ; Universal Time
+//                     "EST" / "EDT" /    ; Eastern:  - 5/ - 4
+//                     "CST" / "CDT" /    ; Central:  - 6/ - 5
+//                     "MST" / "MDT" /    ; Mountain: - 7/ - 6
+//                     "PST" / "PDT" /    ; Pacific:  - 8/ - 7
+//                     %d65-73 /          ; Military zones - "A"
+//                     %d75-90 /          ; through "I" and "K"
+//                     %d97-105 /         ; through "Z", both
+//                     %d107-122          ; upper and lower case
+// The military zones were defined incorrectly in RFC 822, so they are
+// to be considered equivalent to "-0000" (an unknown zone).  Zones are
+// always streamed in the numeric form.
+fn parse_obs_zone(input: &[u8]) -> Result<(Zone, bool, &[u8]), ParseError> {
+    let len = input.iter().take_while(|&&c| is_alpha(c)).count();
+    let name = input[..len].to_ascii_uppercase();
+    let offset: i32 = match &*name {
+        b"UT" | b"GMT" => 0,
+        b"EST" => -500,
+        b"EDT" => -400,
+        b"CST" => -600,
+        b"CDT" => -500,
+        b"MST" => -700,
+        b"MDT" => -600,
+        b"PST" => -800,
+        b"PDT" => -700,
+        [c] if *c != b'J' => return Ok((Zone(0), true, &input[len..])),
+        _ => return Err(ParseError::NotFound("Zone")),
+    };
+    Ok((Zone(offset), false, &input[len..]))
+}
+
 // 3.3
 // second          =   2DIGIT / obs-second
 #[derive(Debug, Clone, PartialEq)]
@@ -1238,21 +1614,26 @@ impl_display!(TimeOfDay);
 
 // 3.3
 // time            =   time-of-day zone
+// A zone of "-0000" means that the time is in UTC but the local zone is
+// unknown, as opposed to "+0000" for a local zone which is UTC. As `Zone`
+// holds only the offset, that is kept here.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Time {
     pub time_of_day: TimeOfDay,
     pub zone: Zone,
+    unknown_local_zone: bool,
 }
 impl Parsable for Time {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         if input.len() == 0 { return Err(ParseError::Eof("Time")); }
         let mut rem = input;
         if let Ok(tod) = parse!(TimeOfDay, rem) {
-            if let Ok(zone) = parse!(Zone, rem) {
+            if let Ok((zone, unknown, r)) = parse_zone(rem) {
                 return Ok((Time {
                     time_of_day: tod,
-                    zone: zone
-                }, rem));
+                    zone: zone,
+                    unknown_local_zone: unknown,
+                }, r));
             }
         }
         Err(ParseError::NotFound("Time"))
@@ -1260,9 +1641,39 @@ impl Parsable for Time {
 }
 impl Streamable for Time {
     fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        if self.is_unknown_local_zone() {
+            return Ok(self.time_of_day.stream(w)? + w.write(b" -0000")?);
+        }
         Ok(self.time_of_day.stream(w)? + self.zone.stream(w)?)
     }
 }
+impl Time {
+    /// The time of day `time_of_day` in `zone`
+    pub fn new(time_of_day: TimeOfDay, zone: Zone) -> Time {
+        Time {
+            time_of_day: time_of_day,
+            zone: zone,
+            unknown_local_zone: false,
+        }
+    }
+
+    /// The time of day `time_of_day` in UTC where the local zone of the
+    /// sender is unknown, which streams with the zone "-0000"
+    pub fn in_unknown_local_zone(time_of_day: TimeOfDay) -> Time {
+        Time {
+            time_of_day: time_of_day,
+            zone: Zone(0),
+            unknown_local_zone: true,
+        }
+    }
+
+    /// Whether the zone was given as "-0000" (or as an obsolete military
+    /// zone), meaning that the time is in UTC but the local zone of the
+    /// sender is unknown
+    pub fn is_unknown_local_zone(&self) -> bool {
+        self.unknown_local_zone && self.zone.0 == 0
+    }
+}
 impl_display!(Time);
 
 // 3.3
@@ -1537,6 +1948,111 @@ impl Streamable for DateTime {
     }
 }
 impl_display!(DateTime);
+impl DateTime {
+    /// Build a date and time from plain numbers, computing the day of the
+    /// week. Fails if any is out of range: the year must be 1900 or later (RFC
+    /// 5322 section 3.3), the day must exist in the month, the second may be
+    /// 60 for a leap second, and the zone offset must be less than 100 hours.
+    pub fn from_parts(year: u32, month: u8, day: u8, hour: u8, minute: u8,
+                      second: Option<u8>, zone_offset_minutes: i32)
+                      -> Result<DateTime, ParseError>
+    {
+        if year < 1900 || month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) {
+            return Err(ParseError::ExpectedType("Date"));
+        }
+        if hour > 23 || minute > 59 || second.map_or(false, |s| s > 60) {
+            return Err(ParseError::ExpectedType("Time"));
+        }
+        let offset = zone_offset_minutes.abs();
+        if offset >= 100 * 60 {
+            return Err(ParseError::ExpectedType("Zone"));
+        }
+        Ok(DateTime {
+            day_of_week: Some(DayOfWeek {
+                pre_fws: None,
+                day_name: DayName(day_of_week(year, month, day)),
+            }),
+            date: Date {
+                day: Day(day),
+                month: Month(month),
+                year: Year(year),
+            },
+            time: Time::new(TimeOfDay {
+                hour: Hour(hour),
+                minute: Minute(minute),
+                second: second.map(Second),
+            }, Zone(zone_offset_minutes.signum() * ((offset / 60) * 100 + offset % 60))),
+            post_cfws: None,
+        })
+    }
+
+    /// Whether the day of the week matches the date, or `None` if no day of
+    /// the week was given. A date which does not exist, such as 30 Feb, is
+    /// never consistent.
+    pub fn weekday_is_consistent(&self) -> Option<bool> {
+        let dow = match self.day_of_week {
+            Some(ref dow) => dow,
+            None => return None,
+        };
+        let (year, month, day) = (self.date.year.0, self.date.month.0, self.date.day.0);
+        if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) {
+            return Some(false);
+        }
+        Some(dow.day_name.0 == day_of_week(year, month, day))
+    }
+
+    /// The components of the date and time as plain numbers
+    pub fn to_parts(&self) -> DateParts {
+        DateParts {
+            year: self.date.year.0,
+            month: self.date.month.0,
+            day: self.date.day.0,
+            hour: self.time.time_of_day.hour.0,
+            minute: self.time.time_of_day.minute.0,
+            second: self.time.time_of_day.second.as_ref().map(|s| s.0),
+            zone_offset_minutes: self.time.zone.offset_seconds() / 60,
+            weekday: self.day_of_week.as_ref().map(|dow| dow.day_name.0),
+        }
+    }
+}
+
+// The number of days in `month` (1 to 12) of `year`
+pub(crate) fn days_in_month(year: u32, month: u8) -> u8 {
+    const DAYS_IN_MONTH: [u8; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    if month == 2 && leap { 29 } else { DAYS_IN_MONTH[month as usize - 1] }
+}
+
+// The day of the week of a date in the Gregorian calendar, numbered as a
+// `DayName` is, 1 for Sunday through 7 for Saturday
+pub(crate) fn day_of_week(year: u32, month: u8, day: u8) -> u8 {
+    // Sakamoto's method offsets, for a weekday with 0 as Sunday
+    const WEEKDAY_OFFSET: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    // 400 years is a whole number of weeks, so adding them keeps the year 0
+    // from underflowing without changing the result
+    let y = if month < 3 { year + 399 } else { year + 400 };
+    let wday = (y + y / 4 - y / 100 + y / 400 + WEEKDAY_OFFSET[month as usize - 1]
+                + day as u32) % 7;
+    wday as u8 + 1
+}
+
+/// The components of a `DateTime`, as returned by `DateTime::to_parts()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateParts {
+    pub year: u32,
+    /// 1 for January through 12 for December
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// `None` if the seconds were omitted
+    pub second: Option<u8>,
+    /// The offset from UTC, e.g. -780 for -1300
+    pub zone_offset_minutes: i32,
+    /// 1 for Sunday through 7 for Saturday, or `None` if the day of the week
+    /// was omitted
+    pub weekday: Option<u8>,
+}
 
 // 3.6.4
 // no-fold-literal =   "[" *dtext "]"
@@ -1667,6 +2183,32 @@ impl Streamable for MsgId {
     }
 }
 impl_display!(MsgId);
+impl MsgId {
+    /// The id as `left@right`, without the angle brackets and any comments
+    /// or whitespace around them
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.id_left, self.id_right)
+    }
+
+    /// Build an id from its `id-left` and `id-right` parts, as `<left@right>`,
+    /// failing if either part is not valid on its own
+    pub fn from_parts(id_left: &str, id_right: &str) -> Result<MsgId, ParseError> {
+        let (idl, rem) = IdLeft::parse(id_left.as_bytes())?;
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("Id-left", id_left.len() - rem.len()));
+        }
+        let (idr, rem) = IdRight::parse(id_right.as_bytes())?;
+        if rem.len() > 0 {
+            return Err(ParseError::TrailingInput("Id-right", id_right.len() - rem.len()));
+        }
+        Ok(MsgId {
+            pre_cfws: None,
+            id_left: idl,
+            id_right: idr,
+            post_cfws: None,
+        })
+    }
+}
 
 // 3.6.7
 // received-token  =   word / angle-addr / addr-spec / domain
@@ -1680,10 +2222,9 @@ pub enum ReceivedToken {
 impl Parsable for ReceivedToken {
     fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
         if input.len() == 0 { return Err(ParseError::Eof("Received Token")); }
-        if let Ok((x, rem)) = Word::parse(input) {
-            Ok((ReceivedToken::Word(x), rem))
-        }
-        else if let Ok((x, rem)) = AngleAddr::parse(input) {
+        // Try the longest alternatives first, otherwise a word would match
+        // just the first label of a dotted domain.
+        if let Ok((x, rem)) = AngleAddr::parse(input) {
             Ok((ReceivedToken::AngleAddr(x), rem))
         }
         else if let Ok((x, rem)) = AddrSpec::parse(input) {
@@ -1692,6 +2233,9 @@ impl Parsable for ReceivedToken {
         else if let Ok((x, rem)) = Domain::parse(input) {
             Ok((ReceivedToken::Domain(x), rem))
         }
+        else if let Ok((x, rem)) = Word::parse(input) {
+            Ok((ReceivedToken::Word(x), rem))
+        }
         else {
             Err(ParseError::NotFound("Received Token"))
         }
@@ -1782,4 +2326,473 @@ impl Streamable for FieldName {
         self.0.stream(w)
     }
 }
+impl FieldName {
+    /// Compare against `name`, ignoring ASCII case (field names are
+    /// case-insensitive)
+    pub fn eq_ignore_case(&self, name: &str) -> bool {
+        (self.0).0.eq_ignore_ascii_case(name.as_bytes())
+    }
+}
 impl_display!(FieldName);
+
+// RFC 2045, 5.1
+// tspecials :=  "(" / ")" / "<" / ">" / "@" /
+//               "," / ";" / ":" / "\" / <">
+//               "/" / "[" / "]" / "?" / "="
+#[inline]
+pub fn is_tspecial(c: u8) -> bool {
+    c==b'(' || c==b')' || c==b'<' || c==b'>' || c==b'@'
+        || c==b',' || c==b';' || c==b':' || c==b'\\' || c==b'"'
+        || c==b'/' || c==b'[' || c==b']' || c==b'?' || c==b'='
+}
+
+// RFC 2045, 5.1
+// token := 1*<any (US-ASCII) CHAR except SPACE, CTLs,
+//             or tspecials>
+#[inline]
+pub fn is_token(c: u8) -> bool { c>32 && c<127 && !is_tspecial(c) }
+def_cclass!(Token, is_token);
+impl_display!(Token);
+
+// RFC 2045, 5.1
+// value := token / quoted-string
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterValue {
+    Token(Token),
+    QuotedString(QuotedString),
+}
+impl Parsable for ParameterValue {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Parameter Value")); }
+        if let Ok((x, rem)) = Token::parse(input) {
+            Ok((ParameterValue::Token(x), rem))
+        }
+        else if let Ok((x, rem)) = QuotedString::parse(input) {
+            Ok((ParameterValue::QuotedString(x), rem))
+        }
+        else {
+            Err(ParseError::NotFound("Parameter Value"))
+        }
+    }
+}
+impl Streamable for ParameterValue {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        match *self {
+            ParameterValue::Token(ref x) => x.stream(w),
+            ParameterValue::QuotedString(ref x) => x.stream(w),
+        }
+    }
+}
+impl ParameterValue {
+    /// The value, with any quoting removed
+    pub fn text(&self) -> String {
+        match *self {
+            ParameterValue::Token(ref t) => String::from_utf8_lossy(&t.0).into_owned(),
+            ParameterValue::QuotedString(ref qs) => qs.text(),
+        }
+    }
+}
+impl_display!(ParameterValue);
+
+// RFC 2045, 5.1
+// parameter := attribute "=" value
+// attribute := token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub pre_cfws: Option<CFWS>,
+    pub attribute: Token,
+    pub value: ParameterValue,
+    pub post_cfws: Option<CFWS>,
+}
+impl Parsable for Parameter {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Parameter")); }
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        if let Ok(attribute) = parse!(Token, rem) {
+            req!(rem, b"=", input);
+            if let Ok(value) = parse!(ParameterValue, rem) {
+                let post_cfws = parse!(CFWS, rem);
+                return Ok((Parameter {
+                    pre_cfws: pre_cfws.ok(),
+                    attribute: attribute,
+                    value: value,
+                    post_cfws: post_cfws.ok(),
+                }, rem));
+            }
+        }
+        Err(ParseError::NotFound("Parameter"))
+    }
+}
+impl Streamable for Parameter {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += self.attribute.stream(w)?;
+        count += w.write(b"=")?;
+        count += self.value.stream(w)?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl_display!(Parameter);
+
+// RFC 2045, 5.1
+// (the value of a content-type field)
+// content := "Content-Type" ":" type "/" subtype
+//            *(";" parameter)
+// type := discrete-type / composite-type     ; all tokens
+// subtype := extension-token / iana-token
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaType {
+    pub pre_cfws: Option<CFWS>,
+    pub top_level: Token,
+    pub sub_level: Token,
+    pub post_cfws: Option<CFWS>,
+    pub parameters: Vec<Parameter>,
+}
+impl Parsable for MediaType {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Media Type")); }
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        let top_level = match parse!(Token, rem) {
+            Ok(t) => t,
+            Err(_) => return Err(ParseError::NotFound("Media Type")),
+        };
+        req!(rem, b"/", input);
+        let sub_level = match parse!(Token, rem) {
+            Ok(t) => t,
+            Err(_) => return Err(ParseError::NotFound("Media Type")),
+        };
+        let post_cfws = parse!(CFWS, rem);
+        let mut parameters: Vec<Parameter> = Vec::new();
+        while rem.len() > 0 && rem[0]==b';' {
+            let mut rem2 = &rem[1..];
+            if let Ok(p) = parse!(Parameter, rem2) {
+                parameters.push(p);
+                rem = rem2;
+            } else {
+                break;
+            }
+        }
+        Ok((MediaType {
+            pre_cfws: pre_cfws.ok(),
+            top_level: top_level,
+            sub_level: sub_level,
+            post_cfws: post_cfws.ok(),
+            parameters: parameters,
+        }, rem))
+    }
+}
+impl Streamable for MediaType {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += self.top_level.stream(w)?;
+        count += w.write(b"/")?;
+        count += self.sub_level.stream(w)?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        for p in &self.parameters {
+            count += w.write(b";")?;
+            count += p.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl MediaType {
+    /// Fetch the unquoted value of the parameter named `attribute` (compared
+    /// case-insensitively)
+    pub fn parameter(&self, attribute: &str) -> Option<String> {
+        for p in &self.parameters {
+            if p.attribute.0.eq_ignore_ascii_case(attribute.as_bytes()) {
+                return Some(p.value.text());
+            }
+        }
+        None
+    }
+
+    /// Fetch all parameters as a map from lowercased attribute to unquoted
+    /// value
+    pub fn parameter_map(&self) -> HashMap<String, String> {
+        let mut output: HashMap<String, String> = HashMap::new();
+        for p in &self.parameters {
+            output.insert(format!("{}", p.attribute).to_ascii_lowercase(), p.value.text());
+        }
+        output
+    }
+}
+impl_display!(MediaType);
+
+// RFC 2231, 3 and 4
+// Find the value of the parameter `name`, joining any continuations
+// (`name*0`, `name*1`, ...) and decoding any that are extended (`name*`,
+// `name*0*`, ...) from their charset. An extended value is preferred over a
+// plain one.
+fn extended_parameter(parameters: &[Parameter], name: &str) -> Option<String> {
+    let name = name.to_ascii_lowercase();
+    let mut plain: Option<String> = None;
+    // (section number, is extended, value)
+    let mut sections: Vec<(u32, bool, Vec<u8>)> = Vec::new();
+    for p in parameters {
+        let attribute = String::from_utf8_lossy(&p.attribute.0).to_ascii_lowercase();
+        if attribute == name {
+            plain = Some(p.value.text());
+            continue;
+        }
+        if !attribute.starts_with(&*name) || attribute[name.len()..].chars().next() != Some('*') {
+            continue;
+        }
+        let section = &attribute[name.len() + 1..];
+        let (section, extended) = if section.ends_with('*') {
+            (&section[..section.len() - 1], true)
+        } else {
+            (section, section.is_empty())
+        };
+        let number: u32 = if section.is_empty() { 0 } else {
+            match section.parse() { Ok(n) => n, Err(_) => continue }
+        };
+        sections.push((number, extended, p.value.text().into_bytes()));
+    }
+    if sections.is_empty() {
+        return plain;
+    }
+    sections.sort_by_key(|&(n, _, _)| n);
+    let mut charset: Vec<u8> = b"us-ascii".to_vec();
+    let mut octets: Vec<u8> = Vec::new();
+    for (i, &(_, extended, ref value)) in sections.iter().enumerate() {
+        if !extended {
+            octets.extend_from_slice(value);
+            continue;
+        }
+        let mut value: &[u8] = value;
+        if i == 0 {
+            // charset "'" [language] "'" value
+            let mut parts = value.splitn(3, |&c| c == b'\'');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(cs), Some(_), Some(v)) => {
+                    if !cs.is_empty() { charset = cs.to_ascii_lowercase(); }
+                    value = v;
+                },
+                _ => {
+                    // Without its charset the value cannot be decoded, so
+                    // use the plain one instead, or else take it as it is
+                    if plain.is_some() { return plain; }
+                    octets.extend_from_slice(value);
+                    continue;
+                },
+            }
+        }
+        let mut pos: usize = 0;
+        while pos < value.len() {
+            let escaped = if value[pos] == b'%' && pos + 2 < value.len() {
+                ::std::str::from_utf8(&value[pos + 1..pos + 3]).ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            } else {
+                None
+            };
+            match escaped {
+                Some(c) => { octets.push(c); pos += 3; },
+                None => { octets.push(value[pos]); pos += 1; },
+            }
+        }
+    }
+    match super::encoded_word::decode_charset(&charset, octets.clone()) {
+        Some(s) => Some(s),
+        None => Some(String::from_utf8_lossy(&octets).into_owned()),
+    }
+}
+
+// RFC 2183, 2
+// disposition := "Content-Disposition" ":"
+//                disposition-type
+//                *(";" disposition-parm)
+// disposition-type := "inline"
+//                   / "attachment"
+//                   / extension-token
+//                   ; values are not case-sensitive
+// disposition-parm := filename-parm / creation-date-parm / ... / parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disposition {
+    pub pre_cfws: Option<CFWS>,
+    pub disposition_type: Token,
+    pub post_cfws: Option<CFWS>,
+    pub parameters: Vec<Parameter>,
+}
+impl Parsable for Disposition {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Disposition")); }
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        let disposition_type = match parse!(Token, rem) {
+            Ok(t) => t,
+            Err(_) => return Err(ParseError::NotFound("Disposition")),
+        };
+        let post_cfws = parse!(CFWS, rem);
+        let mut parameters: Vec<Parameter> = Vec::new();
+        while rem.len() > 0 && rem[0]==b';' {
+            let mut rem2 = &rem[1..];
+            if let Ok(p) = parse!(Parameter, rem2) {
+                parameters.push(p);
+                rem = rem2;
+            } else {
+                break;
+            }
+        }
+        Ok((Disposition {
+            pre_cfws: pre_cfws.ok(),
+            disposition_type: disposition_type,
+            post_cfws: post_cfws.ok(),
+            parameters: parameters,
+        }, rem))
+    }
+}
+impl Streamable for Disposition {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += self.disposition_type.stream(w)?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        for p in &self.parameters {
+            count += w.write(b";")?;
+            count += p.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl Disposition {
+    /// Whether the disposition type is `attachment` (compared
+    /// case-insensitively)
+    pub fn is_attachment(&self) -> bool {
+        self.disposition_type.0.eq_ignore_ascii_case(b"attachment")
+    }
+
+    /// Fetch the value of the parameter named `attribute` (compared
+    /// case-insensitively), joining RFC 2231 continuations and decoding an
+    /// RFC 2231 charset if present
+    pub fn parameter(&self, attribute: &str) -> Option<String> {
+        extended_parameter(&self.parameters, attribute)
+    }
+
+    /// Fetch the `filename` parameter
+    pub fn filename(&self) -> Option<String> {
+        self.parameter("filename")
+    }
+
+    /// Fetch the `creation-date` parameter, which is an RFC 5322 date-time
+    pub fn creation_date(&self) -> Option<DateTime> {
+        let value = self.parameter("creation-date")?;
+        match DateTime::parse(value.as_bytes()) {
+            Ok((dt, rem)) if rem.is_empty() => Some(dt),
+            _ => None,
+        }
+    }
+}
+impl_display!(Disposition);
+
+// RFC 3282, 2 and RFC 5646, 2.1
+// Language-Tag = [CFWS] Language-tag [CFWS]
+// We accept a primary subtag of 2 to 8 letters (or the singleton "x" or "i"
+// of private use and grandfathered tags), then any number of subtags of 1
+// to 8 letters and digits, each preceded by "-". This admits every
+// well-formed tag without checking the subtags against the registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageTag {
+    pub pre_cfws: Option<CFWS>,
+    pub tag: String,
+    pub post_cfws: Option<CFWS>,
+}
+impl Parsable for LanguageTag {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Language Tag")); }
+        let mut rem = input;
+        let pre_cfws = parse!(CFWS, rem);
+        let len = rem.iter().take_while(|&&c| is_alpha(c) || is_digit(c) || c==b'-').count();
+        let tag = &rem[..len];
+        let mut subtags = tag.split(|&c| c==b'-');
+        let primary_ok = match subtags.next() {
+            Some(p) if p.len() == 1 => p.eq_ignore_ascii_case(b"x") || p.eq_ignore_ascii_case(b"i"),
+            Some(p) => p.len() >= 2 && p.len() <= 8 && p.iter().all(|&c| is_alpha(c)),
+            None => false,
+        };
+        if !primary_ok || !subtags.all(|s| s.len() >= 1 && s.len() <= 8) {
+            return Err(ParseError::NotFound("Language Tag"));
+        }
+        rem = &rem[len..];
+        let post_cfws = parse!(CFWS, rem);
+        Ok((LanguageTag {
+            pre_cfws: pre_cfws.ok(),
+            // letters, digits and hyphens are ASCII
+            tag: String::from_utf8_lossy(tag).into_owned(),
+            post_cfws: post_cfws.ok(),
+        }, rem))
+    }
+}
+impl Streamable for LanguageTag {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        let mut count: usize = 0;
+        if let Some(ref cfws) = self.pre_cfws {
+            count += cfws.stream(w)?;
+        }
+        count += w.write(self.tag.as_bytes())?;
+        if let Some(ref cfws) = self.post_cfws {
+            count += cfws.stream(w)?;
+        }
+        Ok(count)
+    }
+}
+impl_display!(LanguageTag);
+
+// RFC 2045, 6.1
+// mechanism := "7bit" / "8bit" / "binary" /
+//              "quoted-printable" / "base64" /
+//              ietf-token / x-token
+// (we only accept the mechanisms defined by RFC 2045)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mechanism {
+    SevenBit,
+    EightBit,
+    Binary,
+    QuotedPrintable,
+    Base64,
+}
+impl Parsable for Mechanism {
+    fn parse(input: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        if input.len() == 0 { return Err(ParseError::Eof("Mechanism")); }
+        let (token, rem) = match Token::parse(input) {
+            Ok(x) => x,
+            Err(_) => return Err(ParseError::NotFound("Mechanism")),
+        };
+        let token = token.0.to_ascii_lowercase();
+        if token==b"7bit" { Ok((Mechanism::SevenBit, rem)) }
+        else if token==b"8bit" { Ok((Mechanism::EightBit, rem)) }
+        else if token==b"binary" { Ok((Mechanism::Binary, rem)) }
+        else if token==b"quoted-printable" { Ok((Mechanism::QuotedPrintable, rem)) }
+        else if token==b"base64" { Ok((Mechanism::Base64, rem)) }
+        else { Err(ParseError::NotFound("Mechanism")) }
+    }
+}
+impl Streamable for Mechanism {
+    fn stream<W: Write>(&self, w: &mut W) -> Result<usize, IoError> {
+        match *self {
+            Mechanism::SevenBit => Ok(w.write(b"7bit")?),
+            Mechanism::EightBit => Ok(w.write(b"8bit")?),
+            Mechanism::Binary => Ok(w.write(b"binary")?),
+            Mechanism::QuotedPrintable => Ok(w.write(b"quoted-printable")?),
+            Mechanism::Base64 => Ok(w.write(b"base64")?),
+        }
+    }
+}
+impl_display!(Mechanism);
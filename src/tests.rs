@@ -10,6 +10,14 @@ macro_rules! assert_match {
 
 use rfc5322::{Parsable, ParseError, Streamable};
 
+// The offset and the error located by a ParseError::At
+fn located(err: ParseError) -> (usize, ParseError) {
+    match err {
+        ParseError::At { offset, inner, .. } => (offset, *inner),
+        e => panic!("expected ParseError::At, got {}", e),
+    }
+}
+
 #[test]
 fn test_alpha() {
     use rfc5322::types::Alpha;
@@ -120,6 +128,8 @@ fn test_comment() {
     let mut output: Vec<u8> = Vec::new();
     assert_eq!(token.stream(&mut output).unwrap(), 27);
     assert_eq!(output, b"( a,b,c \\nYes (and so on) )");
+
+    assert_eq!(token.text(), " a,b,c nYes (and so on) ");
 }
 
 #[test]
@@ -252,7 +262,7 @@ fn test_quoted_string() {
 
 #[test]
 fn test_phrase() {
-    use rfc5322::types::Phrase;
+    use rfc5322::types::{Phrase, Word, Dot};
 
     let input = b" John \"the Snake\" Stevens".to_vec();
     let (phrase, remainder) = Phrase::parse(input.as_slice()).unwrap();
@@ -263,6 +273,16 @@ fn test_phrase() {
     let (phrase, remainder) = Phrase::parse(input.as_slice()).unwrap();
     assert_eq!(phrase.0.len(), 2);
     assert_eq!(remainder, b"[Doctor]");
+
+    // obs-phrase
+    let input = b" J. Random . Hacker <".to_vec();
+    let (phrase, remainder) = Phrase::parse(input.as_slice()).unwrap();
+    assert_eq!(phrase.0.len(), 5);
+    assert_eq!(remainder, b"<");
+    assert_eq!(format!("{}", phrase), " J. Random . Hacker ");
+    assert!(assert_match!(phrase.0[1], Word::Dot(Dot { pre_cfws: None, post_cfws: Some(_) })));
+    assert!(assert_match!(phrase.0[3], Word::Dot(_)));
+    assert_eq!(phrase.text(), "J. Random . Hacker");
 }
 
 #[test]
@@ -386,7 +406,7 @@ fn test_mailbox_list() {
 
 #[test]
 fn test_zone() {
-    use rfc5322::types::Zone;
+    use rfc5322::types::{Zone, Time};
 
     let input = b" +1135".to_vec();
     let (v, rem) = Zone::parse(input.as_slice()).unwrap();
@@ -401,6 +421,19 @@ fn test_zone() {
     let mut output: Vec<u8> = Vec::new();
     assert_eq!(v.stream(&mut output).unwrap(), 6);
     assert_eq!(output, b" -0700".to_vec());
+
+    // -0000 (an unknown local zone) is kept distinct from +0000 by Time
+    let (t, _) = Time::parse(b"15:13:05 -0000").unwrap();
+    assert!(t.is_unknown_local_zone());
+    assert_eq!(t.zone, Zone(0));
+    assert_eq!(t.zone.offset_seconds(), 0);
+    assert_eq!(format!("{}", t), "15:13:05 -0000");
+    let (t2, _) = Time::parse(b"15:13:05 +0000").unwrap();
+    assert!(!t2.is_unknown_local_zone());
+    assert_eq!(format!("{}", t2), "15:13:05 +0000");
+    assert!(t != t2);
+    assert_eq!(t, Time::in_unknown_local_zone(t2.time_of_day.clone()));
+    assert_eq!(t2, Time::new(t.time_of_day.clone(), Zone(0)));
 }
 
 #[test]
@@ -458,6 +491,30 @@ fn test_date_from_chrono()
     let _: OrigDate = TryFrom::try_from(&input).unwrap();
 }
 
+#[cfg(feature="chrono")]
+#[test]
+fn test_date_to_chrono()
+{
+    use ::Email;
+    use chrono::{Datelike, Timelike};
+
+    let email = Email::new("myself@mydomain.com", "Mon, 5 Jan 2015 15:13:05 -1330").unwrap();
+    let dt = email.get_date_chrono().unwrap();
+    assert_eq!(dt.to_rfc2822(), "Mon, 5 Jan 2015 15:13:05 -1330");
+    assert_eq!(dt.offset().local_minus_utc(), -(13 * 3600 + 30 * 60));
+    assert_eq!((dt.year(), dt.month(), dt.day()), (2015, 1, 5));
+    assert_eq!((dt.hour(), dt.minute(), dt.second()), (15, 13, 5));
+
+    // Seconds are optional
+    let email = Email::new("myself@mydomain.com", "5 Jan 2015 15:13 +1300").unwrap();
+    let dt = email.get_date_chrono().unwrap();
+    assert_eq!(dt.to_rfc2822(), "Mon, 5 Jan 2015 15:13:00 +1300");
+
+    // An impossible date is rejected
+    let email = Email::new("myself@mydomain.com", "30 Feb 2015 15:13 +1300").unwrap();
+    assert!(email.get_date_chrono().is_err());
+}
+
 #[cfg(feature="time")]
 #[test]
 fn test_date_from_time()
@@ -469,6 +526,48 @@ fn test_date_from_time()
     let _: OrigDate = TryFrom::try_from(&input).unwrap();
 }
 
+#[cfg(any(feature="time", feature="chrono"))]
+#[test]
+fn test_set_date_now()
+{
+    use ::Email;
+    use rfc5322::headers::OrigDate;
+
+    let mut email = Email::new("myself@mydomain.com", "Mon, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_date_now();
+    let date = email.get_date().unwrap();
+    assert!((date.0).date.year.0 > 2015);
+    let streamed = format!("{}", date);
+    let (reparsed, rem) = OrigDate::parse(streamed.as_bytes()).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(reparsed, date);
+}
+
+#[cfg(feature="time")]
+#[test]
+fn test_date_to_tm()
+{
+    use TryFrom;
+    use rfc5322::headers::OrigDate;
+
+    let date: OrigDate = TryFrom::try_from("Mon, 5 Jan 2015 15:13:05 -1330").unwrap();
+    let tm = date.to_tm().unwrap();
+    assert_eq!((tm.tm_year, tm.tm_mon, tm.tm_mday), (115, 0, 5));
+    assert_eq!((tm.tm_hour, tm.tm_min, tm.tm_sec), (15, 13, 5));
+    assert_eq!((tm.tm_wday, tm.tm_yday), (1, 4));
+    assert_eq!(tm.tm_utcoff, -(13 * 3600 + 30 * 60));
+    let back: OrigDate = TryFrom::try_from(&tm).unwrap();
+    assert_eq!(format!("{}", back), "Date:Mon, 5 Jan 2015 15:13:05 -1330\r\n");
+
+    // A leap year's last day, a Sunday
+    let date: OrigDate = TryFrom::try_from("31 Dec 2000 23:59:59 +0100").unwrap();
+    let tm = date.to_tm().unwrap();
+    assert_eq!((tm.tm_wday, tm.tm_yday), (0, 365));
+
+    let email = ::Email::new("myself@mydomain.com", "29 Feb 2015 00:00 +0000").unwrap();
+    assert!(email.get_date_tm().is_err());
+}
+
 #[test]
 fn test_date_time() {
     use rfc5322::types::DateTime;
@@ -660,16 +759,16 @@ fn test_email_struct_functions() {
                                "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
 
     email.set_date("Wed, 6 Jan 2015 15:13:05 +1300".as_bytes()).unwrap();
-    let date1 = email.get_date();
+    let date1 = email.get_date().unwrap();
     email.set_date("Fri, 30 Dec 2000 09:11:56 -1100").unwrap();
-    let date2 = email.get_date();
+    let date2 = email.get_date().unwrap();
     assert!(date1 != date2);
     email.set_date(date2).unwrap();
 
     email.set_from("mike@sample.com".as_bytes()).unwrap();
-    let from1 = email.get_from();
+    let from1 = email.get_from().unwrap();
     email.set_from("mike@sample2.com").unwrap();
-    let from2 = email.get_from();
+    let from2 = email.get_from().unwrap();
     assert!(from1 != from2);
     email.set_from(from2).unwrap();
 
@@ -852,3 +951,2623 @@ fn test_as_sendable_email() {
                 false );
 
 }
+
+#[cfg(feature="lettre010")]
+#[test]
+fn test_as_lettre_sendable() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+
+    let input = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: Myself <myself@mydomain.com>\r\n\
+                 To: target@publicdomain.com\r\n\
+                 Cc: Target <target@publicdomain.com>\r\n\
+                 Bcc: accomplice@secretdomain.com\r\n\
+                 Message-ID: <id/20161128115731.29084.maelstrom@mydomain.com>\r\n\
+                 Subject: Hello Friend\r\n\
+                 \r\n\
+                 Good to hear from you.".as_bytes();
+
+    let (email, remainder) = Email::parse(&input).unwrap();
+    assert_eq!(remainder.len(), 0);
+
+    let sendable = email.as_lettre_sendable().unwrap();
+    assert_eq!(sendable.message_id, "id/20161128115731.29084.maelstrom@mydomain.com");
+    assert_eq!(sendable.envelope.from().unwrap().to_string(), "myself@mydomain.com");
+    let recipients: Vec<String> = sendable.envelope.to().iter()
+        .map(|a| a.to_string()).collect();
+    assert_eq!(recipients, vec!["target@publicdomain.com", "accomplice@secretdomain.com"]);
+
+    // the Bcc line is not in the message, but remains in the email
+    assert!(!String::from_utf8(sendable.message).unwrap().contains("accomplice"));
+    assert!(email.get_bcc().is_some());
+}
+
+#[test]
+fn test_return_path() {
+    use ::Email;
+    use ::rfc5322::{Parsable, Streamable};
+
+    let input = "Return-Path: <bounces@mydomain.com>\r\n\
+                 Received: from mail.mydomain.com by mx.yourdomain.com;\
+                 Wed, 5 Jan 2015 15:13:05\r\n +1300\r\n\
+                 Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: myself@mydomain.com\r\n\
+                 Subject: Hello Friend\r\n\
+                 \r\n\
+                 Good to hear from you.".as_bytes();
+
+    let (mut email, remainder) = Email::parse(&input).unwrap();
+    assert_eq!(remainder.len(), 0);
+
+    // The fields after the trace must not be absorbed into it
+    assert!(email.get_subject().is_some());
+
+    let mut output: Vec<u8> = Vec::new();
+    email.stream(&mut output).unwrap();
+    assert_eq!(input, &*output);
+
+    assert_eq!(&*format!("{}", email.get_return_path().unwrap()),
+               "Return-Path: <bounces@mydomain.com>\r\n");
+    email.set_return_path("<other@mydomain.com>").unwrap();
+    assert_eq!(&*format!("{}", email.get_return_path().unwrap()),
+               "Return-Path:<other@mydomain.com>\r\n");
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.get_return_path().is_none());
+    assert!(email.set_return_path("<other@mydomain.com>").is_err());
+}
+
+#[test]
+fn test_add_received() {
+    use ::Email;
+    use ::rfc5322::{Parsable, Streamable};
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.get_received().len(), 0);
+    email.add_received("from mail.mydomain.com by mx1.yourdomain.com; \
+                        Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_received("from mx1.yourdomain.com by mx2.yourdomain.com; \
+                        Wed, 5 Jan 2015 15:13:07 +1300").unwrap();
+    assert_eq!(email.get_received().len(), 2);
+    email.set_return_path("<myself@mydomain.com>").unwrap();
+    email.set_body("Hello").unwrap();
+
+    let mut output: Vec<u8> = Vec::new();
+    email.stream(&mut output).unwrap();
+    assert_eq!(&*output,
+               "Return-Path:<myself@mydomain.com>\r\n\
+                Received:from mx1.yourdomain.com by mx2.yourdomain.com; \
+                Wed, 5 Jan 2015\r\n 15:13:07 +1300\r\n\
+                Received:from mail.mydomain.com by mx1.yourdomain.com; \
+                Wed, 5 Jan 2015\r\n 15:13:05 +1300\r\n\
+                Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                From:myself@mydomain.com\r\n\
+                \r\n\
+                Hello".as_bytes());
+
+    // And it parses back into the same structure
+    let (email2, remainder) = Email::parse(&output).unwrap();
+    assert_eq!(remainder.len(), 0);
+    assert_eq!(email2.get_received(), email.get_received());
+    assert!(email2.get_return_path().is_some());
+}
+
+#[test]
+fn test_optional_field_value() {
+    use ::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject("List-Unsubscribe").unwrap();
+    email.add_optional_field(("List-Unsubscribe", "<mailto:leave@mydomain.com>")).unwrap();
+
+    assert!(email.has_optional_field("list-unsubscribe"));
+    assert!(!email.has_optional_field("Subject"));
+    assert_eq!(email.get_optional_field_value("LIST-UNSUBSCRIBE").unwrap(),
+               b"<mailto:leave@mydomain.com>".to_vec());
+    assert!(email.get_optional_field_value("Subject").is_none());
+}
+
+#[test]
+fn test_set_optional_field() {
+    use ::Email;
+    use ::rfc5322::Streamable;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_optional_field(("X-Mailer", "first")).unwrap();
+    email.set_subject("Hello").unwrap();
+    email.add_optional_field(("x-mailer", "second")).unwrap();
+    email.add_optional_field(("X-Other", "other")).unwrap();
+
+    email.set_optional_field(("X-MAILER", "third")).unwrap();
+    email.set_optional_field(("X-New", "new")).unwrap();
+
+    let mut output: Vec<u8> = Vec::new();
+    email.stream(&mut output).unwrap();
+    assert_eq!(&*output,
+               "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                From:myself@mydomain.com\r\n\
+                X-MAILER:third\r\n\
+                Subject:Hello\r\n\
+                X-Other:other\r\n\
+                X-New:new\r\n".as_bytes());
+
+    email.clear_optional_field("x-mailer");
+    assert_eq!(email.get_optional_fields().len(), 2);
+    assert!(!email.has_optional_field("X-Mailer"));
+}
+
+#[test]
+fn test_fields_iterator() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+
+    let input = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: myself@mydomain.com\r\n\
+                 Message-ID: <id/1@mydomain.com>\r\n\
+                 My-Crazy-Field: this is my field\r\n\
+                 Subject: Hello Friend\r\n\
+                 \r\n\
+                 Body".as_bytes();
+    let (email, _) = Email::parse(&input).unwrap();
+
+    let fields: Vec<(String, Vec<u8>)> = email.fields().collect();
+    assert_eq!(fields, vec![
+        ("Date".to_owned(), b" Wed, 5 Jan 2015 15:13:05 +1300".to_vec()),
+        ("From".to_owned(), b" myself@mydomain.com".to_vec()),
+        ("Message-ID".to_owned(), b" <id/1@mydomain.com>".to_vec()),
+        ("My-Crazy-Field".to_owned(), b" this is my field".to_vec()),
+        ("Subject".to_owned(), b" Hello Friend".to_vec()),
+    ]);
+}
+
+#[test]
+fn test_content_type() {
+    use ::Email;
+    use ::rfc5322::{Parsable, Streamable};
+    use ::rfc5322::headers::ContentType;
+
+    let input = b"content-type: multipart/mixed; boundary=\"abc def\" (comment);\r\n charset=utf-8\r\n".to_vec();
+    let (ct, rem) = ContentType::parse(input.as_slice()).unwrap();
+    assert_eq!(rem, b"");
+    assert_eq!(ct.0.top_level.0, b"multipart".to_vec());
+    assert_eq!(ct.0.sub_level.0, b"mixed".to_vec());
+    assert_eq!(ct.0.parameter("Boundary").unwrap(), "abc def");
+    assert_eq!(ct.0.parameter("charset").unwrap(), "utf-8");
+    assert!(ct.0.parameter("name").is_none());
+    assert_eq!(ct.0.parameter_map().len(), 2);
+
+    let mut output: Vec<u8> = Vec::new();
+    ct.stream(&mut output).unwrap();
+    assert_eq!(output,
+               b"Content-Type: multipart/mixed; boundary=\"abc def\" (comment); charset=utf-8\r\n".to_vec());
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.set_content_type("text").is_err());
+    assert!(email.set_content_type("text/plain; charset").is_err());
+    email.add_optional_field(("Content-Type", "text/html")).unwrap();
+    email.set_content_type("text/plain; charset=us-ascii").unwrap();
+    assert_eq!(email.get_optional_fields().len(), 0);
+    assert_eq!(email.get_content_type().unwrap().0.parameter("charset").unwrap(),
+               "us-ascii");
+
+    let input = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: myself@mydomain.com\r\n\
+                 Content-Type: text/plain; charset=\"utf-8\"\r\n\
+                 \r\n\
+                 Body".as_bytes();
+    let (mut email, _) = Email::parse(&input).unwrap();
+    assert!(email.get_content_type().is_some());
+    assert_eq!(email.as_bytes(), input.to_vec());
+
+    email.clear_content_type();
+    assert!(email.get_content_type().is_none());
+}
+
+#[test]
+fn test_content_transfer_encoding() {
+    use ::Email;
+    use ::TryFrom;
+    use ::rfc5322::Parsable;
+    use ::rfc5322::types::Mechanism;
+    use ::rfc5322::headers::ContentTransferEncoding;
+
+    let (cte, rem) = ContentTransferEncoding::parse(
+        b"Content-Transfer-Encoding: Quoted-Printable\r\n").unwrap();
+    assert_eq!(rem, b"");
+    assert_eq!(cte.mechanism, Mechanism::QuotedPrintable);
+    assert_eq!(&*format!("{}", cte), "Content-Transfer-Encoding: quoted-printable\r\n");
+
+    let err = ContentTransferEncoding::try_from("x-uuencode").err().unwrap();
+    assert!(assert_match!(err, ParseError::NotFound("Mechanism")));
+    assert!(ContentTransferEncoding::parse(
+        b"Content-Transfer-Encoding: base64x\r\n").is_err());
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.set_content_transfer_encoding("uuencode").is_err());
+    email.set_content_transfer_encoding("BASE64").unwrap();
+    email.set_content_transfer_encoding(Mechanism::SevenBit).unwrap();
+    assert_eq!(email.get_content_transfer_encoding().unwrap().mechanism,
+               Mechanism::SevenBit);
+    email.clear_content_transfer_encoding();
+    assert!(email.get_content_transfer_encoding().is_none());
+}
+
+#[test]
+fn test_base64() {
+    use rfc5322::encoding::{base64_encode, base64_decode};
+
+    assert_eq!(base64_encode(b""), b"".to_vec());
+    assert_eq!(base64_encode(b"f"), b"Zg==".to_vec());
+    assert_eq!(base64_encode(b"fo"), b"Zm8=".to_vec());
+    assert_eq!(base64_encode(b"foo"), b"Zm9v".to_vec());
+    assert_eq!(base64_encode(b"foobar"), b"Zm9vYmFy".to_vec());
+
+    assert_eq!(base64_decode(b"Zm9v\r\nYmE=").unwrap(), b"fooba".to_vec());
+    assert_eq!(base64_decode(b"Zg==").unwrap(), b"f".to_vec());
+    assert!(base64_decode(b"Zm9v!").is_err());
+    assert!(base64_decode(b"Z").is_err());
+    assert!(base64_decode(b"Zg==Zg==").is_err());
+}
+
+#[test]
+fn test_quoted_printable_decode() {
+    use rfc5322::encoding::quoted_printable_decode;
+
+    assert_eq!(quoted_printable_decode(b"caf=C3=A9 \t\r\nsoft =\r\nbreak").unwrap(),
+               "café\r\nsoft break".as_bytes().to_vec());
+    assert!(quoted_printable_decode(b"bad=ZZ").is_err());
+    assert!(quoted_printable_decode(b"bad=4").is_err());
+}
+
+#[test]
+fn test_body_base64() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+    use ::rfc5322::types::Mechanism;
+
+    let raw: Vec<u8> = (0..200).map(|x| x as u8).collect();
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body_base64(&raw);
+    assert_eq!(email.get_content_transfer_encoding().unwrap().mechanism,
+               Mechanism::Base64);
+    let body = email.get_body().unwrap();
+    assert!(body.0.split(|&c| c == b'\n').all(|line| line.len() <= 77));
+    assert_eq!(email.get_body_decoded().unwrap(), raw);
+
+    // The output parses back
+    let (email2, _) = Email::parse(&email.as_bytes()).unwrap();
+    assert_eq!(email2.get_body_decoded().unwrap(), raw);
+
+    email.set_content_transfer_encoding("quoted-printable").unwrap();
+    email.set_body("a=3Db").unwrap();
+    assert_eq!(email.get_body_decoded().unwrap(), b"a=b".to_vec());
+}
+
+#[test]
+fn test_subject_encoded() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+    use ::rfc5322::encoded_word::{encode, decode};
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject_encoded("Hello Friend").unwrap();
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject:Hello Friend\r\n");
+    assert_eq!(email.get_subject_decoded().unwrap(), "Hello Friend");
+
+    let text = "Grüße aus Köln — ünïcödé ".repeat(4);
+    email.set_subject_encoded(&*text).unwrap();
+    let subject = format!("{}", email.get_subject().unwrap());
+    assert!(subject.starts_with("Subject:=?UTF-8?B?"));
+    assert!(subject.trim_end().split(' ').all(|w| w.trim_start_matches("Subject:").len() <= 75));
+    assert!(subject.split(' ').count() > 1);
+    assert_eq!(email.get_subject_decoded().unwrap(), text);
+
+    // The output parses back
+    email.set_body("Hi").unwrap();
+    let (email2, _) = Email::parse(&email.as_bytes()).unwrap();
+    assert_eq!(email2.get_subject_decoded().unwrap(), text);
+
+    assert_eq!(encode(""), b"".to_vec());
+    assert_eq!(decode(b"=?utf-8?q?caf=C3=A9_au_?=  =?UTF-8?B?bGFpdA==?= now").unwrap(),
+               "café au lait now");
+    assert_eq!(decode(b"=?UTF-8?Q?bad=ZZ?= =?UTF-8?X?abc?=").unwrap(),
+               "=?UTF-8?Q?bad=ZZ?= =?UTF-8?X?abc?=");
+    assert_eq!(decode(b"folded\r\n text").unwrap(), "folded text");
+}
+
+#[test]
+fn test_encoded_word_decode() {
+    use ::Email;
+    use ::rfc5322::encoded_word::decode;
+
+    assert_eq!(decode(b"=?ISO-8859-1?Q?caf=E9?= =?us-ascii?B?b2s=?=").unwrap(), "caféok");
+    assert_eq!(decode(b"=?UTF-8*en?Q?hi?=").unwrap(), "hi");
+    // Malformed or unknown encoded-words are left as literal text
+    assert_eq!(decode(b"=?us-ascii?Q?=E9?= =?KOI8-R?Q?x?= =?UTF-8?B?!?=").unwrap(),
+               "=?us-ascii?Q?=E9?= =?KOI8-R?Q?x?= =?UTF-8?B?!?=");
+    assert!(decode(b"\xff").is_err());
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_comments(" =?ISO-8859-1?Q?Gr=FC=DFe?= aus =?UTF-8?B?S8O2bG4=?=").unwrap();
+    let mut raw: Vec<u8> = Vec::new();
+    email.get_comments()[0].0.stream(&mut raw).unwrap();
+    assert_eq!(decode(&raw).unwrap(), " Grüße aus Köln");
+
+    email.set_optional_field(("X-Note", "=?utf-8?q?=C3=BCber?=")).unwrap();
+    let raw = email.get_optional_field_value("X-Note").unwrap();
+    assert_eq!(decode(&raw).unwrap(), "über");
+}
+
+#[test]
+fn test_address_encoded() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+    use ::rfc5322::encoded_word::{decode, encode_display_names};
+
+    assert_eq!(encode_display_names("Joe <joe@a.com>, \"Smith, J\" <js@a.com>"),
+               b"Joe <joe@a.com>, \"Smith, J\" <js@a.com>".to_vec());
+    assert_eq!(encode_display_names("Hans Müller <hm@x.de>"),
+               b"Hans =?UTF-8?B?TcO8bGxlcg==?= <hm@x.de>".to_vec());
+    // Adjacent non-ASCII words are encoded together to keep their spacing
+    assert_eq!(decode(&encode_display_names("Zoë Müller von Köln")).unwrap(),
+               "Zoë Müller von Köln");
+    assert_eq!(decode(&encode_display_names("\"Müller, Hans\" <hm@x.de>")).unwrap(),
+               "Müller, Hans <hm@x.de>");
+    assert_eq!(encode_display_names("Grp: Zoë <z@x.com> (café);"),
+               b"Grp: =?UTF-8?B?Wm/Dqw==?= <z@x.com> (caf\xc3\xa9);".to_vec());
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.set_to("Müller <m@x.de>").is_err());
+    email.set_from_encoded("Zoë <zoe@mydomain.com>").unwrap();
+    email.set_reply_to_encoded("Zoë <zoe@mydomain.com>").unwrap();
+    email.set_to_encoded("Hans Müller <m@x.de>, Joe <joe@y.com>").unwrap();
+    email.set_cc_encoded("\"Ünal, B\" <b@z.com>").unwrap();
+    assert_eq!(format!("{}", email.get_to().unwrap()),
+               "To:Hans =?UTF-8?B?TcO8bGxlcg==?= <m@x.de>, Joe <joe@y.com>\r\n");
+
+    // The encoded-words round trip as atoms
+    email.set_body("Hi").unwrap();
+    let bytes = email.as_bytes();
+    let (email2, _) = Email::parse(&bytes).unwrap();
+    assert_eq!(email2.as_bytes(), bytes);
+}
+
+#[test]
+fn test_email_addresses() {
+    use ::Email;
+    use ::rfc5322::email_address::EmailAddress;
+
+    let mut email = Email::new("Me <myself@mydomain.com>, other@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.to_addresses().is_empty());
+    assert!(email.cc_addresses().is_empty());
+    email.set_to("You <you@yourdomain.com>, Friends: a@b.com, c@d.com;").unwrap();
+    email.set_cc("cc@ccdomain.com").unwrap();
+
+    let from = email.from_addresses();
+    assert_eq!(from.len(), 2);
+    assert_eq!(from[0].display_name, Some("Me".to_owned()));
+    assert_eq!(from[1].local_part, "other");
+
+    let to = email.to_addresses();
+    assert_eq!(to.iter().map(|a| &*a.local_part).collect::<Vec<_>>(), vec!["you", "a", "c"]);
+    assert_eq!(email.cc_addresses(), vec![EmailAddress {
+        display_name: None,
+        local_part: "cc".to_owned(),
+        domain: "ccdomain.com".to_owned(),
+        comments: Vec::new(),
+    }]);
+}
+
+#[test]
+fn test_all_recipients() {
+    use ::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.all_recipients().is_empty());
+    email.set_to("You <you@yourdomain.com>, Friends: a@b.com, c@d.com;").unwrap();
+    email.set_cc("Again <you@YourDomain.COM>, You@yourdomain.com").unwrap();
+    email.set_bcc("hidden@e.com, a@B.com").unwrap();
+    let recipients: Vec<String> = email.all_recipients().iter()
+        .map(|a| format!("{}@{}", a.local_part, a.domain))
+        .collect();
+    assert_eq!(recipients, vec!["you@yourdomain.com", "a@b.com", "c@d.com",
+                                "You@yourdomain.com", "hidden@e.com"]);
+}
+
+#[test]
+fn test_header_folding() {
+    use ::Email;
+    use ::rfc5322::Parsable;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    let references: Vec<String> = (0..20)
+        .map(|i| format!("<message{}.12345@some.long.domain.example.com>", i))
+        .collect();
+    email.set_references(&*references.join(" ")).unwrap();
+    email.set_subject(&*"word ".repeat(40)).unwrap();
+    email.add_keywords("averyveryveryveryveryveryveryveryveryveryveryveryveryveryveryveryverylongword").unwrap();
+    email.set_body("Hi").unwrap();
+
+    let bytes = email.as_bytes();
+    let header = &bytes[..bytes.len() - 6];
+    for line in header.split(|&c| c == b'\n') {
+        let line = if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line };
+        // A single word longer than a line cannot be folded
+        assert!(line.len() <= 78 || line.starts_with(b"Keywords:"));
+        assert!(line.iter().any(|&c| c != b' ' && c != b'\t'));
+    }
+    assert!(bytes.windows(3).any(|w| w == b"\r\n "));
+
+    // Re-parsing the folded output yields the same email
+    let (email2, rem) = Email::parse(&bytes).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(email2.get_references(), email.get_references());
+    assert_eq!(email2.get_subject(), email.get_subject());
+    assert_eq!(email2.get_keywords(), email.get_keywords());
+    assert_eq!(email2.as_bytes(), bytes);
+
+    // A value which cannot be folded to within 998 characters is rejected
+    let long = "x".repeat(1200);
+    assert!(assert_match!(email.set_subject(&*long), Err(ParseError::LineTooLong(1))));
+    assert!(assert_match!(email.set_references(&*format!("<{}@example.com>", long)),
+                          Err(ParseError::LineTooLong(1))));
+    assert_eq!(email.as_bytes(), bytes);
+    let input = format!("From:a@x.com\r\nSubject:{}\r\n\r\nBody", long);
+    let (_, inner) = located(Email::parse_complete(input.as_bytes()).unwrap_err());
+    assert!(assert_match!(inner, ParseError::LineTooLong(1)));
+
+    // A line continuing a field is folded again if it is still too long
+    let input = format!("Received: from a.example.com\r\n by {}; Wed, 5 Jan 2015 15:13:05 +1300\r\n",
+                        "word ".repeat(30));
+    let folded = ::rfc5322::fold(input.as_bytes());
+    assert!(folded.split(|&c| c == b'\n').all(|l| l.len() <= 79));
+    assert!(folded.split(|&c| c == b'\n').count() > 3);
+    assert!(!folded.windows(4).any(|w| w == b"\r\n\r\n"));
+}
+
+#[test]
+fn test_parse_from_reader() {
+    use std::io::{Read, Error, ErrorKind};
+    use ::Email;
+    use ::rfc5322::ParseError;
+
+    // Hands out a few bytes at a time, so tokens straddle reads
+    struct Trickle<'a>(&'a [u8]);
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            let n = ::std::cmp::min(::std::cmp::min(3, buf.len()), self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> Result<usize, Error> {
+            Err(Error::new(ErrorKind::Other, "broken"))
+        }
+    }
+
+    let input = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: myself@mydomain.com\r\n\
+                 Subject: Hello Friend\r\n\
+                 \r\n\
+                 Good to hear from you.\r\n\
+                 \r\n\
+                 Your Friend".as_bytes();
+    let email = Email::parse_from_reader(Trickle(input)).unwrap();
+    assert_eq!(email.get_subject_decoded().unwrap(), "Hello Friend");
+    assert_eq!(email.get_body().unwrap().0,
+               b"Good to hear from you.\r\n\r\nYour Friend".to_vec());
+    assert_eq!(email.as_bytes(), input.to_vec());
+
+    let email = Email::parse_from_reader(&b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                                            From: myself@mydomain.com\r\n"[..]).unwrap();
+    assert!(email.get_body().is_none());
+
+    assert!(assert_match!(Email::parse_from_reader(Broken), Err(ParseError::Io(_))));
+    assert!(assert_match!(Email::parse_from_reader(&b"Not a header\r\n\r\nBody"[..]),
+                          Err(ParseError::At { offset: 0, .. })));
+    assert!(assert_match!(Email::parse_from_reader(&b"Subject: Hi\r\n\r\nHi\xff"[..]),
+                          Err(ParseError::InvalidBodyChar(0xff))));
+}
+
+#[test]
+fn test_ref_getters() {
+    use ::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.subject_ref().is_none());
+    assert!(email.to_ref().is_none());
+    assert!(email.body_ref().is_none());
+    email.set_subject("Hello Friend").unwrap();
+    email.set_to("you@yourdomain.com").unwrap();
+    email.set_body("Hi").unwrap();
+
+    assert_eq!(email.subject_ref(), email.get_subject().as_ref());
+    assert_eq!(email.to_ref(), email.get_to().as_ref());
+    assert_eq!(email.date_ref(), email.get_date().as_ref());
+    assert_eq!(email.from_ref(), email.get_from().as_ref());
+    assert_eq!(email.body_ref().unwrap().0, b"Hi".to_vec());
+}
+
+#[cfg(feature="obsolete")]
+#[test]
+fn test_obsolete_syntax() {
+    use ::Email;
+    use rfc5322::types::{Domain, Unstructured};
+
+    let (domain, rem) = Domain::parse(b"mail . example (the isp) .com>").unwrap();
+    assert_eq!(rem, b">");
+    assert_eq!(format!("{}", domain), "mail.example.com");
+
+    let (u, rem) = Unstructured::parse(b" Hello\x01 \x00\x7f there\r\n").unwrap();
+    assert_eq!(rem, b"\r\n");
+    assert_eq!(format!("{}", u), " Hello there");
+    assert!(Unstructured::parse(b" \x01\x02").is_err());
+    let email = Email::parse_complete(b"From: a@b.com\r\nSubject: A\x07B\r\n").unwrap();
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject: AB\r\n");
+}
+
+#[test]
+fn test_named_zone() {
+    use ::Email;
+    use rfc5322::types::{Zone, Time, DateTime};
+
+    let (zone, rem) = Zone::parse(b" EDT\r\n").unwrap();
+    assert_eq!(zone, Zone(-400));
+    assert_eq!(rem, b"\r\n");
+    assert_eq!(Zone::parse(b" gmt").unwrap().0, Zone(0));
+    assert_eq!(Zone::parse(b" UT").unwrap().0, Zone(0));
+    assert_eq!(Zone::parse(b" CST").unwrap().0, Zone(-600));
+    assert_eq!(Zone::parse(b" Z").unwrap().0, Zone(0));
+    assert_eq!(Zone::parse(b" a").unwrap().0, Zone(0));
+    assert!(Time::parse(b"10:00 Z").unwrap().0.is_unknown_local_zone());
+    assert!(!Time::parse(b"10:00 UT").unwrap().0.is_unknown_local_zone());
+    assert!(Zone::parse(b" J").is_err());
+    assert!(Zone::parse(b" XYZ").is_err());
+    assert!(Zone::parse(b" ESTX").is_err());
+
+    let (dt, _) = DateTime::parse(b"Mon, 1 Jan 2001 00:00:00 PST").unwrap();
+    assert_eq!(format!("{}", dt), "Mon, 1 Jan 2001 00:00:00 -0800");
+
+    let email = Email::new("myself@mydomain.com", "Mon, 1 Jan 2001 00:00:00 GMT").unwrap();
+    assert_eq!(format!("{}", email.get_date().unwrap()), "Date:Mon, 1 Jan 2001 00:00:00 +0000\r\n");
+}
+
+#[test]
+fn test_generate_message_id() {
+    use ::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.generate_message_id("mydomain.com").unwrap();
+    let first = format!("{}", email.get_message_id().unwrap());
+    assert!(first.starts_with("Message-ID:<"));
+    assert!(first.ends_with("@mydomain.com>\r\n"));
+
+    email.generate_message_id("[192.0.2.1]").unwrap();
+    let second = format!("{}", email.get_message_id().unwrap());
+    assert!(second.ends_with("@[192.0.2.1]>\r\n"));
+    assert!(first[..first.len() - 15] != second[..second.len() - 14]);
+
+    assert!(email.generate_message_id("").is_err());
+    assert!(email.generate_message_id("my domain.com").is_err());
+    assert!(email.generate_message_id("mydomain.com>").is_err());
+    assert!(email.generate_message_id("[bad]]").is_err());
+    // A failed generation leaves the previous id in place
+    assert_eq!(format!("{}", email.get_message_id().unwrap()), second);
+}
+
+#[test]
+fn test_validate() {
+    use ::Email;
+    use ::rfc5322::ValidationError;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject("Hello").unwrap();
+    email.add_comments("one").unwrap();
+    email.add_comments("two").unwrap();
+    assert_eq!(email.validate(), Ok(()));
+
+    email.set_from("myself@mydomain.com, other@mydomain.com").unwrap();
+    assert_eq!(email.validate(), Err(vec![ValidationError::MissingSender]));
+    email.set_sender("myself@mydomain.com").unwrap();
+    assert_eq!(email.validate(), Ok(()));
+
+    let input = "Subject: One\r\n\
+                 Subject: Two\r\n\
+                 To: a@b.com\r\n\
+                 Subject: Three\r\n\
+                 To: c@d.com\r\n\
+                 \r\n\
+                 Body".as_bytes();
+    let (email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.validate(), Err(vec![
+        ValidationError::MissingDate,
+        ValidationError::MissingFrom,
+        ValidationError::DuplicateField("Subject".to_owned()),
+        ValidationError::DuplicateField("To".to_owned()),
+    ]));
+}
+
+#[test]
+fn test_email_builder() {
+    use super::{Email, EmailBuilder};
+
+    let email = EmailBuilder::new()
+        .from("myself@mydomain.com")
+        .date("Wed, 5 Jan 2015 15:13:05 +1300")
+        .to("You <you@yourdomain.com>")
+        .subject("Hello")
+        .subject("Hello Friend")
+        .body("Good to hear from you.")
+        .build()
+        .unwrap();
+    let mut expected = Email::new("myself@mydomain.com",
+                                  "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    expected.set_to("You <you@yourdomain.com>").unwrap();
+    expected.set_subject("Hello Friend").unwrap();
+    expected.set_body("Good to hear from you.").unwrap();
+    assert_eq!(format!("{}", email), format!("{}", expected));
+
+    let result = EmailBuilder::new()
+        .from("myself@mydomain.com")
+        .subject("Hello")
+        .build();
+    assert!(assert_match!(result, Err(ParseError::NotFound("Date"))));
+
+    let result = EmailBuilder::new()
+        .date("Wed, 5 Jan 2015 15:13:05 +1300")
+        .build();
+    assert!(assert_match!(result, Err(ParseError::NotFound("From"))));
+
+    let result = EmailBuilder::new()
+        .from("myself@mydomain.com")
+        .date("Wed, 5 Jan 2015 15:13:05 +1300")
+        .to("not an address")
+        .subject("Hello\r\nThere")
+        .build();
+    assert!(assert_match!(result, Err(_)));
+}
+
+#[test]
+fn test_add_recipients() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_to("a@example.com").unwrap();
+    email.add_to("Bee <b@example.com>").unwrap();
+    assert_eq!(format!("{}", email.get_to().unwrap()),
+               "To:a@example.com,Bee <b@example.com>\r\n");
+    email.set_cc("c@example.com").unwrap();
+    email.add_cc("d@example.com").unwrap();
+    assert_eq!(format!("{}", email.get_cc().unwrap()),
+               "Cc:c@example.com,d@example.com\r\n");
+    email.set_bcc(::rfc5322::headers::Bcc::Empty).unwrap();
+    email.add_bcc("e@example.com").unwrap();
+    email.add_bcc("f@example.com").unwrap();
+    assert_eq!(format!("{}", email.get_bcc().unwrap()),
+               "Bcc:e@example.com,f@example.com\r\n");
+    assert!(email.add_to("not an address").is_err());
+    assert_eq!(email.to_addresses().len(), 2);
+}
+
+#[test]
+fn test_remove_recipients() {
+    use super::Email;
+    use rfc5322::email_address::EmailAddress;
+
+    let addr = |local: &str, domain: &str| EmailAddress {
+        display_name: None,
+        local_part: local.to_owned(),
+        domain: domain.to_owned(),
+        comments: Vec::new(),
+    };
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_to("a@example.com, Bee <b@Example.COM>, c@example.com").unwrap();
+    assert!(email.remove_to(&addr("b", "example.com")));
+    assert!(!email.remove_to(&addr("B", "example.com")));
+    assert_eq!(format!("{}", email.get_to().unwrap()),
+               "To:a@example.com, c@example.com\r\n");
+    assert!(email.remove_to(&addr("a", "example.com")));
+    assert!(email.remove_to(&addr("c", "example.com")));
+    assert!(email.get_to().is_none());
+    assert!(!email.remove_to(&addr("c", "example.com")));
+
+    email.set_cc("d@example.com").unwrap();
+    email.set_bcc("e@example.com").unwrap();
+    assert!(email.remove_cc(&addr("d", "EXAMPLE.com")));
+    assert!(email.get_cc().is_none());
+    assert!(email.remove_bcc(&addr("e", "example.com")));
+    assert!(email.get_bcc().is_none());
+}
+
+#[test]
+fn test_email_eq_hash() {
+    use super::Email;
+    use std::collections::HashSet;
+
+    let mut a = Email::new("myself@mydomain.com",
+                           "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    a.set_subject("Hello").unwrap();
+    let b = a.clone();
+    let (c, _) = Email::parse(b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                                From: myself@mydomain.com\r\n\
+                                Subject:  Hello\r\n\
+                                \r\n\
+                                Body").unwrap();
+    assert_eq!(a, b);
+    assert!(a != c);
+
+    let mut set: HashSet<Email> = HashSet::new();
+    set.insert(a);
+    set.insert(b);
+    set.insert(c);
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_byte_len() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.byte_len(), email.as_bytes().len());
+    email.set_subject("Hello Friend").unwrap();
+    email.add_keywords("Lorem ipsum dolor sit amet consectetur adipiscing elit \
+                        sed do eiusmod tempor incididunt ut labore et dolore").unwrap();
+    email.add_received("from 127.0.0.1 by localhost with ESMTP id 12345; \
+                        Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body("Good to hear from you.\r\nWish you were here.\r\n").unwrap();
+    assert_eq!(email.byte_len(), email.as_bytes().len());
+}
+
+#[test]
+fn test_email_file() {
+    use super::Email;
+    use std::env;
+    use std::fs;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject("Hello Friend").unwrap();
+    email.set_body("Good to hear from you.\r\n").unwrap();
+
+    let path = env::temp_dir().join(format!("email-format-test-{}.eml", ::std::process::id()));
+    email.write_to_file(&path).unwrap();
+    let read = Email::read_from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read.as_bytes(), email.as_bytes());
+
+    assert!(assert_match!(Email::read_from_file(&path), Err(ParseError::Io(_))));
+}
+
+#[test]
+fn test_parse_error_offset() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Bad header\r\n\
+                  \r\n\
+                  Body";
+    let err = Email::parse_from_reader(&input[..]).err().unwrap();
+    match err {
+        ParseError::At { offset, ref context, ref inner } => {
+            assert_eq!(offset, 65);
+            assert_eq!(context, "f@mydomain.com\r\nBad header\r\n");
+            assert!(assert_match!(**inner, ParseError::NotFound("Field")));
+        },
+        _ => panic!("expected ParseError::At"),
+    }
+    assert_eq!(&*format!("{}", err),
+               "Failed at offset 65 near \"f@mydomain.com\\r\\nBad header\\r\\n\": \
+                \"Field\" Not Found");
+
+    // Email::parse() and parse_verbatim() locate a failure in the body at
+    // the start of its line
+    let input = b"From: myself@mydomain.com\r\n\
+                  \r\n\
+                  Line one\r\n\
+                  Line \xff\r\n";
+    let (offset, inner) = located(Email::parse(&input[..]).unwrap_err());
+    assert_eq!(offset, 39);
+    assert!(assert_match!(inner, ParseError::InvalidBodyChar(0xff)));
+    match Email::parse_verbatim(&input[..]) {
+        Err(ParseError::At { offset, ref context, .. }) => {
+            assert_eq!(offset, 39);
+            assert_eq!(context, "om\r\n\r\nLine one\r\nLine \u{fffd}\r\n");
+        },
+        _ => panic!("expected ParseError::At"),
+    }
+}
+
+#[test]
+fn test_set_from_parts() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_from_parts(Some("John Doe"), "john@x.com").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From:John Doe <john@x.com>\r\n");
+    email.set_from_parts(Some("Doe, John"), "john@x.com").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From:\"Doe, John\" <john@x.com>\r\n");
+    email.set_from_parts(Some("The \"Boss\""), "boss@x.com").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()),
+               "From:\"The \\\"Boss\\\"\" <boss@x.com>\r\n");
+    email.set_from_parts(Some("Zoë"), "zoe@x.com").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From:=?UTF-8?B?Wm/Dqw==?= <zoe@x.com>\r\n");
+    email.set_from_parts(None, "john@x.com").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From:john@x.com\r\n");
+    assert!(email.set_from_parts(Some("John Doe"), "John Doe john@x.com").is_err());
+    assert_eq!(email.from_addresses()[0].local_part, "john");
+}
+
+#[test]
+fn test_addr_spec_strings() {
+    use rfc5322::types::AddrSpec;
+
+    let (addr_spec, _) = AddrSpec::parse(b" (comment) john.doe (more)@ example.com (c)").unwrap();
+    assert_eq!(addr_spec.local_part_str(), "john.doe");
+    assert_eq!(addr_spec.domain_str(), "example.com");
+    assert_eq!(addr_spec.to_string_bare(), "john.doe@example.com");
+
+    let (addr_spec, _) = AddrSpec::parse(b"\"john \\\"jd\\\" doe\"@[192.0.2.1]").unwrap();
+    assert_eq!(addr_spec.local_part_str(), "john \"jd\" doe");
+    assert_eq!(addr_spec.domain_str(), "[192.0.2.1]");
+    assert_eq!(addr_spec.to_string_bare(), "\"john \\\"jd\\\" doe\"@[192.0.2.1]");
+}
+
+#[test]
+fn test_same_mailbox() {
+    use rfc5322::types::AddrSpec;
+
+    let (a, _) = AddrSpec::parse(b"Joe@Example.COM").unwrap();
+    let (b, _) = AddrSpec::parse(b"Joe (comment) @ example.com").unwrap();
+    let (c, _) = AddrSpec::parse(b"joe@example.com").unwrap();
+    let (d, _) = AddrSpec::parse(b"\"Joe\"@example.com").unwrap();
+    assert!(a.same_mailbox(&b));
+    assert!(b.same_mailbox(&a));
+    assert!(!a.same_mailbox(&c));
+    assert!(a.same_mailbox(&d));
+}
+
+#[test]
+fn test_domain_literal_ip_addr() {
+    use std::net::IpAddr;
+    use rfc5322::types::DomainLiteral;
+
+    let (dl, _) = DomainLiteral::parse(b"[192.0.2.1]").unwrap();
+    assert_eq!(dl.as_ip_addr(), Some("192.0.2.1".parse::<IpAddr>().unwrap()));
+    let (dl, _) = DomainLiteral::parse(b"[IPv6:2001:db8::1]").unwrap();
+    assert_eq!(dl.as_ip_addr(), Some("2001:db8::1".parse::<IpAddr>().unwrap()));
+    let (dl, _) = DomainLiteral::parse(b"[not an ip]").unwrap();
+    assert_eq!(dl.as_ip_addr(), None);
+    let (dl, _) = DomainLiteral::parse(b"[2001:db8::1]").unwrap();
+    assert_eq!(dl.as_ip_addr(), None);
+    let (dl, _) = DomainLiteral::parse(b"[IPv6:192.0.2.1]").unwrap();
+    assert_eq!(dl.as_ip_addr(), None);
+}
+
+#[test]
+fn test_idna() {
+    use super::Email;
+    use rfc5322::idna;
+
+    assert_eq!(idna::to_ascii("例え.jp").unwrap(), "xn--r8jz45g.jp");
+    assert_eq!(idna::to_ascii("Bücher.example").unwrap(), "xn--bcher-kva.example");
+    assert_eq!(idna::to_ascii("example.com").unwrap(), "example.com");
+    assert_eq!(idna::from_ascii("xn--r8jz45g.jp").unwrap(), "例え.jp");
+    assert_eq!(idna::from_ascii("XN--bcher-kva.example").unwrap(), "bücher.example");
+    assert_eq!(idna::from_ascii("example.com").unwrap(), "example.com");
+    assert!(idna::from_ascii("xn--a!.com").is_err());
+    assert_eq!(idna::from_ascii("xn例.com").unwrap(), "xn例.com");
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_to_idna("User <user@例え.jp>, other@bücher.example").unwrap();
+    assert_eq!(format!("{}", email.get_to().unwrap()),
+               "To:User <user@xn--r8jz45g.jp>, other@xn--bcher-kva.example\r\n");
+}
+
+#[test]
+fn test_unstructured_text() {
+    use rfc5322::types::Unstructured;
+    use rfc5322::headers::{Subject, Comments};
+
+    let (u, _) = Unstructured::parse(b"Hello\r\n  there \t friend").unwrap();
+    assert_eq!(u.to_text(), "Hello there friend");
+    let (u, _) = Unstructured::parse(b" Hello there ").unwrap();
+    assert_eq!(u.to_text(), " Hello there ");
+
+    let (subject, _) = Subject::parse(b"Subject: A long\r\n subject\r\n").unwrap();
+    assert_eq!(subject.text(), " A long subject");
+    let (comments, _) = Comments::parse(b"Comments:Nothing much\r\n").unwrap();
+    assert_eq!(comments.text(), "Nothing much");
+}
+
+#[test]
+fn test_set_subject_trimmed() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject(" Hello ").unwrap();
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject: Hello \r\n");
+    assert_eq!(format!("{}", email.get_subject().unwrap().trimmed()), "Subject:Hello\r\n");
+    email.set_subject_trimmed(" Hello\r\n there ").unwrap();
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject:Hello there\r\n");
+}
+
+#[test]
+fn test_date_from_always_present() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_to("you@yourdomain.com").unwrap();
+    email.clear_to();
+    email.set_cc("you@yourdomain.com").unwrap();
+    email.clear_cc();
+    email.set_bcc("you@yourdomain.com").unwrap();
+    email.clear_bcc();
+    email.set_sender("myself@mydomain.com").unwrap();
+    email.clear_sender();
+    email.set_reply_to("myself@mydomain.com").unwrap();
+    email.clear_reply_to();
+    email.set_subject("Hello").unwrap();
+    email.clear_subject();
+    email.add_optional_field(("X-Foo", "bar")).unwrap();
+    email.clear_optional_field("X-Foo");
+    email.set_date("Thu, 6 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_from("other@mydomain.com").unwrap();
+    assert!(email.get_date().is_some());
+    assert!(email.get_from().is_some());
+
+    let (mut email, _) = Email::parse(b"Subject: Hi\r\n\r\nBody").unwrap();
+    assert!(email.get_date().is_none());
+    assert!(email.get_from().is_none());
+    assert!(email.from_addresses().is_empty());
+    email.set_from("myself@mydomain.com").unwrap();
+    email.set_date("Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.as_string(), "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                                   From:myself@mydomain.com\r\n\
+                                   Subject: Hi\r\n\
+                                   \r\n\
+                                   Body");
+}
+
+#[test]
+fn test_parse_verbatim() {
+    use super::Email;
+
+    let input = b"Received: from mail.example.com by mx.example.org;\r\n \
+                  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  DATE:  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  FROM: myself@mydomain.com (me)\r\n\
+                  subject:   Hello\r\n  Friend\r\n\
+                  X-Long: aaaaaaaaaa bbbbbbbbbb cccccccccc dddddddddd eeeeeeeeee ffffffffff gggggggggg\r\n\
+                  \r\n\
+                  Body";
+    let (mut email, rem) = Email::parse_verbatim(input).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(email.as_bytes(), input.to_vec());
+    let (plain, _) = Email::parse(input).unwrap();
+    assert_eq!(email, plain);
+    assert!(plain.as_bytes() != input.to_vec());
+
+    email.set_subject("Changed").unwrap();
+    email.add_optional_field(("X-Added", "yes")).unwrap();
+    assert_eq!(email.as_string(),
+               "Received: from mail.example.com by mx.example.org;\r\n \
+                Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                DATE:  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                FROM: myself@mydomain.com (me)\r\n\
+                Subject:Changed\r\n\
+                X-Long: aaaaaaaaaa bbbbbbbbbb cccccccccc dddddddddd eeeeeeeeee ffffffffff gggggggggg\r\n\
+                X-Added:yes\r\n\
+                \r\n\
+                Body");
+}
+
+#[test]
+fn test_dkim_canonicalize() {
+    use super::Email;
+    use rfc5322::dkim::{self, Canonicalization};
+
+    // RFC 6376, 3.4.5
+    let header = b"A: X\r\nB : Y\t\r\n\tZ  \r\n";
+    let body = b" C \r\nD \t E\r\n\r\n\r\n";
+    assert_eq!(dkim::canonicalize_header(header, Canonicalization::Relaxed),
+               b"a:X\r\nb:Y Z\r\n".to_vec());
+    assert_eq!(dkim::canonicalize_body(body, Canonicalization::Relaxed),
+               b" C\r\nD E\r\n".to_vec());
+    assert_eq!(dkim::canonicalize_header(header, Canonicalization::Simple), header.to_vec());
+    assert_eq!(dkim::canonicalize_body(body, Canonicalization::Simple),
+               b" C \r\nD \t E\r\n".to_vec());
+
+    let input = b"A: X\r\n\
+                  B: Y\t\r\n\
+                  \tZ  \r\n\
+                  \r\n \
+                  C \r\n\
+                  D \t E\r\n\
+                  \r\n\
+                  \r\n";
+    let (email, _) = Email::parse_verbatim(input).unwrap();
+    assert_eq!(email.dkim_canonicalize(Canonicalization::Relaxed),
+               b"a:X\r\nb:Y Z\r\n\r\n C\r\nD E\r\n".to_vec());
+    assert_eq!(email.dkim_canonicalize(Canonicalization::Simple),
+               b"A: X\r\nB: Y\t\r\n\tZ  \r\n\r\n C \r\nD \t E\r\n".to_vec());
+
+    let email = Email::new("myself@mydomain.com",
+                           "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.dkim_canonical_body(Canonicalization::Simple), b"\r\n".to_vec());
+    assert_eq!(email.dkim_canonical_body(Canonicalization::Relaxed), b"".to_vec());
+}
+
+#[test]
+fn test_parse_strict() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Subject: One\r\n\
+                  Comments: One\r\n\
+                  Comments: Two\r\n\
+                  X-Foo: One\r\n\
+                  X-Foo: Two\r\n\
+                  \r\n\
+                  Body";
+    assert!(Email::parse_strict(input).is_ok());
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Subject: One\r\n\
+                  Subject: Two\r\n\
+                  \r\n\
+                  Body";
+    assert!(Email::parse(input).is_ok());
+    let (offset, inner) = located(Email::parse_strict(input).unwrap_err());
+    assert_eq!(offset, 79);
+    match inner {
+        ParseError::DuplicateField(name) => assert_eq!(name, "Subject"),
+        _ => panic!("expected ParseError::DuplicateField"),
+    }
+}
+
+#[test]
+fn test_is_multipart() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(!email.is_multipart());
+    assert_eq!(email.boundary(), None);
+    email.set_content_type("Multipart/Mixed; boundary=\"simple boundary\"").unwrap();
+    assert!(email.is_multipart());
+    assert_eq!(email.boundary(), Some("simple boundary".to_owned()));
+    email.set_content_type("text/plain; boundary=xyz").unwrap();
+    assert!(!email.is_multipart());
+    assert_eq!(email.boundary(), None);
+
+    let (email, _) = Email::parse(b"Content-Type: multipart\r\n\r\nBody").unwrap();
+    assert!(!email.is_multipart());
+}
+
+#[test]
+fn test_multipart_parts() {
+    use super::Email;
+    use rfc5322::multipart::{self, RawPart};
+
+    // RFC 2046, 5.1.1
+    let input = b"From: Nathaniel Borenstein <nsb@bellcore.com>\r\n\
+                  Date: Sun, 21 Mar 1993 23:56:48 -0800\r\n\
+                  Subject: Sample message\r\n\
+                  MIME-Version: 1.0\r\n\
+                  Content-Type: multipart/mixed; boundary=\"simple boundary\"\r\n\
+                  \r\n\
+                  This is the preamble.\r\n\
+                  --simple boundary\r\n\
+                  \r\n\
+                  This is implicitly typed plain US-ASCII text.\r\n\
+                  It does NOT end with a linebreak.\r\n\
+                  --simple boundary \r\n\
+                  Content-type: text/plain; charset=us-ascii\r\n\
+                  \r\n\
+                  This is explicitly typed plain US-ASCII text.\r\n\
+                  It DOES end with a linebreak.\r\n\
+                  \r\n\
+                  --simple boundary--\r\n\
+                  This is the epilogue.";
+    let (email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.parts().unwrap(), vec![
+        RawPart {
+            header: b"".to_vec(),
+            blank_line: true,
+            body: b"This is implicitly typed plain US-ASCII text.\r\n\
+                    It does NOT end with a linebreak.".to_vec(),
+        },
+        RawPart {
+            header: b"Content-type: text/plain; charset=us-ascii\r\n".to_vec(),
+            blank_line: true,
+            body: b"This is explicitly typed plain US-ASCII text.\r\n\
+                    It DOES end with a linebreak.\r\n".to_vec(),
+        },
+    ]);
+
+    let multipart = email.multipart().unwrap();
+    assert_eq!(multipart.preamble, Some(b"This is the preamble.".to_vec()));
+    assert_eq!(multipart.epilogue, Some(b"This is the epilogue.".to_vec()));
+    assert_eq!(multipart.padding, vec![b"".to_vec(), b" ".to_vec(), b"".to_vec()]);
+    assert_eq!(multipart.parts.len(), 2);
+    let body = &email.get_body().unwrap().0;
+    assert_eq!(multipart.to_bytes("simple boundary"), *body);
+
+    // Rebuilding is byte for byte, including a leading CRLF, a part with a
+    // blank line and no body, a part with neither, and the CRLF after the
+    // close delimiter
+    for body in &[&b"\r\n--b\r\nX: y\r\n\r\n\r\n--b\r\nX: y\r\n--b--\r\n"[..],
+                  &b"--b\r\n\r\n--b\r\n\r\n--b--"[..],
+                  &b"--b\t\r\nbody\r\n--b-- \r\n\r\n"[..]] {
+        let multipart = multipart::split(body, "b").unwrap();
+        assert_eq!(multipart.to_bytes("b"), body.to_vec());
+    }
+    let multipart = multipart::split(b"\r\n--b\r\nX: y\r\n\r\n\r\n--b--", "b").unwrap();
+    assert_eq!(multipart.preamble, Some(Vec::new()));
+    assert_eq!(multipart.parts, vec![RawPart {
+        header: b"X: y\r\n".to_vec(),
+        blank_line: true,
+        body: Vec::new(),
+    }]);
+    assert_eq!(multipart.epilogue, None);
+
+    let mut email = email.clone();
+    email.set_body("--simple boundary\r\n\r\nNo close delimiter").unwrap();
+    assert!(email.parts().is_err());
+    email.set_content_type("text/plain").unwrap();
+    assert!(email.parts().is_err());
+}
+
+#[test]
+fn test_reply() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: Them <them@theirdomain.com>\r\n\
+                  To: myself@mydomain.com\r\n\
+                  Message-ID: <2@theirdomain.com>\r\n\
+                  In-Reply-To: <1@mydomain.com>\r\n\
+                  Subject: Hello\r\n\
+                  \r\n\
+                  Body";
+    let (original, _) = Email::parse(input).unwrap();
+    let reply = original.reply("myself@mydomain.com",
+                               "Thu, 6 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(reply.as_string(),
+               "Date:Thu, 6 Jan 2015 15:13:05 +1300\r\n\
+                From:myself@mydomain.com\r\n\
+                To: Them <them@theirdomain.com>\r\n\
+                Subject:Re: Hello\r\n\
+                In-Reply-To: <2@theirdomain.com>\r\n\
+                References: <1@mydomain.com> <2@theirdomain.com>\r\n");
+
+    let input = b"Date: Thu, 6 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Reply-To: list@mydomain.com\r\n\
+                  Subject: RE: Hello\r\n\
+                  \r\n\
+                  Body";
+    let (original, _) = Email::parse(input).unwrap();
+    let reply = original.reply("them@theirdomain.com",
+                               "Fri, 7 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(reply.as_string(),
+               "Date:Fri, 7 Jan 2015 15:13:05 +1300\r\n\
+                From:them@theirdomain.com\r\n\
+                To: list@mydomain.com\r\n\
+                Subject:RE: Hello\r\n");
+}
+
+#[test]
+fn test_forward() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: them@theirdomain.com\r\n\
+                  To: myself@mydomain.com\r\n\
+                  Bcc: secret@mydomain.com\r\n\
+                  Message-ID: <2@theirdomain.com>\r\n\
+                  Subject: Hello\r\n\
+                  \r\n\
+                  Body";
+    let (original, _) = Email::parse(input).unwrap();
+    let forward = original.forward("myself@mydomain.com",
+                                   "Thu, 6 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(forward.as_string(),
+               "Date:Thu, 6 Jan 2015 15:13:05 +1300\r\n\
+                From:myself@mydomain.com\r\n\
+                Subject:Fwd: Hello\r\n\
+                \r\n\
+                > Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                > From: them@theirdomain.com\r\n\
+                > To: myself@mydomain.com\r\n\
+                > Message-ID: <2@theirdomain.com>\r\n\
+                > Subject: Hello\r\n\
+                > \r\n\
+                > Body\r\n");
+    assert!(forward.get_message_id().is_none());
+    assert!(forward.get_to().is_none());
+
+    // No empty line is quoted after a body ending in CRLF, and a line too
+    // long to quote is continued
+    let mut original = Email::new("them@theirdomain.com",
+                                  "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    let long = "x".repeat(998);
+    original.set_body(&*format!("Hi\r\n{}\r\n", long)).unwrap();
+    let forward = original.forward("myself@mydomain.com",
+                                   "Thu, 6 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(forward.body_str().unwrap(),
+               format!("> Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                        > From:them@theirdomain.com\r\n\
+                        > \r\n\
+                        > Hi\r\n\
+                        > {}\r\n\
+                        > xx\r\n", &long[..996]));
+}
+
+#[test]
+fn test_set_to_from_email_addresses() {
+    use super::Email;
+    use rfc5322::email_address::EmailAddress;
+    use rfc5322::error::ParseError;
+
+    let addresses = vec![
+        EmailAddress {
+            display_name: Some("Smith, John".to_owned()),
+            local_part: "john".to_owned(),
+            domain: "example.com".to_owned(),
+            comments: Vec::new(),
+        },
+        EmailAddress {
+            display_name: None,
+            local_part: "jane doe".to_owned(),
+            domain: "example.com".to_owned(),
+            comments: Vec::new(),
+        },
+    ];
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_to(addresses.clone()).unwrap();
+    email.set_from(vec![addresses[0].clone()]).unwrap();
+    assert_eq!(format!("{}", email.get_to().unwrap()),
+               "To:\"Smith, John\" <john@example.com>,\"jane doe\"@example.com\r\n");
+    assert_eq!(format!("{}", email.get_from().unwrap()),
+               "From:\"Smith, John\" <john@example.com>\r\n");
+    assert_eq!(email.to_addresses()[1], addresses[1]);
+
+    let err = email.set_cc(Vec::<EmailAddress>::new()).unwrap_err();
+    assert!(assert_match!(err, ParseError::NotFound("EmailAddress")));
+}
+
+#[test]
+fn test_raw_header() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Subject:  Hello\r\n  there\r\n\
+                  X-Mailer: Mine\r\n\
+                  \r\n\
+                  Body";
+    let (email, _) = Email::parse_verbatim(input).unwrap();
+    assert_eq!(email.raw_header("subject").unwrap(),
+               b"Subject:  Hello\r\n  there\r\n".to_vec());
+    assert_eq!(email.raw_header("X-MAILER").unwrap(), b"X-Mailer: Mine\r\n".to_vec());
+    assert!(email.raw_header("To").is_none());
+
+    let (email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.raw_header("Date").unwrap(),
+               b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n".to_vec());
+}
+
+#[test]
+fn test_set_body_text() {
+    use super::Email;
+    use rfc5322::error::ParseError;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body_text("a\nb\r\nc\rd\n").unwrap();
+    assert_eq!(email.get_body().unwrap().0, b"a\r\nb\r\nc\r\nd\r\n".to_vec());
+
+    let long = format!("short\n{}", "x".repeat(999));
+    let err = email.set_body_text(&*long).unwrap_err();
+    assert!(assert_match!(err, ParseError::LineTooLong(2)));
+}
+
+#[test]
+fn test_set_body_wrapped() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body_wrapped("the quick brown fox\nabcdefghijklmnopqrstuvwxyz end", 10).unwrap();
+    assert_eq!(email.get_body().unwrap().0,
+               b"the quick\r\nbrown fox\r\nabcdefghij\r\nklmnopqrst\r\nuvwxyz end".to_vec());
+
+    let long = "word ".repeat(300);
+    email.set_body_wrapped(&*long, 2000).unwrap();
+    let body = email.get_body().unwrap().0;
+    assert!(body.split(|&c| c == b'\n').all(|line| line.len() <= 1000));
+}
+
+#[test]
+fn test_msg_ids() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  In-Reply-To: <2@theirdomain.com>\r\n\
+                  References: <1@mydomain.com> (first)\r\n <2@[192.0.2.1]>\r\n\
+                  \r\n";
+    let (email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.get_in_reply_to().unwrap().ids(),
+               vec!["2@theirdomain.com".to_owned()]);
+    assert_eq!(email.get_references().unwrap().ids(),
+               vec!["1@mydomain.com".to_owned(), "2@[192.0.2.1]".to_owned()]);
+}
+
+#[test]
+fn test_to_mailboxes() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.to_mailboxes().is_empty());
+    email.set_to("A <a@example.com>, Friends: b@example.com, C <c@example.com>;, \
+                  Nobody:;").unwrap();
+    let mailboxes: Vec<String> = email.to_mailboxes().iter()
+        .map(|m| format!("{}", m)).collect();
+    assert_eq!(mailboxes, vec!["A <a@example.com>", " b@example.com", " C <c@example.com>"]);
+}
+
+#[test]
+fn test_counting_sink() {
+    use super::Email;
+    use rfc5322::{CountingSink, Streamable};
+
+    let email = Email::new("myself@mydomain.com",
+                           "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    let mut sink = CountingSink::new();
+    let count = email.get_from().unwrap().stream(&mut sink).unwrap();
+    assert_eq!(count, 26);
+    assert_eq!(sink.count(), 26);
+    email.get_date().unwrap().stream(&mut sink).unwrap();
+    assert_eq!(sink.count(), 26 + 37);
+    assert_eq!(email.byte_len(), sink.count());
+}
+
+#[test]
+fn test_body_parse_strict_78() {
+    use rfc5322::{Body, Parsable};
+    use rfc5322::error::ParseError;
+
+    let ok = format!("short\r\n{}\r\nend", "x".repeat(78));
+    let (body, rem) = Body::parse_strict_78(ok.as_bytes()).unwrap();
+    assert_eq!(body.0, ok.as_bytes().to_vec());
+    assert_eq!(rem.len(), 0);
+
+    let long = format!("short\r\n{}\r\nend", "x".repeat(79));
+    assert!(Body::parse(long.as_bytes()).is_ok());
+    let err = Body::parse_strict_78(long.as_bytes()).unwrap_err();
+    assert!(assert_match!(err, ParseError::LineExceedsRecommended(2)));
+}
+
+#[test]
+fn test_mime_version() {
+    use super::Email;
+    use rfc5322::Parsable;
+    use rfc5322::headers::MimeVersion;
+    use rfc5322::error::ParseError;
+
+    let input = b"MIME-Version: 1.0 (produced by MetaSend Vx.x)\r\n";
+    let (mv, rem) = MimeVersion::parse(input).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!((mv.major, mv.minor), (1, 0));
+    assert_eq!(format!("{}", mv), "MIME-Version: 1.0 (produced by MetaSend Vx.x)\r\n");
+
+    let err = MimeVersion::parse(b"MIME-Version: banana\r\n").unwrap_err();
+    assert!(assert_match!(err, ParseError::Parse("MIME-Version", _)));
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.set_mime_version("banana").is_err());
+    email.add_optional_field(("MIME-Version", "1.0")).unwrap();
+    email.set_mime_version(MimeVersion::default()).unwrap();
+    assert_eq!(email.get_optional_fields().len(), 0);
+    assert_eq!(format!("{}", email.get_mime_version().unwrap()), "MIME-Version:1.0\r\n");
+    email.set_mime_version((1, 1)).unwrap();
+    assert_eq!(email.mime_version_ref().unwrap().minor, 1);
+    email.clear_mime_version();
+    assert!(email.get_mime_version().is_none());
+
+    let (email, _) = Email::parse(b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                                    From: myself@mydomain.com\r\n\
+                                    Mime-Version: 1.0\r\n\
+                                    \r\n").unwrap();
+    assert!(email.get_mime_version().is_some());
+}
+
+#[test]
+fn test_content_disposition() {
+    use super::Email;
+    use rfc5322::Parsable;
+    use rfc5322::headers::ContentDisposition;
+
+    let input = b"Content-Disposition: attachment; filename=\"genome report.jpeg\";\r\n \
+                  creation-date=\"Wed, 12 Feb 1997 16:29:51 -0500\"\r\n";
+    let (cd, rem) = ContentDisposition::parse(input).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert!(cd.0.is_attachment());
+    assert_eq!(cd.0.filename().unwrap(), "genome report.jpeg");
+    assert_eq!(format!("{}", cd.0.creation_date().unwrap()),
+               "Wed, 12 Feb 1997 16:29:51 -0500");
+
+    // RFC 2231 continuations and charset
+    let input = b"Content-Disposition: inline; filename*0*=utf-8''%E4%BE%8B;\r\n \
+                  filename*1=.txt\r\n";
+    let (cd, _) = ContentDisposition::parse(input).unwrap();
+    assert!(!cd.0.is_attachment());
+    assert_eq!(cd.0.filename().unwrap(), "例.txt");
+
+    // An extended value without its charset falls back to the plain one
+    let input = b"Content-Disposition: attachment; filename=\"plain.txt\"; filename*=bogus\r\n";
+    let (cd, _) = ContentDisposition::parse(input).unwrap();
+    assert_eq!(cd.0.filename().unwrap(), "plain.txt");
+    let input = b"Content-Disposition: attachment; filename*=bogus\r\n";
+    let (cd, _) = ContentDisposition::parse(input).unwrap();
+    assert_eq!(cd.0.filename().unwrap(), "bogus");
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_content_disposition(ContentDisposition::attachment("a \"b\".txt").unwrap())
+        .unwrap();
+    assert_eq!(format!("{}", email.get_content_disposition().unwrap()),
+               "Content-Disposition:attachment; filename=\"a \\\"b\\\".txt\"\r\n");
+    assert_eq!(email.content_disposition_ref().unwrap().0.filename().unwrap(), "a \"b\".txt");
+
+    let long = "ü".repeat(20);
+    let cd = ContentDisposition::attachment(&*long).unwrap();
+    assert_eq!(cd.0.parameters.len(), 3);
+    assert_eq!(cd.0.filename().unwrap(), long);
+
+    email.clear_content_disposition();
+    assert!(email.get_content_disposition().is_none());
+    assert!(email.set_content_disposition("; filename=x").is_err());
+}
+
+#[test]
+fn test_parse_complete() {
+    use super::Email;
+    use rfc5322::error::ParseError;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  \r\n\
+                  Body";
+    let email = Email::parse_complete(input).unwrap();
+    assert_eq!(email.get_body().unwrap().0, b"Body".to_vec());
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: myself@mydomain.com\r\n\
+                  Bad header\r\n";
+    let (offset, inner) = located(Email::parse_complete(input).unwrap_err());
+    assert_eq!(offset, 65);
+    assert!(assert_match!(inner, ParseError::NotFound("Field")));
+}
+
+#[test]
+fn test_new_without_date() {
+    use super::Email;
+    use rfc5322::error::ValidationError;
+
+    let mut email = Email::new_without_date("myself@mydomain.com").unwrap();
+    assert!(email.get_date().is_none());
+    assert_eq!(email.validate(), Err(vec![ValidationError::MissingDate]));
+    assert_eq!(email.as_string(), "From:myself@mydomain.com\r\n");
+
+    email.set_date("Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.validate(), Ok(()));
+    assert_eq!(email.as_string(),
+               "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                From:myself@mydomain.com\r\n");
+}
+
+#[test]
+fn test_set_authors() {
+    use super::Email;
+    use rfc5322::email_address::EmailAddress;
+    use rfc5322::error::ParseError;
+
+    let addr = |local: &str| EmailAddress {
+        display_name: None,
+        local_part: local.to_owned(),
+        domain: "example.com".to_owned(),
+        comments: Vec::new(),
+    };
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_authors(vec![addr("a"), addr("b")], addr("a")).unwrap();
+    assert_eq!(email.as_string(),
+               "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                From:a@example.com,b@example.com\r\n\
+                Sender:a@example.com\r\n");
+    assert_eq!(email.validate(), Ok(()));
+
+    let err = email.set_authors(Vec::new(), addr("c")).unwrap_err();
+    assert!(assert_match!(err, ParseError::NotFound("EmailAddress")));
+    assert_eq!(format!("{}", email.get_sender().unwrap()), "Sender:a@example.com\r\n");
+}
+
+#[test]
+fn test_body_str() {
+    use super::Email;
+    use rfc5322::Body;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.body_str(), None);
+    email.set_body("Hello\r\nthere").unwrap();
+    assert_eq!(email.body_str(), Some("Hello\r\nthere"));
+    email.set_body(Body(vec![0xff, 0xfe])).unwrap();
+    assert_eq!(email.body_str(), None);
+}
+
+#[test]
+fn test_email_from_str() {
+    use super::Email;
+    use rfc5322::error::ParseError;
+
+    let email: Email = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                        From: myself@mydomain.com\r\n\
+                        \r\n\
+                        Body".parse().unwrap();
+    assert_eq!(email.body_str(), Some("Body"));
+
+    let err = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+               From: myself@mydomain.com\r\n\
+               Bad header\r\n".parse::<Email>().unwrap_err();
+    assert!(assert_match!(err, ParseError::At { offset: 65, .. }));
+}
+
+#[test]
+fn test_email_try_from() {
+    use super::{Email, TryFrom};
+
+    let input = "Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                 From: myself@mydomain.com\r\n\
+                 \r\n\
+                 Body";
+    let email = Email::try_from(input.as_bytes()).unwrap();
+    assert_eq!(Email::try_from(input.to_owned()).unwrap(), email);
+    assert_eq!(Email::try_from(input).unwrap(), email);
+    assert!(Email::try_from("Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                             From: myself@mydomain.com\r\n\
+                             Bad header\r\n").is_err());
+}
+
+#[test]
+fn test_parse_error_source() {
+    use std::error::Error;
+    use std::io;
+    use rfc5322::error::ParseError;
+
+    let err = ParseError::Parse("Email", Box::new(ParseError::NotFound("Field")));
+    assert_eq!(format!("{}", err.source().unwrap()), "\"Field\" Not Found");
+    assert!(err.source().unwrap().source().is_none());
+
+    let err = ParseError::Io(io::Error::new(io::ErrorKind::Other, "oops"));
+    assert_eq!(format!("{}", err.source().unwrap()), "oops");
+    assert!(ParseError::InternalError.source().is_none());
+}
+
+#[test]
+fn test_parse_error_from_io_error() {
+    use std::io;
+    use rfc5322::error::ParseError;
+
+    fn read() -> Result<(), ParseError> {
+        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short read"))?;
+        Ok(())
+    }
+    let err = read().unwrap_err();
+    assert!(assert_match!(err, ParseError::Io(_)));
+    assert_eq!(format!("{}", err), "I/O Error: short read");
+}
+
+#[test]
+fn test_semantically_eq() {
+    use super::Email;
+
+    let a = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+              From: Me (at home) <myself@mydomain.com>\r\n\
+              To: you@yourdomain.com,\r\n them@theirdomain.com\r\n\
+              Subject: =?UTF-8?Q?Coffee?= time\r\n\
+              X-Note: keep (this)\r\n\
+              \r\n\
+              Hello  \r\n\
+              \r\n";
+    let b = b"Date:   Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+              From: Me <myself@mydomain.com>\r\n\
+              To: you@yourdomain.com, them@theirdomain.com\r\n\
+              Subject: Coffee  time\r\n\
+              x-note: keep (this)\r\n\
+              \r\n\
+              Hello\r\n";
+    let a = Email::parse_complete(a).unwrap();
+    let b = Email::parse_complete(b).unwrap();
+    assert!(a != b);
+    assert!(a.semantically_eq(&b));
+
+    let mut c = b.clone();
+    c.set_to("You@yourdomain.com, them@theirdomain.com").unwrap();
+    assert!(!a.semantically_eq(&c));
+
+    let mut c = b.clone();
+    c.set_optional_field(("X-Note", "keep")).unwrap();
+    assert!(!a.semantically_eq(&c));
+}
+
+#[test]
+fn test_display_name_with_dots() {
+    use super::Email;
+
+    let mut email = Email::new("J. Random Hacker <j@example.com>",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(format!("{}", email.get_from().unwrap()),
+               "From:J. Random Hacker <j@example.com>\r\n");
+    assert_eq!(email.from_addresses()[0].display_name,
+               Some("J. Random Hacker".to_owned()));
+    email.set_to("john.doe@example.com").unwrap();
+    assert_eq!(email.to_addresses()[0].local_part, "john.doe");
+}
+
+#[test]
+fn test_address_comments() {
+    use rfc5322::Parsable;
+    use rfc5322::types::AddrSpec;
+    use rfc5322::email_address::EmailAddress;
+
+    let (addr_spec, _) = AddrSpec::parse(b"(Primary) john@example.com (Work \\(a\\) (b))").unwrap();
+    assert_eq!(addr_spec.comments(), vec!["Primary".to_owned(), "Work (a) (b)".to_owned()]);
+    assert!(EmailAddress::from_addr_spec(&addr_spec).comments.is_empty());
+    let addr = EmailAddress::from_addr_spec_with_comments(&addr_spec);
+    assert_eq!(addr.local_part, "john");
+    assert_eq!(addr.comments, vec!["Primary".to_owned(), "Work (a) (b)".to_owned()]);
+}
+
+#[test]
+fn test_stream_smtp() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body(".Hi\r\n.\r\nend.\r\n..").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    let count = email.stream_smtp(&mut output).unwrap();
+    assert_eq!(output, b"Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                         From:myself@mydomain.com\r\n\
+                         \r\n\
+                         ..Hi\r\n\
+                         ..\r\n\
+                         end.\r\n\
+                         ...\r\n\
+                         .\r\n".to_vec());
+    assert_eq!(count, output.len());
+
+    email.set_body("Body\r\n").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    email.stream_smtp(&mut output).unwrap();
+    assert!(output.ends_with(b"\r\nBody\r\n.\r\n"));
+
+    // A writer which accepts only a few bytes per call loses nothing
+    struct Short(Vec<u8>);
+    impl ::std::io::Write for Short {
+        fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+            let n = ::std::cmp::min(3, buf.len());
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+        fn flush(&mut self) -> ::std::io::Result<()> { Ok(()) }
+    }
+    email.set_body(".Hi\r\n.\r\nend.\r\n..").unwrap();
+    let mut short = Short(Vec::new());
+    let count = email.stream_smtp(&mut short).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    email.stream_smtp(&mut output).unwrap();
+    assert_eq!(short.0, output);
+    assert_eq!(count, output.len());
+}
+
+#[test]
+fn test_parse_limits() {
+    use super::Email;
+    use rfc5322::limits::ParseLimits;
+
+    let mut input: Vec<u8> = b"Date:Wed, 5 Jan 2015 15:13:05 +1300\r\nFrom:a@x.com ".to_vec();
+    input.extend(vec![b'('; 100_000]);
+    input.extend_from_slice(b"\r\n\r\n");
+    let (offset, inner) = located(Email::parse(&input).unwrap_err());
+    assert_eq!(offset, 37);
+    assert!(assert_match!(inner, ParseError::LimitExceeded("Comment Depth")));
+
+    let input = b"Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:a@x.com (one (two (three)))\r\n\
+                  To:b@x.com,c@x.com,d@x.com\r\n\
+                  Subject:Hi\r\n\
+                  \r\n";
+    assert!(Email::parse(input).is_ok());
+
+    let limits = ParseLimits { max_comment_depth: 2, ..ParseLimits::default() };
+    let (offset, inner) = located(Email::parse_with_limits(input, &limits).unwrap_err());
+    assert_eq!(offset, 37);
+    assert!(assert_match!(inner, ParseError::LimitExceeded("Comment Depth")));
+    let limits = ParseLimits { max_addresses: 2, ..ParseLimits::default() };
+    let (offset, inner) = located(Email::parse_with_limits(input, &limits).unwrap_err());
+    assert_eq!(offset, 71);
+    assert!(assert_match!(inner, ParseError::LimitExceeded("Addresses")));
+    let limits = ParseLimits { max_fields: 3, ..ParseLimits::default() };
+    let (offset, inner) = located(Email::parse_with_limits(input, &limits).unwrap_err());
+    assert_eq!(offset, 99);
+    assert!(assert_match!(inner, ParseError::LimitExceeded("Header Fields")));
+    let limits = ParseLimits { max_comment_depth: 3, max_addresses: 3, max_fields: 4 };
+    assert!(Email::parse_with_limits(input, &limits).is_ok());
+
+    // The default limits are back in force afterwards
+    assert!(Email::parse(input).is_ok());
+}
+
+#[test]
+fn test_parse_truncated() {
+    use super::Email;
+
+    for input in &[&b""[..], b" ", b"\t \t", b"\r", b"\n", b"\r\n", b"\r\n\r\n"] {
+        let _ = Email::parse(input);
+    }
+
+    let (email, rem) = Email::parse(b"Subject: Hi\r\n").unwrap();
+    assert!(email.get_body().is_none());
+    assert_eq!(rem, b"");
+    let (_, rem) = Email::parse(b"Subject: Hi\r\n\r").unwrap();
+    assert_eq!(rem, b"\r");
+
+    // Every prefix of a message, cut anywhere, returns rather than panicking
+    let input = b"Return-Path:<a@x.com>\r\n\
+                  Received:from x.com by y.com; Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:\"A\" <a@x.com> (comment)\r\n\
+                  To:b@x.com, Group: c@x.com;\r\n\
+                  Subject: =?utf-8?q?Hi?=\r\n\
+                   folded\r\n\
+                  \r\n\
+                  Body\r\n";
+    for len in 0..input.len() + 1 {
+        let _ = Email::parse(&input[..len]);
+        let _ = Email::parse_verbatim(&input[..len]);
+    }
+}
+
+#[test]
+fn test_fuzz_parse() {
+    use super::fuzz_parse;
+
+    for input in &[&b""[..], b"\r\n", b":", b"From:", b"From:<", b"From:a@", b"Date:Wed, 5",
+                   b"To:g:;", b"Subject:=?", b"Subject:=?utf-8?b?=?=\r\n", b"\xff\r\n\r\n\xff",
+                   b"Return-Path:<>\r\nReceived:;", b"Content-Type:multipart/x;boundary=\r\n\r\n--"] {
+        fuzz_parse(input);
+    }
+}
+
+#[test]
+fn test_parse_body_bare_lf() {
+    use super::Email;
+
+    let input = b"Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:myself@mydomain.com\r\n\
+                  \r\n\
+                  Line one\n\
+                  Line two\r\n\
+                  \n\
+                  Line four";
+    let email = Email::parse_complete(input).unwrap();
+    assert_eq!(email.body_str(), Some("Line one\r\nLine two\r\n\r\nLine four"));
+    assert!(email.as_bytes().ends_with(b"\r\n\r\nLine one\r\nLine two\r\n\r\nLine four"));
+
+    // A CR which does not end a line is still invalid
+    let (offset, inner) = located(Email::parse_complete(b"From:a@x.com\r\n\r\nA\rB").unwrap_err());
+    assert_eq!(offset, 16);
+    assert!(assert_match!(inner, ParseError::InvalidBodyChar(b'\r')));
+}
+
+#[test]
+fn test_parse_header_bare_lf() {
+    use super::Email;
+
+    // The line ending of the first line is used for the whole header
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300\n\
+                  From: a@b.com\n\
+                  Subject: Hello\n \
+                  there\n\
+                  \n\
+                  body\n";
+    let (email, rem) = Email::parse(input).unwrap();
+    assert_eq!(rem, b"");
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From: a@b.com\r\n");
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject: Hello there\r\n");
+    assert_eq!(email.body_str(), Some("body\r\n"));
+    assert!(email.as_bytes().ends_with(b"\r\nFrom: a@b.com\r\nSubject: Hello there\r\n\r\nbody\r\n"));
+    assert_eq!(Email::parse_from_reader(&input[..]).unwrap(), email);
+    assert_eq!(Email::parse_complete(b"From: a@b.com\n").unwrap().get_from(),
+               email.get_from());
+
+    // Failures are located within the input as given
+    let err = Email::parse_complete(b"From: a@b.com\nBad line\n\nbody").unwrap_err();
+    assert_eq!(located(err).0, 14);
+
+    // A bare LF is still rejected when the first line ends in CRLF
+    assert!(Email::parse_complete(b"From: a@b.com\r\nSubject: x\n\r\nbody").is_err());
+    assert!(Email::parse_from_reader(&b"From: a@b.com\r\nSubject: x\n\r\nbody"[..]).is_err());
+}
+
+#[test]
+fn test_set_parts() {
+    use super::Email;
+    use rfc5322::headers::ContentType;
+    use rfc5322::multipart::RawPart;
+    use TryFrom;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_body("Hello\r\n").unwrap();
+    let body = email.take_body().unwrap();
+    assert!(email.get_body().is_none());
+
+    let parts = vec![
+        RawPart::new(b"Content-Type: text/plain\r\n".to_vec(), body.0),
+        RawPart::new(b"Content-Type: text/plain\r\n".to_vec(),
+                     b"--boundary-0123456789abcdef\r\n".to_vec()),
+    ];
+    let content_type = ContentType::try_from("multipart/mixed; boundary=old").unwrap();
+    email.set_parts(parts.clone(), content_type).unwrap();
+    let boundary = email.boundary().unwrap();
+    assert!(boundary.starts_with("boundary-"));
+    assert_eq!(email.get_content_type().unwrap().0.parameters.len(), 1);
+    assert_eq!(email.parts().unwrap(), parts);
+
+    let (parsed, _) = Email::parse(&email.as_bytes()).unwrap();
+    assert_eq!(parsed.parts().unwrap(), parts);
+
+    let content_type = ContentType::try_from("text/plain").unwrap();
+    assert!(email.set_parts(parts, content_type).is_err());
+}
+
+#[cfg(feature="encoding")]
+#[test]
+fn test_body_decoded_to_utf8() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_content_type("text/plain; charset=ISO-8859-1").unwrap();
+    email.set_content_transfer_encoding("quoted-printable").unwrap();
+    email.set_body("caf=E9 =80").unwrap();
+    assert!(email.body_str().is_some());
+    assert_eq!(email.body_decoded_to_utf8().unwrap(), "café €");
+
+    email.set_content_type("text/plain; charset=\"utf-8\"").unwrap();
+    email.set_body("caf=C3=A9").unwrap();
+    assert_eq!(email.body_decoded_to_utf8().unwrap(), "café");
+    email.set_body("caf=E9").unwrap();
+    assert!(email.body_decoded_to_utf8().is_err());
+
+    email.set_content_type("text/plain; charset=x-unknown").unwrap();
+    assert!(email.body_decoded_to_utf8().is_err());
+
+    email.clear_content_type();
+    email.clear_content_transfer_encoding();
+    email.set_body("plain").unwrap();
+    assert_eq!(email.body_decoded_to_utf8().unwrap(), "plain");
+}
+
+#[test]
+fn test_parse_preserving() {
+    use super::Email;
+
+    let input = b"DATE:  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  FROM: myself@mydomain.com\r\n\
+                  Subject: Hi\r\n\
+                  \r\n\
+                  Line one\n\
+                  Line two\r\n";
+    let mut email = Email::parse_preserving(input).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    let count = email.stream(&mut output).unwrap();
+    assert_eq!(output, input.to_vec());
+    assert_eq!(count, input.len());
+
+    email.set_subject("Changed").unwrap();
+    email.set_body("New body\r\n").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    email.stream(&mut output).unwrap();
+    assert_eq!(output, b"DATE:  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                         FROM: myself@mydomain.com\r\n\
+                         Subject:Changed\r\n\
+                         \r\n\
+                         New body\r\n".to_vec());
+
+    assert!(assert_match!(Email::parse_preserving(b"From:a@x.com\r\nbad"),
+                          Err(ParseError::At { offset: 14, .. })));
+}
+
+#[test]
+fn test_effective_sender() {
+    use super::Email;
+
+    let mut email = Email::new("A <a@x.com>, b@x.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.effective_sender().unwrap().local_part, "a");
+    email.set_sender("s@y.com").unwrap();
+    let sender = email.effective_sender().unwrap();
+    assert_eq!((&*sender.local_part, &*sender.domain), ("s", "y.com"));
+
+    let (email, _) = Email::parse(b"Subject: Hi\r\n\r\n").unwrap();
+    assert!(email.effective_sender().is_none());
+}
+
+#[test]
+fn test_field_canonical_name() {
+    use super::Email;
+
+    let input = b"message-id: <a@b>\r\nmime-version: 1.0\r\nreply-to: a@b.c\r\nx-Custom: yes\r\n\r\n";
+    let (email, _) = Email::parse(input).unwrap();
+    let names: Vec<String> = email.fields().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["Message-ID", "MIME-Version", "Reply-To", "x-Custom"]);
+}
+
+#[test]
+fn test_set_fields_from_email_address() {
+    use super::Email;
+    use rfc5322::email_address::EmailAddress;
+
+    let address = EmailAddress {
+        display_name: Some("Smith, J. (Jr)".to_owned()),
+        local_part: "john".to_owned(),
+        domain: "example.com".to_owned(),
+        comments: Vec::new(),
+    };
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_from(address.clone()).unwrap();
+    email.set_sender(address.clone()).unwrap();
+    email.set_reply_to(address.clone()).unwrap();
+    email.set_to(address.clone()).unwrap();
+    email.set_cc(vec![address.clone(), address.clone()]).unwrap();
+    email.set_reply_to(vec![address.clone()]).unwrap();
+    assert_eq!(format!("{}", email.get_sender().unwrap()),
+               "Sender:\"Smith, J. (Jr)\" <john@example.com>\r\n");
+    assert_eq!(format!("{}", email.get_reply_to().unwrap()),
+               "Reply-To:\"Smith, J. (Jr)\" <john@example.com>\r\n");
+    assert_eq!(format!("{}", email.get_from().unwrap()),
+               "From:\"Smith, J. (Jr)\" <john@example.com>\r\n");
+    assert_eq!(email.to_addresses()[0].local_part, "john");
+    assert_eq!(email.cc_addresses().len(), 2);
+}
+
+#[test]
+fn test_recipient_count() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.recipient_count(), 0);
+    email.set_to("a@x.com, Team: b@x.com, c@x.com;").unwrap();
+    email.set_cc("a@x.com").unwrap();
+    email.add_bcc("d@x.com").unwrap();
+    assert_eq!(email.recipient_count(), 5);
+    assert_eq!(email.all_recipients().len(), 4);
+
+    email.set_max_recipients(Some(6));
+    email.add_to("e@x.com").unwrap();
+    assert!(assert_match!(email.add_cc("f@x.com"), Err(ParseError::LimitExceeded("Recipients"))));
+    assert!(assert_match!(email.add_bcc("f@x.com"), Err(ParseError::LimitExceeded("Recipients"))));
+    assert_eq!(email.recipient_count(), 6);
+
+    // Setting a field counts the addresses it replaces, not the sum
+    assert!(assert_match!(email.set_to("a@x.com, b@x.com, c@x.com, e@x.com, f@x.com"),
+                          Err(ParseError::LimitExceeded("Recipients"))));
+    email.set_to("g@x.com, h@x.com, i@x.com").unwrap();
+    assert!(assert_match!(email.set_cc("a@x.com, b@x.com, c@x.com"),
+                          Err(ParseError::LimitExceeded("Recipients"))));
+    email.set_bcc("j@x.com").unwrap();
+    assert!(assert_match!(email.set_to_encoded("a@x.com, b@x.com, c@x.com, e@x.com, f@x.com"),
+                          Err(ParseError::LimitExceeded("Recipients"))));
+    assert_eq!(email.recipient_count(), 5);
+
+    let mut template = email.clone();
+    template.set_max_recipients(None);
+    template.add_cc("k@x.com").unwrap();
+    template.add_cc("l@x.com").unwrap();
+    assert!(assert_match!(email.merge_headers_from(&template, true),
+                          Err(ParseError::LimitExceeded("Recipients"))));
+    assert_eq!(email.recipient_count(), 5);
+    template.set_cc("k@x.com").unwrap();
+    email.merge_headers_from(&template, true).unwrap();
+    assert_eq!(format!("{}", email.get_cc().unwrap()), "Cc:k@x.com\r\n");
+
+    email.set_max_recipients(None);
+    email.add_cc("f@x.com").unwrap();
+    assert_eq!(email.recipient_count(), 6);
+}
+
+#[test]
+fn test_normalize_sender() {
+    use super::Email;
+
+    let mut email = Email::new("Me <me@Example.COM>",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_sender("me@example.com").unwrap();
+    assert!(email.normalize_sender());
+    assert!(email.get_sender().is_none());
+    assert!(!email.normalize_sender());
+
+    email.set_sender("Me@example.com").unwrap();
+    assert!(!email.normalize_sender());
+    assert!(email.get_sender().is_some());
+
+    email.set_from("me@example.com, you@example.com").unwrap();
+    email.set_sender("me@example.com").unwrap();
+    assert!(!email.normalize_sender());
+    assert!(email.get_sender().is_some());
+}
+
+#[test]
+fn test_date_to_parts() {
+    use rfc5322::types::{DateTime, DateParts};
+
+    let (dt, _) = DateTime::parse(b"Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(dt.to_parts(), DateParts {
+        year: 2015, month: 1, day: 5, hour: 15, minute: 13, second: Some(5),
+        zone_offset_minutes: 780, weekday: Some(4),
+    });
+    let (dt, _) = DateTime::parse(b"15 Aug 1999 09:30 -0330").unwrap();
+    let parts = dt.to_parts();
+    assert_eq!((parts.second, parts.weekday, parts.zone_offset_minutes), (None, None, -210));
+}
+
+#[test]
+fn test_date_from_parts() {
+    use rfc5322::types::DateTime;
+
+    let dt = DateTime::from_parts(2015, 1, 5, 15, 13, Some(5), 780).unwrap();
+    assert_eq!(format!("{}", dt), "Mon, 5 Jan 2015 15:13:05 +1300");
+    let (parsed, _) = DateTime::parse(format!("{}", dt).as_bytes()).unwrap();
+    assert_eq!(parsed.to_parts(), dt.to_parts());
+
+    let dt = DateTime::from_parts(2000, 2, 29, 0, 0, None, -210).unwrap();
+    assert_eq!(format!("{}", dt), "Tue, 29 Feb 2000 00:00 -0330");
+    assert_eq!(DateTime::from_parts(2016, 12, 31, 23, 59, Some(60), 0).unwrap()
+               .to_parts().weekday, Some(7));
+
+    assert!(DateTime::from_parts(2015, 2, 29, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(1900, 2, 29, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 4, 31, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 13, 1, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 1, 0, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(1899, 1, 1, 0, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 1, 1, 24, 0, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 1, 1, 0, 60, None, 0).is_err());
+    assert!(DateTime::from_parts(2015, 1, 1, 0, 0, Some(61), 0).is_err());
+    assert!(DateTime::from_parts(2015, 1, 1, 0, 0, None, 6000).is_err());
+}
+
+#[test]
+fn test_weekday_is_consistent() {
+    use rfc5322::types::DateTime;
+
+    let (dt, _) = DateTime::parse(b"Mon, 1 Jan 2001 00:00:00 +0000").unwrap();
+    assert_eq!(dt.weekday_is_consistent(), Some(true));
+    let (dt, _) = DateTime::parse(b"Tue, 1 Jan 2001 00:00:00 +0000").unwrap();
+    assert_eq!(dt.weekday_is_consistent(), Some(false));
+    let (dt, _) = DateTime::parse(b"Sat, 29 Feb 2020 12:00 -0500").unwrap();
+    assert_eq!(dt.weekday_is_consistent(), Some(true));
+    let (dt, _) = DateTime::parse(b"Sun, 30 Feb 2020 12:00 -0500").unwrap();
+    assert_eq!(dt.weekday_is_consistent(), Some(false));
+    let (dt, _) = DateTime::parse(b"1 Jan 2001 00:00:00 +0000").unwrap();
+    assert_eq!(dt.weekday_is_consistent(), None);
+}
+
+#[test]
+fn test_keywords_list() {
+    use super::Email;
+    use rfc5322::headers::Keywords;
+
+    let input = b"Keywords: urgent, \"Project X\" (internal),, (just a comment) ,J. Doe,\r\n\r\n";
+    let (email, _) = Email::parse(input).unwrap();
+    let keywords = email.get_keywords();
+    assert_eq!(keywords.len(), 1);
+    assert_eq!(keywords[0].words(), vec!["urgent", "Project X", "J. Doe"]);
+    assert!(email.get_optional_fields().is_empty());
+
+    let (keywords, _) = Keywords::parse(b"Keywords:a,b\r\n").unwrap();
+    assert_eq!(keywords.words(), vec!["a", "b"]);
+    assert_eq!(format!("{}", keywords), "Keywords:a,b\r\n");
+    assert!(Keywords::parse(b"Keywords: , (none)\r\n").is_err());
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_keywords("one, two three").unwrap();
+    assert_eq!(email.get_keywords()[0].words(), vec!["one", "two three"]);
+}
+
+#[test]
+fn test_comment_by_index() {
+    use super::Email;
+
+    let mut email = Email::new("myself@mydomain.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_comments("Generated").unwrap();
+    email.set_subject("Hi").unwrap();
+    email.add_comments("From the user").unwrap();
+    assert_eq!(format!("{}", email.comment(1).unwrap()), "Comments:From the user\r\n");
+    assert!(email.comment(2).is_none());
+
+    assert!(email.remove_comment(0));
+    assert!(!email.remove_comment(1));
+    assert_eq!(email.get_comments().len(), 1);
+    assert_eq!(format!("{}", email.comment(0).unwrap()), "Comments:From the user\r\n");
+    assert!(email.get_subject().is_some());
+}
+
+#[test]
+fn test_merge_headers_from() {
+    use super::Email;
+
+    let mut template = Email::new("list@example.com",
+                                  "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    template.set_subject("Template").unwrap();
+    template.add_optional_field(("List-Id", "<list.example.com>")).unwrap();
+    template.add_optional_field(("Precedence", "list")).unwrap();
+    template.add_comments("From the template").unwrap();
+    template.set_content_type("text/html").unwrap();
+    template.set_content_language("fr").unwrap();
+
+    let mut email = Email::new("me@example.com",
+                               "Thu, 6 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject("Hello").unwrap();
+    email.add_optional_field(("precedence", "bulk")).unwrap();
+    email.add_comments("Mine").unwrap();
+    email.set_content_type("text/plain").unwrap();
+
+    let mut skipped = email.clone();
+    skipped.merge_headers_from(&template, false).unwrap();
+    assert_eq!(format!("{}", skipped.get_subject().unwrap()), "Subject:Hello\r\n");
+    assert_eq!(format!("{}", skipped.get_from().unwrap()), "From:me@example.com\r\n");
+    let optional: Vec<String> = skipped.get_optional_fields().iter()
+        .map(|f| format!("{}", f)).collect();
+    assert_eq!(optional, vec!["precedence:bulk\r\n", "List-Id:<list.example.com>\r\n"]);
+    assert_eq!(skipped.get_comments().len(), 2);
+    assert_eq!(format!("{}", skipped.get_content_type().unwrap()),
+               "Content-Type:text/plain\r\n");
+
+    email.merge_headers_from(&template, true).unwrap();
+    assert_eq!(format!("{}", email.get_subject().unwrap()), "Subject:Template\r\n");
+    assert_eq!(format!("{}", email.get_from().unwrap()), "From:list@example.com\r\n");
+    let optional: Vec<String> = email.get_optional_fields().iter()
+        .map(|f| format!("{}", f)).collect();
+    assert_eq!(optional, vec!["List-Id:<list.example.com>\r\n", "Precedence:list\r\n"]);
+    assert_eq!(email.get_comments().len(), 2);
+    assert_eq!(format!("{}", email.get_content_type().unwrap()),
+               "Content-Type:text/plain\r\n");
+    assert!(email.get_content_language().is_empty());
+}
+
+#[test]
+fn test_message_id_parts() {
+    use super::Email;
+    use rfc5322::types::MsgId;
+
+    let id = MsgId::from_parts("1234.abcd", "example.com").unwrap();
+    assert_eq!(format!("{}", id), "<1234.abcd@example.com>");
+    let id = MsgId::from_parts("x", "[192.0.2.1]").unwrap();
+    assert_eq!(id.id(), "x@[192.0.2.1]");
+
+    assert!(MsgId::from_parts("a b", "example.com").is_err());
+    assert!(MsgId::from_parts("a", "example.com>").is_err());
+    assert!(MsgId::from_parts("", "example.com").is_err());
+    assert!(MsgId::from_parts("<a", "example.com").is_err());
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_message_id_parts("first", "example.com").unwrap();
+    email.set_message_id_parts("second", "example.com").unwrap();
+    assert_eq!(format!("{}", email.get_message_id().unwrap()),
+               "Message-ID:<second@example.com>\r\n");
+    assert!(email.set_message_id_parts("bad@left", "example.com").is_err());
+}
+
+#[test]
+fn test_received_headers() {
+    use super::Email;
+
+    let input = b"Received:from c.example.com by d.example.com; \
+                  Wed, 5 Jan 2015 15:13:09 +1300\r\n\
+                  Received:from b.example.com by c.example.com; \
+                  Wed, 5 Jan 2015 15:13:07 +1300\r\n\
+                  X-Scanned:yes\r\n\
+                  Return-Path:<me@example.com>\r\n\
+                  Received:from a.example.com by b.example.com; \
+                  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:me@example.com\r\n\
+                  \r\n\
+                  Hello";
+    let (email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.get_received().len(), 2);
+    let received: Vec<String> = email.received_headers().iter()
+        .map(|r| format!("{}", r)).collect();
+    assert_eq!(received.len(), 3);
+    assert!(received[0].starts_with("Received:from c.example.com"));
+    assert!(received[1].starts_with("Received:from b.example.com"));
+    assert!(received[2].starts_with("Received:from a.example.com"));
+
+    let email = Email::new("me@example.com", "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.received_headers().is_empty());
+}
+
+#[test]
+fn test_authentication_results() {
+    use super::Email;
+    use rfc5322::Parsable;
+    use rfc5322::authres::AuthenticationResults;
+
+    let (ar, rem) = AuthenticationResults::parse(
+        b" mx.example.org 1; spf=pass smtp.mailfrom=me@example.com;\r\n \
+          dkim=fail (bad signature) reason=\"body hash mismatch\" header.d=example.com\r\n \
+          header.s=sel1; dmarc=none (no policy) header.from=example.com").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(ar.authserv_id, "mx.example.org");
+    assert_eq!(ar.version, Some(1));
+    assert_eq!(ar.results.len(), 3);
+    let spf = ar.result("SPF").unwrap();
+    assert_eq!(spf.result, "pass");
+    assert_eq!(spf.property("smtp", "mailfrom"), Some("me@example.com"));
+    let dkim = ar.result("dkim").unwrap();
+    assert_eq!(dkim.result, "fail");
+    assert_eq!(dkim.reason, Some("body hash mismatch".to_owned()));
+    assert_eq!(dkim.property("header", "d"), Some("example.com"));
+    assert_eq!(dkim.property("header", "s"), Some("sel1"));
+    assert_eq!(ar.result("dmarc").unwrap().result, "none");
+    assert!(ar.result("arc").is_none());
+    assert_eq!(format!("{}", ar),
+               "mx.example.org 1; spf=pass smtp.mailfrom=me@example.com; \
+                dkim=fail reason=\"body hash mismatch\" header.d=example.com header.s=sel1; \
+                dmarc=none header.from=example.com");
+
+    let (ar, rem) = AuthenticationResults::parse(b"example.com; none").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert!(ar.results.is_empty());
+    assert_eq!(format!("{}", ar), "example.com; none");
+    assert!(AuthenticationResults::parse(b"example.com").is_err());
+    assert!(AuthenticationResults::parse(b"example.com; spf").is_err());
+
+    let input = b"Authentication-Results: mx.example.org; spf=pass smtp.mailfrom=example.com\r\n\
+                  Received:from a.example.com by mx.example.org; \
+                  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:me@example.com\r\n\
+                  Authentication-Results: relay.example.net; dkim=pass header.d=example.com\r\n\
+                  Authentication-Results: garbage\r\n\
+                  \r\n\
+                  Hello";
+    let (email, _) = Email::parse(input).unwrap();
+    let results = email.get_authentication_results();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].authserv_id, "mx.example.org");
+    assert_eq!(results[0].result("spf").unwrap().result, "pass");
+    assert_eq!(results[1].authserv_id, "relay.example.net");
+    assert_eq!(results[1].result("dkim").unwrap().property("header", "d"),
+               Some("example.com"));
+}
+
+#[test]
+fn test_optional_fields_named() {
+    use super::Email;
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.add_optional_field(("X-Spam-Status", "No")).unwrap();
+    email.add_optional_field(("X-Other", "1")).unwrap();
+    email.add_optional_field(("x-spam-status", "Yes")).unwrap();
+
+    let values: Vec<String> = email.optional_fields_named("X-SPAM-STATUS")
+        .map(|f| format!("{}", f.value)).collect();
+    assert_eq!(values, vec!["No", "Yes"]);
+    assert_eq!(email.optional_fields_named("X-Other").count(), 1);
+    assert_eq!(email.optional_fields_named("Subject").count(), 0);
+}
+
+#[test]
+fn test_references_folding() {
+    use super::Email;
+    use rfc5322::Parsable;
+    use rfc5322::headers::References;
+    use rfc5322::types::MsgId;
+
+    let ids: Vec<String> = (0..6)
+        .map(|i| format!("<20150105151305.{}.abcdefghij@mail.example.com>", i))
+        .collect();
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_references(&*ids.join("")).unwrap();
+    email.set_in_reply_to(&*ids[5]).unwrap();
+
+    let output = format!("{}", email.get_references().unwrap());
+    assert!(output.starts_with(&format!("References:{}\r\n {}\r\n {}\r\n",
+                                        ids[0], ids[1], ids[2])));
+    assert!(output.lines().all(|l| l.len() <= 78));
+    let (references, rem) = References::parse(output.as_bytes()).unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(references, email.get_references().unwrap());
+    let reparsed: Vec<String> = references.0.iter().map(|m| format!("<{}>", m.id())).collect();
+    assert_eq!(reparsed, ids);
+    let (email2, _) = Email::parse(email.as_bytes().as_slice()).unwrap();
+    assert_eq!(email2.get_references(), email.get_references());
+
+    // So do ids built from their parts
+    let parts = vec![MsgId::from_parts("a", "example.com").unwrap(),
+                     MsgId::from_parts("b", "example.com").unwrap()];
+    email.set_references(parts).unwrap();
+    assert_eq!(format!("{}", email.get_references().unwrap()),
+               "References:<a@example.com> <b@example.com>\r\n");
+    let (email2, _) = Email::parse(email.as_bytes().as_slice()).unwrap();
+    assert_eq!(email2.get_references(), email.get_references());
+
+    // Short ids share a line, and comments between ids are kept
+    email.set_references("<a@b> (first)<c@d>\r\n <e@f><g@h>").unwrap();
+    assert_eq!(format!("{}", email.get_references().unwrap()),
+               "References:<a@b> (first)<c@d> <e@f> <g@h>\r\n");
+
+    let (email2, _) = Email::parse(email.as_bytes().as_slice()).unwrap();
+    assert_eq!(email2.get_references(), email.get_references());
+    assert_eq!(format!("{}", email2.get_in_reply_to().unwrap()),
+               format!("In-Reply-To:{}\r\n", ids[5]));
+}
+
+#[test]
+fn test_headers_bytes() {
+    use super::Email;
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    email.set_subject("Hello").unwrap();
+    let header = "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:me@example.com\r\n\
+                  Subject:Hello\r\n";
+    assert_eq!(email.headers_bytes(), header.as_bytes().to_vec());
+
+    // The body does not affect the header, even if it is empty or looks
+    // like a header itself
+    email.set_body("").unwrap();
+    assert_eq!(email.headers_bytes(), header.as_bytes().to_vec());
+    email.set_body("\r\nX-Not:a header").unwrap();
+    assert_eq!(email.headers_bytes(), header.as_bytes().to_vec());
+    let bytes = email.as_bytes();
+    assert!(bytes.starts_with(&email.headers_bytes()));
+
+    // A verbatim parse keeps the original bytes of the header
+    let input = b"Date:  Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From: me@example.com\r\n\
+                  \r\n\
+                  Body";
+    let (email, _) = Email::parse_verbatim(input).unwrap();
+    assert_eq!(email.headers_bytes(), input[..input.len() - 6].to_vec());
+}
+
+#[test]
+fn test_raw_body_bytes() {
+    use super::Email;
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert_eq!(email.raw_body_bytes(), Vec::<u8>::new());
+    email.set_body("Hello\r\nWorld").unwrap();
+    assert_eq!(email.raw_body_bytes(), b"Hello\r\nWorld".to_vec());
+
+    // The body bytes kept by a preserving parse are returned as they were
+    let input = b"From:me@example.com\r\n\
+                  \r\n\
+                  Line one\n\
+                  Line two\r\n";
+    let email = Email::parse_preserving(input).unwrap();
+    assert_eq!(email.raw_body_bytes(), b"Line one\nLine two\r\n".to_vec());
+    assert!(email.as_bytes().ends_with(&email.raw_body_bytes()));
+}
+
+#[test]
+fn test_header_injection() {
+    use super::Email;
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(assert_match!(email.add_optional_field(("X", "a\r\nBcc: evil@x")),
+                          Err(ParseError::HeaderInjection(1))));
+    assert!(assert_match!(email.set_subject("Hi\nBcc: evil@x"),
+                          Err(ParseError::HeaderInjection(2))));
+    assert!(assert_match!(email.set_subject("Hi\r"),
+                          Err(ParseError::HeaderInjection(2))));
+    assert!(assert_match!(email.set_to("you@example.com\r\nBcc: evil@x"),
+                          Err(ParseError::HeaderInjection(15))));
+    assert!(assert_match!(email.add_to("you@example.com\r\n"),
+                          Err(ParseError::HeaderInjection(15))));
+    assert!(assert_match!(email.set_references("<a@b>\r\n\r\n <c@d>"),
+                          Err(ParseError::HeaderInjection(5))));
+    assert!(email.get_optional_fields().is_empty());
+    assert!(email.get_to().is_none());
+
+    // A fold is allowed
+    email.set_subject("A long\r\n subject").unwrap();
+    email.set_references("<a@b>\r\n <c@d>").unwrap();
+}
+
+#[test]
+fn test_content_language() {
+    use super::Email;
+    use rfc5322::types::LanguageTag;
+    use rfc5322::headers::ContentLanguage;
+
+    for tag in &["en", "en-US", "zh-Hant-TW", "sgn-BE-FR", "x-klingon", "i-default",
+                 "de-CH-1901"] {
+        let (t, rem) = LanguageTag::parse(tag.as_bytes()).unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(&*t.tag, *tag);
+    }
+    for garbage in &["e", "", "en--US", "en-", "-en", "englishlanguage",
+                     "123", "en-abcdefghi", "q-foo"] {
+        let t: Result<ContentLanguage, _> = ::TryFrom::try_from(*garbage);
+        assert!(t.is_err(), "{}", garbage);
+    }
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    assert!(email.get_content_language().is_empty());
+    email.set_content_language("en-US, fr").unwrap();
+    assert_eq!(email.get_content_language(), vec!["en-US", "fr"]);
+    assert_eq!(format!("{}", email.content_language_ref().unwrap()),
+               "Content-Language:en-US, fr\r\n");
+    assert!(email.set_content_language("en_US").is_err());
+
+    let input = b"Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                  From:me@example.com\r\n\
+                  Content-Language: de (German), en\r\n\
+                  \r\n\
+                  Hallo";
+    let (mut email, _) = Email::parse(input).unwrap();
+    assert_eq!(email.get_content_language(), vec!["de", "en"]);
+    assert_eq!(email.as_bytes(), input.to_vec());
+    email.clear_content_language();
+    assert!(email.content_language_ref().is_none());
+}
+
+#[test]
+fn test_set_text_body() {
+    use super::Email;
+    use rfc5322::headers::ContentType;
+    use rfc5322::multipart::RawPart;
+    use TryFrom;
+
+    let mut email = Email::new("me@example.com",
+                               "Wed, 5 Jan 2015 15:13:05 +1300").unwrap();
+    let parts = vec![
+        RawPart::new(b"Content-Type:text/plain\r\n".to_vec(), b"One".to_vec()),
+        RawPart::new(b"Content-Type:text/html\r\n".to_vec(), b"<p>One</p>".to_vec()),
+    ];
+    let content_type = ContentType::try_from("multipart/alternative").unwrap();
+    email.set_parts(parts, content_type).unwrap();
+    email.set_content_transfer_encoding("8bit").unwrap();
+    assert!(email.is_multipart());
+
+    email.set_text_body("Just text\nnow");
+    assert!(!email.is_multipart());
+    assert_eq!(format!("{}", email.get_content_type().unwrap()),
+               "Content-Type:text/plain; charset=utf-8\r\n");
+    assert_eq!(format!("{}", email.get_content_transfer_encoding().unwrap()),
+               "Content-Transfer-Encoding:7bit\r\n");
+    assert!(email.get_mime_version().is_some());
+    assert_eq!(email.get_body().unwrap().0, b"Just text\r\nnow".to_vec());
+    assert_eq!(email.get_optional_fields().len(), 0);
+    assert!(email.validate().is_ok());
+
+    // Text which cannot be sent as it is, is base64 encoded
+    email.set_text_body("Grüße\n");
+    assert_eq!(format!("{}", email.get_content_transfer_encoding().unwrap()),
+               "Content-Transfer-Encoding:base64\r\n");
+    assert_eq!(email.get_body_decoded().unwrap(), "Grüße\r\n".as_bytes().to_vec());
+    assert_eq!(format!("{}", email.get_content_type().unwrap()),
+               "Content-Type:text/plain; charset=utf-8\r\n");
+}
+
+#[test]
+fn test_minify() {
+    use super::Email;
+
+    let input = b"Date: Wed, 5 Jan 2015 15:13:05 +1300 (local time)\r\n\
+                  From: \"Smith, John\" (the boss) <john@example.com>\r\n\
+                  To: Jane   Doe <jane@example.com> (home),\r\n   \
+                  Undisclosed (hidden) recipients : ;,  bob@example.com (Bob)\r\n\
+                  Message-ID:   <1234@example.com>   (generated)\r\n\
+                  MIME-Version: 1.0 (produced by Mailer)\r\n\
+                  Content-Type: text/plain; (comment) charset=\"utf-8\"\r\n\
+                  Subject:  Hello   (not a comment)\r\n\
+                  X-Custom:  keep   this\r\n\
+                  \r\n\
+                  Body";
+    let (original, _) = Email::parse(input).unwrap();
+    let mut email = original.clone();
+    email.minify();
+
+    let output = email.as_bytes();
+    assert!(output.len() < original.as_bytes().len());
+    assert_eq!(String::from_utf8(email.headers_bytes()).unwrap(),
+               "Date:Wed, 5 Jan 2015 15:13:05 +1300\r\n\
+                From:\"Smith, John\"<john@example.com>\r\n\
+                To:Jane Doe<jane@example.com>,Undisclosed recipients:;,bob@example.com\r\n\
+                Message-ID:<1234@example.com>\r\n\
+                MIME-Version:1.0\r\n\
+                Content-Type:text/plain;charset=\"utf-8\"\r\n\
+                Subject: Hello (not a comment)\r\n\
+                X-Custom: keep this\r\n");
+
+    // The meaning is unchanged, other than the comments being gone
+    let (reparsed, rem) = Email::parse(&output).unwrap();
+    assert_eq!(rem.len(), 0);
+    let addresses = |email: &Email| -> Vec<String> {
+        email.from_addresses().iter().chain(email.to_addresses().iter())
+            .map(|a| format!("{}@{}", a.local_part, a.domain)).collect()
+    };
+    assert_eq!(addresses(&reparsed), addresses(&original));
+    assert_eq!(addresses(&reparsed).len(), 3);
+    assert_eq!(reparsed.to_addresses()[0].display_name, Some("Jane Doe".to_owned()));
+    assert_eq!(reparsed.get_date().unwrap().0.to_parts(),
+               original.get_date().unwrap().0.to_parts());
+    assert_eq!(reparsed.get_message_id().unwrap().0.id(),
+               original.get_message_id().unwrap().0.id());
+    assert_eq!(reparsed.get_content_type().unwrap().0.parameter("charset"),
+               Some("utf-8".to_owned()));
+    assert_eq!(reparsed.get_body(), original.get_body());
+
+    // Minifying again changes nothing
+    let mut again = reparsed.clone();
+    again.minify();
+    assert_eq!(again.as_bytes(), output);
+}